@@ -20,6 +20,30 @@ public class Core: ObservableObject {
     public init() {
         let shell = IOSShell()
         coreFfi = CoreFfi(shell)
+
+        // The core never reads the system clock, so tell it the time on launch
+        // and whenever the app comes back, along with the time zone stats count
+        // days in
+        tick()
+        update(.timeZone(TimeZone.current.identifier))
+        NotificationCenter.default.addObserver(
+            forName: UIApplication.willEnterForegroundNotification, object: nil, queue: .main
+        ) { [weak self] _ in
+            MainActor.assumeIsolated {
+                self?.tick()
+            }
+        }
+        NotificationCenter.default.addObserver(
+            forName: .NSSystemTimeZoneDidChange, object: nil, queue: .main
+        ) { [weak self] _ in
+            MainActor.assumeIsolated {
+                self?.update(.timeZone(TimeZone.current.identifier))
+            }
+        }
+    }
+
+    private func tick() {
+        update(.tick(ISO8601DateFormatter().string(from: Date())))
     }
 
     public var view: ViewModel {
@@ -0,0 +1,14 @@
+-- Track the open pause on the session itself
+ALTER TABLE sessions ADD COLUMN paused_at VARCHAR(255);
+ALTER TABLE sessions ADD COLUMN accumulated_seconds INTEGER;
+
+-- Completed pause intervals, excluded from the session duration
+CREATE TABLE session_pauses (
+    id SERIAL PRIMARY KEY,
+    session_id VARCHAR(255) NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    paused_at VARCHAR(255) NOT NULL,
+    resumed_at VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_session_pauses_session_id ON session_pauses(session_id);
@@ -0,0 +1,19 @@
+-- Create study_sessions table (time spent on a study within a practice session)
+CREATE TABLE study_sessions (
+    id VARCHAR(255) PRIMARY KEY,
+    session_id VARCHAR(255) NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    study_id VARCHAR(255) NOT NULL,
+    score INTEGER,
+    state VARCHAR(50) NOT NULL DEFAULT 'NotStarted',
+    start_time VARCHAR(255),
+    resumed_at VARCHAR(255),
+    end_time VARCHAR(255),
+    elapsed_seconds INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_study_sessions_session_id ON study_sessions(session_id);
+
+-- Track the study currently being worked on within a session
+ALTER TABLE sessions ADD COLUMN active_study_session_id VARCHAR(255);
@@ -0,0 +1,11 @@
+-- Responses to mutating requests, keyed by the client's Idempotency-Key header
+-- so a retried request replays the original response instead of re-applying it.
+-- A key is claimed before its request runs, the response being NULL until then.
+CREATE TABLE idempotency_keys (
+    key VARCHAR(255) PRIMARY KEY,
+    method VARCHAR(10) NOT NULL,
+    path VARCHAR(1024) NOT NULL,
+    status_code INTEGER,
+    response_body BYTEA,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
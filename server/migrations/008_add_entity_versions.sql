@@ -0,0 +1,5 @@
+-- Optimistic concurrency: every write bumps the version, and updates based on
+-- an older version are rejected
+ALTER TABLE goals ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE studies ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE sessions ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
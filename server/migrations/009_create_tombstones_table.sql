@@ -0,0 +1,12 @@
+-- Deleted entities, so clients syncing incrementally can drop their local copies
+CREATE TABLE tombstones (
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id VARCHAR(255) NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (entity_type, entity_id)
+);
+
+CREATE INDEX idx_tombstones_deleted_at ON tombstones(deleted_at);
+CREATE INDEX idx_goals_updated_at ON goals(updated_at);
+CREATE INDEX idx_studies_updated_at ON studies(updated_at);
+CREATE INDEX idx_sessions_updated_at ON sessions(updated_at);
@@ -0,0 +1,21 @@
+-- Accounts, with every goal, study and session owned by one of them
+CREATE TABLE users (
+    id VARCHAR(255) PRIMARY KEY,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    display_name VARCHAR(255),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Rows created before accounts existed have no owner until the server is
+-- started with LEGACY_DATA_OWNER naming the account that adopts them
+ALTER TABLE goals ADD COLUMN user_id VARCHAR(255) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE studies ADD COLUMN user_id VARCHAR(255) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE sessions ADD COLUMN user_id VARCHAR(255) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE tombstones ADD COLUMN user_id VARCHAR(255) REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE idempotency_keys ADD COLUMN user_id VARCHAR(255);
+
+CREATE INDEX idx_goals_user_id ON goals(user_id);
+CREATE INDEX idx_studies_user_id ON studies(user_id);
+CREATE INDEX idx_sessions_user_id ON sessions(user_id);
+CREATE INDEX idx_tombstones_user_id ON tombstones(user_id);
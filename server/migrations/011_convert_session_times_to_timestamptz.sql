@@ -0,0 +1,7 @@
+-- Session start and end times were stored as the RFC 3339 strings clients
+-- send. As timestamps they can be bucketed into days in the user's time zone.
+ALTER TABLE sessions
+    ALTER COLUMN start_time TYPE TIMESTAMPTZ USING NULLIF(start_time, '')::TIMESTAMPTZ,
+    ALTER COLUMN end_time TYPE TIMESTAMPTZ USING NULLIF(end_time, '')::TIMESTAMPTZ;
+
+CREATE INDEX idx_sessions_user_id_start_time ON sessions(user_id, start_time);
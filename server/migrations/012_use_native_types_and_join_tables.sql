@@ -0,0 +1,58 @@
+-- Goal dates are calendar days, sent as "2025-05-01"
+ALTER TABLE goals
+    ALTER COLUMN start_date TYPE DATE USING NULLIF(LEFT(start_date, 10), '')::DATE,
+    ALTER COLUMN target_date TYPE DATE USING NULLIF(LEFT(target_date, 10), '')::DATE;
+
+-- The remaining session times, stored like start_time and end_time
+ALTER TABLE sessions
+    ALTER COLUMN paused_at TYPE TIMESTAMPTZ USING NULLIF(paused_at, '')::TIMESTAMPTZ;
+
+ALTER TABLE session_pauses
+    ALTER COLUMN paused_at TYPE TIMESTAMPTZ USING paused_at::TIMESTAMPTZ,
+    ALTER COLUMN resumed_at TYPE TIMESTAMPTZ USING resumed_at::TIMESTAMPTZ;
+
+ALTER TABLE study_sessions
+    ALTER COLUMN start_time TYPE TIMESTAMPTZ USING NULLIF(start_time, '')::TIMESTAMPTZ,
+    ALTER COLUMN resumed_at TYPE TIMESTAMPTZ USING NULLIF(resumed_at, '')::TIMESTAMPTZ,
+    ALTER COLUMN end_time TYPE TIMESTAMPTZ USING NULLIF(end_time, '')::TIMESTAMPTZ;
+
+-- Studies a goal works through, in the order the goal lists them. Deleting
+-- either side removes the link.
+CREATE TABLE goal_studies (
+    goal_id VARCHAR(255) NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    study_id VARCHAR(255) NOT NULL REFERENCES studies(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (goal_id, study_id)
+);
+
+CREATE INDEX idx_goal_studies_study_id ON goal_studies(study_id);
+
+-- Goals a session was practice for, in the order the session lists them
+CREATE TABLE session_goals (
+    session_id VARCHAR(255) NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    goal_id VARCHAR(255) NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (session_id, goal_id)
+);
+
+CREATE INDEX idx_session_goals_goal_id ON session_goals(goal_id);
+
+-- Carry over links to entities that still exist and have the same owner
+INSERT INTO goal_studies (goal_id, study_id, position)
+SELECT DISTINCT ON (g.id, linked.study_id) g.id, linked.study_id, linked.position::INTEGER
+FROM goals g
+CROSS JOIN LATERAL jsonb_array_elements_text(g.study_ids::jsonb)
+    WITH ORDINALITY AS linked(study_id, position)
+JOIN studies s ON s.id = linked.study_id AND s.user_id IS NOT DISTINCT FROM g.user_id
+ORDER BY g.id, linked.study_id, linked.position;
+
+INSERT INTO session_goals (session_id, goal_id, position)
+SELECT DISTINCT ON (s.id, linked.goal_id) s.id, linked.goal_id, linked.position::INTEGER
+FROM sessions s
+CROSS JOIN LATERAL jsonb_array_elements_text(s.goal_ids::jsonb)
+    WITH ORDINALITY AS linked(goal_id, position)
+JOIN goals g ON g.id = linked.goal_id AND g.user_id IS NOT DISTINCT FROM s.user_id
+ORDER BY s.id, linked.goal_id, linked.position;
+
+ALTER TABLE goals DROP COLUMN study_ids;
+ALTER TABLE sessions DROP COLUMN goal_ids;
@@ -0,0 +1,11 @@
+-- Structured practice targets, e.g. 20 minutes a day or 3 sessions a week
+ALTER TABLE goals ADD COLUMN target_amount INTEGER;
+ALTER TABLE goals ADD COLUMN target_measure TEXT;
+ALTER TABLE goals ADD COLUMN target_period TEXT;
+
+ALTER TABLE goals ADD CONSTRAINT goals_practice_target_check CHECK (
+    (target_amount IS NULL AND target_measure IS NULL AND target_period IS NULL)
+    OR (target_amount > 0
+        AND target_measure IN ('Minutes', 'Sessions')
+        AND target_period IN ('Day', 'Week', 'Total'))
+);
@@ -0,0 +1,8 @@
+-- Tempo reached per study session, for a tempo history per study
+ALTER TABLE study_sessions ADD COLUMN tempo INTEGER;
+ALTER TABLE study_sessions ADD COLUMN clean BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX idx_study_sessions_study_id_tempo ON study_sessions(study_id) WHERE tempo IS NOT NULL;
+
+-- Clean plays at the tempo target after which a goal completes itself
+ALTER TABLE goals ADD COLUMN clean_reps_to_complete INTEGER;
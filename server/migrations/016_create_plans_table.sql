@@ -0,0 +1,48 @@
+-- Recurring practice plans, which schedule sessions on some days of every week
+CREATE TABLE plans (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    intention TEXT NOT NULL DEFAULT '',
+    weekdays TEXT[] NOT NULL DEFAULT '{}',
+    time_of_day TIME NOT NULL, -- local to time_zone, so it stays put when the clocks change
+    time_zone TEXT NOT NULL DEFAULT 'UTC',
+    planned_minutes INTEGER NOT NULL CHECK (planned_minutes > 0),
+    start_date DATE NOT NULL,
+    end_date DATE,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_plans_user_id ON plans(user_id);
+CREATE INDEX idx_plans_updated_at ON plans(updated_at);
+
+-- Goals and studies a plan's sessions are for, in the order the plan lists them
+CREATE TABLE plan_goals (
+    plan_id VARCHAR(255) NOT NULL REFERENCES plans(id) ON DELETE CASCADE,
+    goal_id VARCHAR(255) NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (plan_id, goal_id)
+);
+
+CREATE INDEX idx_plan_goals_goal_id ON plan_goals(goal_id);
+
+CREATE TABLE plan_studies (
+    plan_id VARCHAR(255) NOT NULL REFERENCES plans(id) ON DELETE CASCADE,
+    study_id VARCHAR(255) NOT NULL REFERENCES studies(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (plan_id, study_id)
+);
+
+CREATE INDEX idx_plan_studies_study_id ON plan_studies(study_id);
+
+-- The plan occurrence a session was scheduled for. Sessions outlive their plan.
+ALTER TABLE sessions ADD COLUMN plan_id VARCHAR(255) REFERENCES plans(id) ON DELETE SET NULL;
+ALTER TABLE sessions ADD COLUMN scheduled_for TIMESTAMPTZ;
+
+CREATE INDEX idx_sessions_plan_id ON sessions(plan_id);
+
+-- A plan occurrence has at most one session, however many devices schedule it
+CREATE UNIQUE INDEX idx_sessions_plan_occurrence ON sessions(plan_id, scheduled_for)
+    WHERE plan_id IS NOT NULL;
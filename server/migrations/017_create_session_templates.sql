@@ -0,0 +1,37 @@
+-- Session templates ("routines"): an intention, goals and ordered study blocks
+CREATE TABLE session_templates (
+    id VARCHAR(255) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    intention TEXT NOT NULL DEFAULT '',
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_session_templates_user_id ON session_templates(user_id);
+CREATE INDEX idx_session_templates_updated_at ON session_templates(updated_at);
+
+CREATE TABLE template_goals (
+    template_id VARCHAR(255) NOT NULL REFERENCES session_templates(id) ON DELETE CASCADE,
+    goal_id VARCHAR(255) NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (template_id, goal_id)
+);
+
+CREATE INDEX idx_template_goals_goal_id ON template_goals(goal_id);
+
+-- A study may appear in more than one block, so blocks are keyed by position
+CREATE TABLE template_blocks (
+    template_id VARCHAR(255) NOT NULL REFERENCES session_templates(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    study_id VARCHAR(255) NOT NULL REFERENCES studies(id) ON DELETE CASCADE,
+    planned_minutes INTEGER NOT NULL CHECK (planned_minutes > 0),
+    PRIMARY KEY (template_id, position)
+);
+
+CREATE INDEX idx_template_blocks_study_id ON template_blocks(study_id);
+
+-- Study sessions keep the order and planned time of the blocks they came from
+ALTER TABLE study_sessions ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE study_sessions ADD COLUMN planned_minutes INTEGER CHECK (planned_minutes > 0);
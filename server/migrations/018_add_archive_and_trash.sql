@@ -0,0 +1,12 @@
+-- Archived entities are hidden from the default lists but kept. Deleted ones
+-- wait in the trash, hidden everywhere, until purged after a retention period.
+ALTER TABLE goals ADD COLUMN archived_at TIMESTAMPTZ;
+ALTER TABLE goals ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE studies ADD COLUMN archived_at TIMESTAMPTZ;
+ALTER TABLE studies ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD COLUMN archived_at TIMESTAMPTZ;
+ALTER TABLE sessions ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_goals_deleted_at ON goals(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_studies_deleted_at ON studies(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX idx_sessions_deleted_at ON sessions(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,36 @@
+-- The change feed goes by the transaction that last wrote a row rather than
+-- its timestamp. A cursor is the oldest transaction still running when it was
+-- handed out, so a row whose transaction commits late can't fall behind it.
+CREATE FUNCTION stamp_change_xid() RETURNS trigger AS $$
+BEGIN
+    NEW.change_xid := pg_current_xact_id()::text::bigint;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE goals ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE studies ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE sessions ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE plans ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE session_templates ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE tombstones ADD COLUMN change_xid BIGINT NOT NULL DEFAULT 0;
+
+CREATE TRIGGER goals_change_xid BEFORE INSERT OR UPDATE ON goals
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+CREATE TRIGGER studies_change_xid BEFORE INSERT OR UPDATE ON studies
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+CREATE TRIGGER sessions_change_xid BEFORE INSERT OR UPDATE ON sessions
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+CREATE TRIGGER plans_change_xid BEFORE INSERT OR UPDATE ON plans
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+CREATE TRIGGER session_templates_change_xid BEFORE INSERT OR UPDATE ON session_templates
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+CREATE TRIGGER tombstones_change_xid BEFORE INSERT OR UPDATE ON tombstones
+    FOR EACH ROW EXECUTE FUNCTION stamp_change_xid();
+
+CREATE INDEX idx_goals_change_xid ON goals(user_id, change_xid);
+CREATE INDEX idx_studies_change_xid ON studies(user_id, change_xid);
+CREATE INDEX idx_sessions_change_xid ON sessions(user_id, change_xid);
+CREATE INDEX idx_plans_change_xid ON plans(user_id, change_xid);
+CREATE INDEX idx_session_templates_change_xid ON session_templates(user_id, change_xid);
+CREATE INDEX idx_tombstones_change_xid ON tombstones(user_id, change_xid);
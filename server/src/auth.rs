@@ -0,0 +1,581 @@
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts},
+    response::Json,
+    routing::{get, post},
+    Extension, Router,
+};
+use base64::{
+    engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::Utc;
+use ring::{
+    hmac, pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+use shared::{AuthResponse, LogInRequest, SignUpRequest, User};
+use sqlx::FromRow;
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, LazyLock},
+};
+
+use crate::{
+    error::field_error,
+    repository::{Database, RepositoryError, RepositoryResult},
+    ApiError,
+};
+
+const TOKEN_LIFETIME_SECONDS: i64 = 30 * 24 * 60 * 60;
+const PASSWORD_ITERATIONS: u32 = 100_000;
+const MIN_PASSWORD_LENGTH: usize = 8;
+const SALT_LENGTH: usize = 16;
+
+// *************
+// TOKENS
+// *************
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum TokenError {
+    #[error("Malformed token")]
+    Malformed,
+    #[error("Invalid token signature")]
+    BadSignature,
+    #[error("Token has expired")]
+    Expired,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Claims {
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Signs and verifies bearer tokens: HS256 JWTs keyed with the server's secret
+pub struct TokenKeys {
+    key: hmac::Key,
+}
+
+impl TokenKeys {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret),
+        }
+    }
+
+    pub fn issue(&self, user_id: &str, now: i64) -> String {
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+        let claims = Claims {
+            sub: user_id.to_string(),
+            iat: now,
+            exp: now + TOKEN_LIFETIME_SECONDS,
+        };
+        let claims =
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).expect("claims serialize to JSON"));
+
+        let signing_input = format!("{header}.{claims}");
+        let signature = hmac::sign(&self.key, signing_input.as_bytes());
+        format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature.as_ref())
+        )
+    }
+
+    pub fn verify(&self, token: &str, now: i64) -> Result<Claims, TokenError> {
+        let (signing_input, signature) = token.rsplit_once('.').ok_or(TokenError::Malformed)?;
+        let (_header, claims) = signing_input.split_once('.').ok_or(TokenError::Malformed)?;
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| TokenError::Malformed)?;
+        hmac::verify(&self.key, signing_input.as_bytes(), &signature)
+            .map_err(|_| TokenError::BadSignature)?;
+
+        let claims = URL_SAFE_NO_PAD
+            .decode(claims)
+            .map_err(|_| TokenError::Malformed)?;
+        let claims: Claims = serde_json::from_slice(&claims).map_err(|_| TokenError::Malformed)?;
+        if claims.exp <= now {
+            return Err(TokenError::Expired);
+        }
+        Ok(claims)
+    }
+}
+
+// *************
+// PASSWORDS
+// *************
+
+// Stored as pbkdf2-sha256$<iterations>$<salt>$<hash>, so the work factor can
+// be raised later without invalidating existing passwords
+fn hash_password_with(password: &str, iterations: u32) -> String {
+    let mut salt = [0u8; SALT_LENGTH];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .expect("system random number generator is available");
+
+    let mut hash = [0u8; ring::digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(iterations).expect("iterations are non-zero"),
+        &salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+
+    format!(
+        "pbkdf2-sha256${iterations}${}${}",
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash)
+    )
+}
+
+pub fn hash_password(password: &str) -> String {
+    hash_password_with(password, PASSWORD_ITERATIONS)
+}
+
+// Checked against when no account has the email, so a log in takes as long
+// whether or not the email has an account
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| hash_password(""));
+
+pub fn verify_password(password: &str, stored: &str) -> bool {
+    let mut parts = stored.split('$');
+    let (Some("pbkdf2-sha256"), Some(iterations), Some(salt), Some(hash), None) = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    ) else {
+        return false;
+    };
+    let (Some(iterations), Ok(salt), Ok(hash)) = (
+        iterations.parse().ok().and_then(NonZeroU32::new),
+        STANDARD_NO_PAD.decode(salt),
+        STANDARD_NO_PAD.decode(hash),
+    ) else {
+        return false;
+    };
+
+    pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        &salt,
+        password.as_bytes(),
+        &hash,
+    )
+    .is_ok()
+}
+
+// *************
+// EXTRACTOR
+// *************
+
+/// The signed in user, taken from the request's bearer token
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthUser(pub String);
+
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Already checked by the layer in front of the /api routes
+        if let Some(user) = parts.extensions.get::<AuthUser>() {
+            return Ok(user.clone());
+        }
+
+        let keys = parts
+            .extensions
+            .get::<Arc<TokenKeys>>()
+            .cloned()
+            .ok_or_else(|| ApiError::internal("Token keys are not configured"))?;
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::unauthorized("Missing bearer token"))?;
+
+        let claims = keys
+            .verify(token, Utc::now().timestamp())
+            .map_err(|e| ApiError::unauthorized(e.to_string()))?;
+
+        let user = AuthUser(claims.sub);
+        parts.extensions.insert(user.clone());
+        Ok(user)
+    }
+}
+
+// *************
+// USERS
+// *************
+
+// Database row struct
+#[derive(FromRow)]
+pub struct UserRow {
+    pub id: String,
+    pub email: String,
+    pub password_hash: String,
+    pub display_name: Option<String>,
+}
+
+impl UserRow {
+    pub fn into_user(self) -> User {
+        User {
+            id: self.id,
+            email: self.email,
+            display_name: self.display_name,
+        }
+    }
+}
+
+// Simple User repository - no traits, just methods
+pub struct UserRepository {
+    db: Database,
+}
+
+impl UserRepository {
+    pub fn new(pool: crate::repository::DbPool) -> Self {
+        Self {
+            db: Database::new(pool),
+        }
+    }
+
+    pub async fn create(&self, user: &User, password_hash: &str) -> RepositoryResult<()> {
+        sqlx::query(
+            "INSERT INTO users (id, email, password_hash, display_name) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&user.id)
+        .bind(&user.email)
+        .bind(password_hash)
+        .bind(&user.display_name)
+        .execute(&self.db.pool)
+        .await
+        .map_err(|e| match e {
+            // Two sign ups with one email can both get past the handler's check
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                RepositoryError::Conflict("An account with this email already exists".to_string())
+            }
+            e => RepositoryError::Database(e),
+        })?;
+
+        Ok(())
+    }
+
+    /// Gives the rows created before accounts existed to the account with
+    /// this email, returning how many there were
+    pub async fn adopt_legacy_data(&self, email: &str) -> RepositoryResult<u64> {
+        let email = normalize_email(email);
+        let Some(user) = self.find_by_email(&email).await? else {
+            return Err(RepositoryError::NotFound(format!("No account for {email}")));
+        };
+
+        let mut tx = self.db.pool.begin().await?;
+        let mut adopted = 0;
+        for table in ["goals", "studies", "sessions", "tombstones"] {
+            adopted += sqlx::query(&format!(
+                "UPDATE {table} SET user_id = $1 WHERE user_id IS NULL"
+            ))
+            .bind(&user.id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(adopted)
+    }
+
+    pub async fn find_by_email(&self, email: &str) -> RepositoryResult<Option<UserRow>> {
+        let row = sqlx::query_as!(
+            UserRow,
+            "SELECT id, email, password_hash, display_name FROM users WHERE email = $1",
+            email
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    pub async fn find_by_id(&self, id: &str) -> RepositoryResult<Option<User>> {
+        let row = sqlx::query_as!(
+            UserRow,
+            "SELECT id, email, password_hash, display_name FROM users WHERE id = $1",
+            id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        Ok(row.map(UserRow::into_user))
+    }
+}
+
+fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+fn validate_sign_up(req: &SignUpRequest) -> Result<(), ApiError> {
+    let mut details = Vec::new();
+    if !req.email.contains('@') {
+        details.push(field_error("email", "A valid email address is required"));
+    }
+    if req.password.chars().count() < MIN_PASSWORD_LENGTH {
+        details.push(field_error(
+            "password",
+            "Password must be at least 8 characters",
+        ));
+    }
+
+    if details.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::validation("Invalid sign up", details))
+    }
+}
+
+// HTTP Handlers
+async fn sign_up(
+    State(user_repo): State<Arc<UserRepository>>,
+    Extension(keys): Extension<Arc<TokenKeys>>,
+    Json(mut req): Json<SignUpRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    req.email = normalize_email(&req.email);
+    validate_sign_up(&req)?;
+
+    let existing = user_repo.find_by_email(&req.email).await?;
+    if existing.is_some() {
+        return Err(ApiError::conflict(
+            "An account with this email already exists",
+        ));
+    }
+
+    let user = User {
+        id: shared::generate_id(),
+        email: req.email,
+        display_name: req.display_name,
+    };
+    user_repo
+        .create(&user, &hash_password(&req.password))
+        .await?;
+
+    Ok(Json(AuthResponse {
+        token: keys.issue(&user.id, Utc::now().timestamp()),
+        user,
+    }))
+}
+
+async fn log_in(
+    State(user_repo): State<Arc<UserRepository>>,
+    Extension(keys): Extension<Arc<TokenKeys>>,
+    Json(req): Json<LogInRequest>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    let row = user_repo
+        .find_by_email(&normalize_email(&req.email))
+        .await?;
+
+    let password_hash = row
+        .as_ref()
+        .map_or(DUMMY_PASSWORD_HASH.as_str(), |row| &row.password_hash);
+    let verified = verify_password(&req.password, password_hash);
+
+    match row {
+        Some(row) if verified => {
+            let user = row.into_user();
+            Ok(Json(AuthResponse {
+                token: keys.issue(&user.id, Utc::now().timestamp()),
+                user,
+            }))
+        }
+        _ => Err(ApiError::unauthorized("Invalid email or password")),
+    }
+}
+
+async fn get_me(
+    State(user_repo): State<Arc<UserRepository>>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<User>, ApiError> {
+    let user = user_repo.find_by_id(&user_id).await?;
+
+    user.map(Json)
+        .ok_or_else(|| ApiError::unauthorized("Account no longer exists"))
+}
+
+pub fn routes() -> Router<Arc<UserRepository>> {
+    Router::new()
+        .route("/signup", post(sign_up))
+        .route("/login", post(log_in))
+}
+
+pub fn me_routes() -> Router<Arc<UserRepository>> {
+    Router::new().route("/me", get(get_me))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    const NOW: i64 = 1_750_000_000;
+
+    #[test]
+    fn test_token_round_trip() {
+        let keys = TokenKeys::new(b"secret");
+        let token = keys.issue("user-1", NOW);
+
+        let claims = keys.verify(&token, NOW + 60).unwrap();
+        assert_eq!(claims.sub, "user-1");
+        assert_eq!(claims.exp, NOW + TOKEN_LIFETIME_SECONDS);
+    }
+
+    #[test]
+    fn test_token_rejects_other_keys_and_tampering() {
+        let token = TokenKeys::new(b"secret").issue("user-1", NOW);
+
+        assert_eq!(
+            TokenKeys::new(b"another secret").verify(&token, NOW),
+            Err(TokenError::BadSignature)
+        );
+
+        let (header, rest) = token.split_once('.').unwrap();
+        let (_, signature) = rest.split_once('.').unwrap();
+        let forged_claims = URL_SAFE_NO_PAD.encode(br#"{"sub":"user-2","iat":0,"exp":9999999999}"#);
+        let forged = format!("{header}.{forged_claims}.{signature}");
+        assert_eq!(
+            TokenKeys::new(b"secret").verify(&forged, NOW),
+            Err(TokenError::BadSignature)
+        );
+        assert_eq!(
+            TokenKeys::new(b"secret").verify("not a token", NOW),
+            Err(TokenError::Malformed)
+        );
+    }
+
+    #[test]
+    fn test_token_expires() {
+        let keys = TokenKeys::new(b"secret");
+        let token = keys.issue("user-1", NOW);
+
+        assert_eq!(
+            keys.verify(&token, NOW + TOKEN_LIFETIME_SECONDS),
+            Err(TokenError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_password_hash_round_trip() {
+        // Few iterations keep the test fast, the format records the count
+        let stored = hash_password_with("correct horse", 10);
+
+        assert!(stored.starts_with("pbkdf2-sha256$10$"));
+        assert!(verify_password("correct horse", &stored));
+        assert!(!verify_password("wrong horse", &stored));
+        assert!(!verify_password("correct horse", "plaintext"));
+        assert_ne!(stored, hash_password_with("correct horse", 10));
+    }
+
+    #[test]
+    fn test_dummy_hash_costs_as_much_as_a_real_one() {
+        let iterations = format!("pbkdf2-sha256${PASSWORD_ITERATIONS}$");
+        assert!(DUMMY_PASSWORD_HASH.starts_with(&iterations));
+    }
+
+    #[test]
+    fn test_sign_up_validation() {
+        let mut req = SignUpRequest {
+            email: "player@example.com".to_string(),
+            password: "long enough".to_string(),
+            display_name: None,
+        };
+        assert!(validate_sign_up(&req).is_ok());
+
+        req.password = "short".to_string();
+        let error = validate_sign_up(&req).unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.details[0].field, "password");
+
+        // Every problem is reported at once
+        req.email = "not an email".to_string();
+        let fields: Vec<String> = validate_sign_up(&req)
+            .unwrap_err()
+            .details
+            .into_iter()
+            .map(|detail| detail.field)
+            .collect();
+        assert_eq!(fields, vec!["email", "password"]);
+        assert_eq!(
+            normalize_email("  Player@Example.COM "),
+            "player@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_up_with_a_taken_email_conflicts() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let user_repo = UserRepository::new(pool);
+        let email = format!("{}@example.com", shared::generate_id());
+        let user = |email: &str| User {
+            id: shared::generate_id(),
+            email: email.to_string(),
+            display_name: None,
+        };
+        user_repo.create(&user(&email), "hash").await.unwrap();
+
+        let error = user_repo.create(&user(&email), "hash").await.unwrap_err();
+        assert!(matches!(error, RepositoryError::Conflict(_)));
+        let error = ApiError::from(error);
+        assert_eq!(error.status, StatusCode::CONFLICT);
+        assert_eq!(error.code, shared::ApiErrorCode::Conflict);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_data_goes_to_the_named_account_only() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let user_repo = UserRepository::new(pool.clone());
+        let goal_repo = crate::goals::GoalRepository::new(pool.clone());
+        let owner = crate::repository::create_test_user(&pool).await;
+        let goal = shared::PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+        goal_repo.create(&owner, &goal).await.unwrap();
+        sqlx::query("UPDATE goals SET user_id = NULL WHERE id = $1")
+            .bind(&goal.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Signing up doesn't take it
+        let email = format!("{}@example.com", shared::generate_id());
+        let user = User {
+            id: shared::generate_id(),
+            email: email.clone(),
+            display_name: None,
+        };
+        user_repo.create(&user, "hash").await.unwrap();
+        assert!(goal_repo
+            .find_by_id(&user.id, &goal.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(user_repo.adopt_legacy_data(&email).await.unwrap() >= 1);
+        assert!(goal_repo
+            .find_by_id(&user.id, &goal.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(matches!(
+            user_repo.adopt_legacy_data("nobody@example.com").await,
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+}
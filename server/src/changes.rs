@@ -0,0 +1,234 @@
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use shared::{Changes, Tombstone};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    goals::GoalRepository,
+    plans::PlanRepository,
+    repository::{entity_type_from_name, format_timestamp, Database, RepositoryResult},
+    sessions::SessionRepository,
+    studies::StudyRepository,
+    templates::TemplateRepository,
+    ApiError,
+};
+
+// Database row struct
+#[derive(FromRow)]
+pub struct TombstoneRow {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub deleted_at: NaiveDateTime,
+}
+
+impl TombstoneRow {
+    pub fn into_tombstone(self) -> RepositoryResult<Tombstone> {
+        Ok(Tombstone {
+            kind: entity_type_from_name(&self.entity_type)?,
+            id: self.entity_id,
+            deleted_at: format_timestamp(self.deleted_at),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    // Cursor from a previous response, omitted for a full pull
+    pub since: Option<String>,
+}
+
+// Change feed across the other repositories - no traits, just methods
+pub struct ChangeRepository {
+    db: Database,
+    goals: Arc<GoalRepository>,
+    studies: Arc<StudyRepository>,
+    sessions: Arc<SessionRepository>,
+    plans: Arc<PlanRepository>,
+    templates: Arc<TemplateRepository>,
+}
+
+impl ChangeRepository {
+    pub fn new(
+        pool: crate::repository::DbPool,
+        goals: Arc<GoalRepository>,
+        studies: Arc<StudyRepository>,
+        sessions: Arc<SessionRepository>,
+        plans: Arc<PlanRepository>,
+        templates: Arc<TemplateRepository>,
+    ) -> Self {
+        Self {
+            db: Database::new(pool),
+            goals,
+            studies,
+            sessions,
+            plans,
+            templates,
+        }
+    }
+
+    // The oldest transaction still running. Every write from before it has
+    // committed, so reads from here on see it, while one from a transaction
+    // still running is stamped with it or a later one however long it takes.
+    // Rows written since by transactions that committed early are sent again
+    // by the next pull, which is harmless because merges go by version.
+    async fn cursor(&self) -> RepositoryResult<i64> {
+        let cursor: i64 =
+            sqlx::query_scalar("SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint")
+                .fetch_one(&self.db.pool)
+                .await?;
+        Ok(cursor)
+    }
+
+    pub async fn find_tombstones_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<Tombstone>> {
+        let rows = sqlx::query_as!(
+            TombstoneRow,
+            "SELECT entity_type, entity_id, deleted_at FROM tombstones
+             WHERE user_id = $1 AND change_xid >= $2 ORDER BY deleted_at",
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        rows.into_iter().map(TombstoneRow::into_tombstone).collect()
+    }
+
+    pub async fn changes_since(
+        &self,
+        user_id: &str,
+        since: Option<i64>,
+    ) -> RepositoryResult<Changes> {
+        // Taken before reading, so anything written during the reads is
+        // picked up again by the next pull
+        let cursor = self.cursor().await?.to_string();
+
+        let Some(since) = since else {
+            return Ok(Changes {
+                goals: self.goals.find_all(user_id).await?,
+                studies: self.studies.find_all(user_id).await?,
+                sessions: self.sessions.find_all(user_id).await?,
+                plans: self.plans.find_all(user_id).await?,
+                templates: self.templates.find_all(user_id).await?,
+                deleted: Vec::new(),
+                cursor,
+            });
+        };
+
+        Ok(Changes {
+            goals: self.goals.find_changed_since(user_id, since).await?,
+            studies: self.studies.find_changed_since(user_id, since).await?,
+            sessions: self.sessions.find_changed_since(user_id, since).await?,
+            plans: self.plans.find_changed_since(user_id, since).await?,
+            templates: self.templates.find_changed_since(user_id, since).await?,
+            deleted: self.find_tombstones_since(user_id, since).await?,
+            cursor,
+        })
+    }
+}
+
+fn parse_cursor(since: &str) -> Result<i64, ApiError> {
+    since
+        .parse()
+        .map_err(|e| ApiError::bad_request(format!("Invalid since cursor: {e}")))
+}
+
+// HTTP Handlers
+async fn get_changes(
+    State(change_repo): State<Arc<ChangeRepository>>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ChangesQuery>,
+) -> Result<Json<Changes>, ApiError> {
+    let since = query.since.as_deref().map(parse_cursor).transpose()?;
+
+    let changes = change_repo.changes_since(&user_id, since).await?;
+
+    Ok(Json(changes))
+}
+
+pub fn routes() -> Router<Arc<ChangeRepository>> {
+    Router::new().route("/changes", get(get_changes))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use chrono::NaiveDate;
+    use shared::EntityKind;
+
+    #[test]
+    fn test_tombstone_row_conversion() {
+        let row = TombstoneRow {
+            entity_type: "Session".to_string(),
+            entity_id: "session-1".to_string(),
+            deleted_at: NaiveDate::from_ymd_opt(2025, 1, 1)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+        };
+
+        let tombstone = row.into_tombstone().unwrap();
+        assert_eq!(tombstone.kind, EntityKind::Session);
+        assert_eq!(tombstone.id, "session-1");
+        assert_eq!(tombstone.deleted_at, "2025-01-01T10:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_cursor_reads_transaction_ids() {
+        assert_eq!(parse_cursor("1234").unwrap(), 1234);
+    }
+
+    #[test]
+    fn test_parse_cursor_rejects_garbage() {
+        let error = parse_cursor("yesterday").unwrap_err();
+        assert_eq!(error.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_change_committed_after_a_cursor_is_pulled_next_time() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let goal_repo = Arc::new(GoalRepository::new(pool.clone()));
+        let change_repo = ChangeRepository::new(
+            pool.clone(),
+            goal_repo.clone(),
+            Arc::new(StudyRepository::new(pool.clone())),
+            Arc::new(SessionRepository::new(pool.clone())),
+            Arc::new(PlanRepository::new(pool.clone())),
+            Arc::new(TemplateRepository::new(pool.clone())),
+        );
+        let user = crate::repository::create_test_user(&pool).await;
+
+        // A slow write starts before a pull and commits after it
+        let goal = shared::PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+        let mut tx = pool.begin().await.unwrap();
+        GoalRepository::create_in(&mut tx, &user, &goal)
+            .await
+            .unwrap();
+        let pulled = change_repo.changes_since(&user, None).await.unwrap();
+        assert!(pulled.goals.is_empty());
+        tx.commit().await.unwrap();
+
+        let since = parse_cursor(&pulled.cursor).unwrap();
+        let pulled = change_repo.changes_since(&user, Some(since)).await.unwrap();
+        assert_eq!(pulled.goals.len(), 1);
+        assert_eq!(pulled.goals[0].id, goal.id);
+    }
+}
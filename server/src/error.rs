@@ -0,0 +1,244 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use shared::{ApiErrorCode, FieldError, GoalError, SessionError};
+
+use crate::repository::RepositoryError;
+
+/// Error response shared by every handler: the status, a machine-readable
+/// code, a message for people and, for validation failures, the fields at fault
+#[derive(Debug, Serialize)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: ApiErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+    // Boxed to keep the error small, it is returned by every handler
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_error: Option<Box<SessionError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goal_error: Option<Box<GoalError>>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: ApiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: Vec::new(),
+            session_error: None,
+            goal_error: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, ApiErrorCode::BadRequest, message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Unauthorized,
+            message,
+        )
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, ApiErrorCode::NotFound, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, ApiErrorCode::Conflict, message)
+    }
+
+    pub fn validation(message: impl Into<String>, details: Vec<FieldError>) -> Self {
+        Self {
+            details,
+            ..Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ApiErrorCode::ValidationFailed,
+                message,
+            )
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ApiErrorCode::Internal,
+            message,
+        )
+    }
+}
+
+impl From<SessionError> for ApiError {
+    fn from(error: SessionError) -> Self {
+        Self {
+            session_error: Some(Box::new(error.clone())),
+            ..Self::new(
+                StatusCode::CONFLICT,
+                ApiErrorCode::InvalidTransition,
+                error.to_string(),
+            )
+        }
+    }
+}
+
+impl From<GoalError> for ApiError {
+    fn from(error: GoalError) -> Self {
+        Self {
+            goal_error: Some(Box::new(error.clone())),
+            ..Self::new(
+                StatusCode::CONFLICT,
+                ApiErrorCode::InvalidTransition,
+                error.to_string(),
+            )
+        }
+    }
+}
+
+pub fn field_error(field: &str, message: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+impl From<RepositoryError> for ApiError {
+    fn from(err: RepositoryError) -> Self {
+        let code = match err {
+            RepositoryError::InvalidTransition(error) => return error.into(),
+            RepositoryError::NotFound(_) | RepositoryError::Database(sqlx::Error::RowNotFound) => {
+                ApiErrorCode::NotFound
+            }
+            RepositoryError::ValidationError(_) => ApiErrorCode::ValidationFailed,
+            RepositoryError::VersionConflict(_) => ApiErrorCode::VersionConflict,
+            RepositoryError::Referenced(_) | RepositoryError::Conflict(_) => ApiErrorCode::Conflict,
+            RepositoryError::Database(_) | RepositoryError::Serialization(_) => {
+                ApiErrorCode::Internal
+            }
+        };
+        Self::new(err.status_code(), code, err.to_string())
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(err: sqlx::Error) -> Self {
+        RepositoryError::from(err).into()
+    }
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repository_errors_map_to_statuses() {
+        let cases = [
+            (
+                RepositoryError::NotFound("goals with id 1".to_string()),
+                StatusCode::NOT_FOUND,
+                ApiErrorCode::NotFound,
+            ),
+            (
+                RepositoryError::ValidationError("Unknown entity type".to_string()),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ApiErrorCode::ValidationFailed,
+            ),
+            (
+                RepositoryError::VersionConflict("goals 1 is at version 3".to_string()),
+                StatusCode::CONFLICT,
+                ApiErrorCode::VersionConflict,
+            ),
+            (
+                RepositoryError::Referenced("Goal is used by 14 sessions".to_string()),
+                StatusCode::CONFLICT,
+                ApiErrorCode::Conflict,
+            ),
+            (
+                RepositoryError::Database(sqlx::Error::PoolTimedOut),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiErrorCode::Internal,
+            ),
+        ];
+
+        for (err, status, code) in cases {
+            let api_error = ApiError::from(err);
+            assert_eq!(api_error.status, status);
+            assert_eq!(api_error.code, code);
+        }
+    }
+
+    #[test]
+    fn test_error_body_round_trips_through_shared_type() {
+        let api_error = ApiError::validation(
+            "Invalid sign up",
+            vec![field_error(
+                "password",
+                "Password must be at least 8 characters",
+            )],
+        );
+
+        let json = serde_json::to_string(&api_error).unwrap();
+        let body: shared::ApiErrorBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(body.code, ApiErrorCode::ValidationFailed);
+        assert_eq!(body.details[0].field, "password");
+
+        // No status in the body and no details when there are none
+        let json = serde_json::to_value(ApiError::not_found("Goal not found")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "code": "not_found", "message": "Goal not found" })
+        );
+    }
+
+    #[test]
+    fn test_invalid_transition_carries_session_error() {
+        let api_error = ApiError::from(RepositoryError::InvalidTransition(
+            SessionError::AnotherSessionStarted,
+        ));
+        assert_eq!(api_error.status, StatusCode::CONFLICT);
+        assert_eq!(api_error.code, ApiErrorCode::InvalidTransition);
+
+        let json = serde_json::to_string(&api_error).unwrap();
+        let body: shared::ApiErrorBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            shared::AppError::from(body),
+            shared::AppError::Session(SessionError::AnotherSessionStarted)
+        );
+    }
+
+    #[test]
+    fn test_goal_transition_decodes_to_goal_error() {
+        let api_error = ApiError::from(GoalError::AlreadyCompleted);
+        assert_eq!(api_error.status, StatusCode::CONFLICT);
+
+        let json = serde_json::to_string(&api_error).unwrap();
+        let body: shared::ApiErrorBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            shared::AppError::from(body),
+            shared::AppError::Goal(GoalError::AlreadyCompleted)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_into_response_uses_status() {
+        let response = ApiError::conflict("Email taken").into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
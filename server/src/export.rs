@@ -0,0 +1,604 @@
+use axum::{
+    extract::{DefaultBodyLimit, Query, State},
+    http::header,
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use shared::{
+    EntityKind, ExportArchive, PracticeGoal, PracticeSession, SessionError, Study, EXPORT_VERSION,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    goals::GoalRepository,
+    repository::{
+        archive_entity, format_instant, restore_entity, Database, RepositoryError, RepositoryResult,
+    },
+    sessions::SessionRepository,
+    studies::StudyRepository,
+    ApiError,
+};
+
+// Archives hold a user's whole history, so they are allowed to be larger
+// than other request bodies
+pub const IMPORT_BODY_LIMIT: usize = 20 * 1024 * 1024;
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    // Sessions only, one row each
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+// An archived entity the user already has
+struct Saved {
+    version: u32,
+    archived: bool,
+}
+
+/// What an import did, with the new id of everything whose id was already
+/// taken by another user or by something in the trash
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub created: u32,
+    pub updated: u32,
+    pub remapped_ids: HashMap<String, String>,
+}
+
+// Export and import across the other repositories - no traits, just methods
+pub struct ExportRepository {
+    db: Database,
+    goals: Arc<GoalRepository>,
+    studies: Arc<StudyRepository>,
+    sessions: Arc<SessionRepository>,
+}
+
+impl ExportRepository {
+    pub fn new(
+        pool: crate::repository::DbPool,
+        goals: Arc<GoalRepository>,
+        studies: Arc<StudyRepository>,
+        sessions: Arc<SessionRepository>,
+    ) -> Self {
+        Self {
+            db: Database::new(pool),
+            goals,
+            studies,
+            sessions,
+        }
+    }
+
+    /// Everything the user has, archived included; the trash is left out
+    pub async fn export(&self, user_id: &str) -> RepositoryResult<ExportArchive> {
+        Ok(ExportArchive {
+            version: EXPORT_VERSION,
+            exported_at: format_instant(Utc::now()),
+            goals: self.goals.find_all(user_id).await?,
+            studies: self.studies.find_all(user_id).await?,
+            sessions: self.sessions.find_all(user_id).await?,
+        })
+    }
+
+    /// Creates what the user doesn't have yet and overwrites what they do,
+    /// matching by id. The whole archive is saved in one transaction, so an
+    /// import that fails leaves nothing of it behind.
+    pub async fn import(
+        &self,
+        user_id: &str,
+        mut archive: ExportArchive,
+    ) -> RepositoryResult<ImportReport> {
+        archive
+            .validate()
+            .map_err(|e| RepositoryError::ValidationError(e.to_string()))?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        let mut report = ImportReport::default();
+        let taken = Self::taken_ids(&mut tx, user_id, &archive).await?;
+        report
+            .remapped_ids
+            .extend(archive.remap(&taken, shared::generate_id));
+        // Study sessions are upserted by id without moving them between
+        // sessions, so one already in another session needs a new id too
+        let existing = Self::existing_study_sessions(&mut tx, &archive).await?;
+        let moved = moved_study_sessions(&archive, &existing);
+        report
+            .remapped_ids
+            .extend(archive.remap(&moved, shared::generate_id));
+        Self::drop_unknown_plans(&mut tx, user_id, &mut archive).await?;
+
+        // Studies first, as goals and study sessions link to them, then goals
+        // that sessions link to
+        let ids = archive.studies.iter().map(|s| s.id.clone()).collect();
+        let saved = Self::saved(&mut tx, "studies", user_id, ids).await?;
+        for study in &archive.studies {
+            Self::import_study(&mut tx, user_id, study, saved.get(&study.id), &mut report).await?;
+        }
+        let ids = archive.goals.iter().map(|g| g.id.clone()).collect();
+        let saved = Self::saved(&mut tx, "goals", user_id, ids).await?;
+        for goal in &archive.goals {
+            Self::import_goal(&mut tx, user_id, goal, saved.get(&goal.id), &mut report).await?;
+        }
+        let ids = archive.sessions.iter().map(|s| s.id.clone()).collect();
+        let saved = Self::saved(&mut tx, "sessions", user_id, ids).await?;
+        for session in &archive.sessions {
+            Self::import_session(
+                &mut tx,
+                user_id,
+                session,
+                saved.get(&session.id),
+                &mut report,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(report)
+    }
+
+    async fn import_study(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        study: &Study,
+        saved: Option<&Saved>,
+        report: &mut ImportReport,
+    ) -> RepositoryResult<()> {
+        let was_archived = match saved {
+            Some(saved) => {
+                let study = Study {
+                    version: saved.version,
+                    ..study.clone()
+                };
+                StudyRepository::update_in(tx, user_id, &study).await?;
+                report.updated += 1;
+                saved.archived
+            }
+            None => {
+                StudyRepository::create_in(tx, user_id, study).await?;
+                report.created += 1;
+                false
+            }
+        };
+
+        match (was_archived, study.archived_at.is_some()) {
+            (false, true) => archive_entity(&mut **tx, "studies", user_id, &study.id)
+                .await
+                .map(|_| ()),
+            (true, false) => StudyRepository::restore_in(tx, user_id, &study.id)
+                .await
+                .map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn import_goal(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        goal: &PracticeGoal,
+        saved: Option<&Saved>,
+        report: &mut ImportReport,
+    ) -> RepositoryResult<()> {
+        let was_archived = match saved {
+            Some(saved) => {
+                let goal = PracticeGoal {
+                    version: saved.version,
+                    ..goal.clone()
+                };
+                GoalRepository::update_in(tx, user_id, &goal).await?;
+                report.updated += 1;
+                saved.archived
+            }
+            None => {
+                GoalRepository::create_in(tx, user_id, goal).await?;
+                report.created += 1;
+                false
+            }
+        };
+
+        match (was_archived, goal.archived_at.is_some()) {
+            (false, true) => archive_entity(&mut **tx, "goals", user_id, &goal.id)
+                .await
+                .map(|_| ()),
+            (true, false) => GoalRepository::restore_in(tx, user_id, &goal.id)
+                .await
+                .map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn import_session(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+        saved: Option<&Saved>,
+        report: &mut ImportReport,
+    ) -> RepositoryResult<()> {
+        let was_archived = match saved {
+            Some(saved) => {
+                Self::ensure_moves_on(tx, user_id, session).await?;
+                let session = PracticeSession {
+                    version: saved.version,
+                    ..session.clone()
+                };
+                SessionRepository::update_in(tx, user_id, &session).await?;
+                report.updated += 1;
+                saved.archived
+            }
+            None => {
+                SessionRepository::create_in(tx, user_id, session).await?;
+                report.created += 1;
+                false
+            }
+        };
+
+        match (was_archived, session.archived_at.is_some()) {
+            (false, true) => archive_entity(&mut **tx, "sessions", user_id, &session.id)
+                .await
+                .map(|_| ()),
+            (true, false) => {
+                restore_entity(tx, "sessions", EntityKind::Session, user_id, &session.id)
+                    .await
+                    .map(|_| ())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // An import can carry a session the user has on to a later state, e.g.
+    // end it, but not take it back to an earlier one
+    async fn ensure_moves_on(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+    ) -> RepositoryResult<()> {
+        let stored: String =
+            sqlx::query_scalar("SELECT session_state FROM sessions WHERE id = $1 AND user_id = $2")
+                .bind(&session.id)
+                .bind(user_id)
+                .fetch_one(&mut **tx)
+                .await?;
+
+        let imported = session.state.name();
+        if stage(imported) < stage(&stored) {
+            return Err(RepositoryError::InvalidTransition(
+                SessionError::InvalidTransition {
+                    from: stored,
+                    to: imported.to_string(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    // The version and archived state of each of the ids the user has saved,
+    // leaving out the trash
+    async fn saved(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        table: &str,
+        user_id: &str,
+        ids: Vec<String>,
+    ) -> RepositoryResult<HashMap<String, Saved>> {
+        let rows: Vec<(String, i32, bool)> = sqlx::query_as(&format!(
+            "SELECT id, version, archived_at IS NOT NULL FROM {table}
+             WHERE id = ANY($1) AND user_id = $2 AND deleted_at IS NULL"
+        ))
+        .bind(&ids)
+        .bind(user_id)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, version, archived)| {
+                let saved = Saved {
+                    version: version as u32,
+                    archived,
+                };
+                (id, saved)
+            })
+            .collect())
+    }
+
+    // Ids in the archive that can't be used as they are: another user's, or
+    // this user's but in the trash
+    async fn taken_ids(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        archive: &ExportArchive,
+    ) -> RepositoryResult<HashSet<String>> {
+        let goal_ids: Vec<String> = archive.goals.iter().map(|g| g.id.clone()).collect();
+        let study_ids: Vec<String> = archive.studies.iter().map(|s| s.id.clone()).collect();
+        let session_ids: Vec<String> = archive.sessions.iter().map(|s| s.id.clone()).collect();
+
+        let mut taken = HashSet::new();
+        for (table, ids) in [
+            ("goals", goal_ids),
+            ("studies", study_ids),
+            ("sessions", session_ids),
+        ] {
+            let rows: Vec<String> = sqlx::query_scalar(&format!(
+                "SELECT id FROM {table} WHERE id = ANY($1)
+                 AND (user_id <> $2 OR deleted_at IS NOT NULL)"
+            ))
+            .bind(&ids)
+            .bind(user_id)
+            .fetch_all(&mut **tx)
+            .await?;
+            taken.extend(rows);
+        }
+
+        Ok(taken)
+    }
+
+    // The session each of the archive's study session ids is saved under now
+    async fn existing_study_sessions(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        archive: &ExportArchive,
+    ) -> RepositoryResult<Vec<(String, String)>> {
+        let ids: Vec<String> = archive.study_sessions().map(|s| s.id.clone()).collect();
+        let rows: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, session_id FROM study_sessions WHERE id = ANY($1)")
+                .bind(&ids)
+                .fetch_all(&mut **tx)
+                .await?;
+
+        Ok(rows)
+    }
+
+    // Plans aren't part of the archive, so sessions only keep the plan they
+    // came from when the user has it
+    async fn drop_unknown_plans(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        archive: &mut ExportArchive,
+    ) -> RepositoryResult<()> {
+        let plan_ids: Vec<String> = archive
+            .sessions
+            .iter()
+            .filter_map(|s| s.plan_id.clone())
+            .collect();
+        let owned: HashSet<String> = sqlx::query_scalar::<_, String>(
+            "SELECT id FROM plans WHERE id = ANY($1) AND user_id = $2",
+        )
+        .bind(&plan_ids)
+        .bind(user_id)
+        .fetch_all(&mut **tx)
+        .await?
+        .into_iter()
+        .collect();
+
+        for session in &mut archive.sessions {
+            if session
+                .plan_id
+                .as_ref()
+                .is_some_and(|id| !owned.contains(id))
+            {
+                session.plan_id = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Study sessions of the archive already saved under a session other than
+// the one the archive puts them in
+// How far through its life a session in this state is
+fn stage(session_state: &str) -> u8 {
+    match session_state {
+        "NotStarted" => 0,
+        "Started" | "Paused" => 1,
+        "PendingReflection" => 2,
+        _ => 3,
+    }
+}
+
+fn moved_study_sessions(archive: &ExportArchive, existing: &[(String, String)]) -> HashSet<String> {
+    existing
+        .iter()
+        .filter(|(id, session_id)| {
+            archive
+                .study_sessions()
+                .any(|s| &s.id == id && &s.session_id != session_id)
+        })
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+// HTTP Handlers
+async fn export(
+    State(export_repo): State<Arc<ExportRepository>>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let archive = export_repo.export(&user_id).await?;
+
+    let response = match query.format {
+        ExportFormat::Json => Json(archive).into_response(),
+        ExportFormat::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"intrada-sessions.csv\"",
+                ),
+            ],
+            archive.sessions_csv(),
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
+
+async fn import(
+    State(export_repo): State<Arc<ExportRepository>>,
+    AuthUser(user_id): AuthUser,
+    Json(archive): Json<ExportArchive>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let report = export_repo.import(&user_id, archive).await?;
+
+    Ok(Json(report))
+}
+
+pub fn routes() -> Router<Arc<ExportRepository>> {
+    Router::new().route("/export", get(export)).route(
+        "/import",
+        post(import).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+    )
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::SessionState;
+
+    #[test]
+    fn test_export_format_defaults_to_json() {
+        let query: ExportQuery = serde_json::from_str("{}").unwrap();
+        assert_eq!(query.format, ExportFormat::Json);
+
+        let query: ExportQuery = serde_json::from_str(r#"{"format": "csv"}"#).unwrap();
+        assert_eq!(query.format, ExportFormat::Csv);
+    }
+
+    #[test]
+    fn test_study_sessions_saved_under_another_session_are_moved() {
+        let mut session = PracticeSession::new(Vec::new(), "Scales".to_string());
+        let study_session = shared::StudySession::new("study".to_string(), session.id.clone());
+        let study_session_id = study_session.id.clone();
+        session.study_sessions.push(study_session);
+        let archive = ExportArchive {
+            sessions: vec![session.clone()],
+            ..ExportArchive::default()
+        };
+
+        let same = [(study_session_id.clone(), session.id.clone())];
+        assert!(moved_study_sessions(&archive, &same).is_empty());
+
+        let other = [(study_session_id.clone(), "other".to_string())];
+        assert_eq!(
+            moved_study_sessions(&archive, &other),
+            HashSet::from([study_session_id])
+        );
+    }
+
+    fn export_repo(pool: &crate::repository::DbPool) -> ExportRepository {
+        ExportRepository::new(
+            pool.clone(),
+            Arc::new(GoalRepository::new(pool.clone())),
+            Arc::new(StudyRepository::new(pool.clone())),
+            Arc::new(SessionRepository::new(pool.clone())),
+        )
+    }
+
+    // A study and a session practicing it, ended
+    fn ended_archive() -> ExportArchive {
+        let study = Study::new("Scales".to_string(), None);
+        let mut session = PracticeSession::new(Vec::new(), "Scales".to_string());
+        session.state = SessionState::Ended {
+            start_time: "2025-05-01T18:00:00Z".to_string(),
+            end_time: "2025-05-01T18:30:00Z".to_string(),
+            duration_in_seconds: 1800,
+        };
+        session.study_sessions.push(shared::StudySession::new(
+            study.id.clone(),
+            session.id.clone(),
+        ));
+        ExportArchive {
+            version: EXPORT_VERSION,
+            exported_at: "2025-05-02T08:00:00Z".to_string(),
+            goals: Vec::new(),
+            studies: vec![study],
+            sessions: vec![session],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_creates_then_updates() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let export_repo = export_repo(&pool);
+        let user = crate::repository::create_test_user(&pool).await;
+        let archive = ended_archive();
+
+        let report = export_repo.import(&user, archive.clone()).await.unwrap();
+        assert_eq!((report.created, report.updated), (2, 0));
+        let report = export_repo.import(&user, archive.clone()).await.unwrap();
+        assert_eq!((report.created, report.updated), (0, 2));
+
+        let session = SessionRepository::new(pool.clone())
+            .find_by_id(&user, &archive.sessions[0].id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.is_ended());
+        assert_eq!(session.study_sessions.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_import_cannot_take_a_session_back_and_saves_nothing() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let export_repo = export_repo(&pool);
+        let study_repo = StudyRepository::new(pool.clone());
+        let user = crate::repository::create_test_user(&pool).await;
+        let mut archive = ended_archive();
+        export_repo.import(&user, archive.clone()).await.unwrap();
+
+        // The session goes back to not started, alongside a new study
+        archive.sessions[0].state = SessionState::NotStarted;
+        let study = Study::new("Arpeggios".to_string(), None);
+        archive.studies.push(study.clone());
+        let error = export_repo
+            .import(&user, archive.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, RepositoryError::InvalidTransition(_)));
+
+        let session = SessionRepository::new(pool.clone())
+            .find_by_id(&user, &archive.sessions[0].id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(session.is_ended());
+        assert!(study_repo
+            .find_by_id(&user, &study.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_refuses_running_sessions() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let user = crate::repository::create_test_user(&pool).await;
+        let mut archive = ended_archive();
+        archive.sessions[0].state = SessionState::Started {
+            start_time: "2025-05-01T18:00:00Z".to_string(),
+        };
+
+        let error = export_repo(&pool).import(&user, archive).await.unwrap_err();
+        assert!(matches!(error, RepositoryError::ValidationError(_)));
+    }
+}
@@ -1,17 +1,27 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::Deserialize;
-use shared::{GoalStatus, PracticeGoal};
+use shared::{
+    DeleteImpact, DeleteMode, EntityKind, GoalError, GoalStatus, PracticeGoal, PracticeTarget,
+    TargetMeasure, TargetPeriod,
+};
 use sqlx::FromRow;
 use std::sync::Arc;
 
 use crate::{
-    repository::{Database, RepositoryError, RepositoryResult},
+    auth::AuthUser,
+    repository::{
+        archive_entity, check_unreferenced, check_version, count_linking, explicit_null,
+        format_instant, format_timestamp, missing_or_stale, parse_date, restore_entity, save_links,
+        touch_linking, trash_entity, Database, DeleteQuery, ListQuery, RepositoryError,
+        RepositoryResult, GOAL_STUDIES, PLAN_GOALS, SESSION_GOALS, TEMPLATE_GOALS,
+    },
     ApiError,
 };
 
@@ -22,31 +32,59 @@ pub struct GoalRow {
     pub name: String,
     pub description: Option<String>,
     pub status: String,
-    pub start_date: Option<String>,
-    pub target_date: Option<String>,
-    pub study_ids: String, // JSON string
+    pub start_date: Option<NaiveDate>,
+    pub target_date: Option<NaiveDate>,
+    pub study_ids: Vec<String>, // from goal_studies, in order
     pub tempo_target: Option<i32>,
+    pub target_amount: Option<i32>,
+    pub target_measure: Option<String>,
+    pub target_period: Option<String>,
+    pub clean_reps_to_complete: Option<i32>,
+    pub version: i32,
+    pub updated_at: Option<NaiveDateTime>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl GoalRow {
     pub fn into_goal(self) -> RepositoryResult<PracticeGoal> {
-        let study_ids: Vec<String> = serde_json::from_str(&self.study_ids)?;
         let status = match self.status.as_str() {
             "NotStarted" => GoalStatus::NotStarted,
             "InProgress" => GoalStatus::InProgress,
             "Completed" => GoalStatus::Completed,
             _ => GoalStatus::NotStarted,
         };
+        let practice_target = match (self.target_amount, self.target_measure, self.target_period) {
+            (Some(amount), Some(measure), Some(period)) => Some(PracticeTarget {
+                amount: amount as u32,
+                measure: match measure.as_str() {
+                    "Sessions" => TargetMeasure::Sessions,
+                    _ => TargetMeasure::Minutes,
+                },
+                period: match period.as_str() {
+                    "Week" => TargetPeriod::Week,
+                    "Total" => TargetPeriod::Total,
+                    _ => TargetPeriod::Day,
+                },
+            }),
+            _ => None,
+        };
 
         Ok(PracticeGoal {
             id: self.id,
             name: self.name,
             description: self.description,
             status,
-            start_date: self.start_date,
-            target_date: self.target_date,
-            study_ids,
+            start_date: self.start_date.map(|d| d.to_string()),
+            target_date: self.target_date.map(|d| d.to_string()),
+            study_ids: self.study_ids,
             tempo_target: self.tempo_target.map(|t| t as u32),
+            practice_target,
+            clean_reps_to_complete: self.clean_reps_to_complete.map(|r| r as u32),
+            version: self.version as u32,
+            updated_at: self.updated_at.map(format_timestamp),
+            archived_at: self.archived_at.map(format_instant),
+            deleted_at: self.deleted_at.map(format_instant),
         })
     }
 }
@@ -54,11 +92,15 @@ impl GoalRow {
 // Request types
 #[derive(Debug, Deserialize)]
 pub struct CreateGoalRequest {
+    // Client generated id, so optimistic local records keep their identity
+    pub id: Option<String>,
     pub name: String,
     pub description: Option<String>,
     pub target_date: Option<String>,
     pub study_ids: Vec<String>,
     pub tempo_target: Option<u32>,
+    pub practice_target: Option<PracticeTarget>,
+    pub clean_reps_to_complete: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -70,6 +112,12 @@ pub struct UpdateGoalRequest {
     pub target_date: Option<String>,
     pub study_ids: Option<Vec<String>>,
     pub tempo_target: Option<u32>,
+    // Left out to keep the target, null to remove it
+    #[serde(default, deserialize_with = "explicit_null")]
+    pub practice_target: Option<Option<PracticeTarget>>,
+    pub clean_reps_to_complete: Option<u32>,
+    // The version the client's edit was based on, stale edits are rejected
+    pub version: Option<u32>,
 }
 
 // Simple Goal repository - no traits, just methods
@@ -92,33 +140,99 @@ impl GoalRepository {
         }
     }
 
-    pub async fn create(&self, goal: &PracticeGoal) -> RepositoryResult<()> {
-        let study_ids_json = serde_json::to_string(&goal.study_ids)?;
+    fn dates(goal: &PracticeGoal) -> RepositoryResult<(Option<NaiveDate>, Option<NaiveDate>)> {
+        let start_date = goal
+            .start_date
+            .as_deref()
+            .map(|d| parse_date("start_date", d))
+            .transpose()?;
+        let target_date = goal
+            .target_date
+            .as_deref()
+            .map(|d| parse_date("target_date", d))
+            .transpose()?;
+        Ok((start_date, target_date))
+    }
+
+    // The practice target as its amount, measure and period columns
+    fn target_columns(
+        goal: &PracticeGoal,
+    ) -> RepositoryResult<(Option<i32>, Option<&'static str>, Option<&'static str>)> {
+        let Some(target) = &goal.practice_target else {
+            return Ok((None, None, None));
+        };
+        if target.amount == 0 {
+            return Err(RepositoryError::ValidationError(
+                "practice_target amount must be greater than 0".to_string(),
+            ));
+        }
+        let measure = match target.measure {
+            TargetMeasure::Minutes => "Minutes",
+            TargetMeasure::Sessions => "Sessions",
+        };
+        let period = match target.period {
+            TargetPeriod::Day => "Day",
+            TargetPeriod::Week => "Week",
+            TargetPeriod::Total => "Total",
+        };
+        Ok((Some(target.amount as i32), Some(measure), Some(period)))
+    }
+
+    pub async fn create(&self, user_id: &str, goal: &PracticeGoal) -> RepositoryResult<()> {
+        let mut tx = self.db.pool.begin().await?;
+        Self::create_in(&mut tx, user_id, goal).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Creates a goal as part of a larger transaction, such as an import
+    pub async fn create_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        goal: &PracticeGoal,
+    ) -> RepositoryResult<()> {
+        let (start_date, target_date) = Self::dates(goal)?;
+        let (target_amount, target_measure, target_period) = Self::target_columns(goal)?;
 
         sqlx::query(
-            "INSERT INTO goals (id, name, description, status, start_date, target_date, study_ids, tempo_target) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+            "INSERT INTO goals (id, name, description, status, start_date, target_date, tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, user_id) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
         )
         .bind(&goal.id)
         .bind(&goal.name)
         .bind(&goal.description)
         .bind(Self::status_to_string(&goal.status))
-        .bind(&goal.start_date)
-        .bind(&goal.target_date)
-        .bind(&study_ids_json)
+        .bind(start_date)
+        .bind(target_date)
         .bind(goal.tempo_target.map(|t| t as i32))
-        .execute(&self.db.pool)
+        .bind(target_amount)
+        .bind(target_measure)
+        .bind(target_period)
+        .bind(goal.clean_reps_to_complete.map(|r| r as i32))
+        .bind(user_id)
+        .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        save_links(tx, &GOAL_STUDIES, user_id, &goal.id, &goal.study_ids).await
     }
 
-    pub async fn find_by_id(&self, id: &str) -> RepositoryResult<Option<PracticeGoal>> {
+    pub async fn find_by_id(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<PracticeGoal>> {
         let row = sqlx::query_as!(
             GoalRow,
-            "SELECT id, name, description, status, start_date, target_date, study_ids, tempo_target 
-             FROM goals WHERE id = $1",
-            id
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#,
+            id,
+            user_id
         )
         .fetch_optional(&self.db.pool)
         .await?;
@@ -129,11 +243,16 @@ impl GoalRepository {
         }
     }
 
-    pub async fn find_all(&self) -> RepositoryResult<Vec<PracticeGoal>> {
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Vec<PracticeGoal>> {
         let rows = sqlx::query_as!(
             GoalRow,
-            "SELECT id, name, description, status, start_date, target_date, study_ids, tempo_target 
-             FROM goals ORDER BY created_at DESC"
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC"#,
+            user_id
         )
         .fetch_all(&self.db.pool)
         .await?;
@@ -145,51 +264,209 @@ impl GoalRepository {
         Ok(goals)
     }
 
-    pub async fn update(&self, goal: &PracticeGoal) -> RepositoryResult<()> {
-        let study_ids_json = serde_json::to_string(&goal.study_ids)?;
+    pub async fn update(
+        &self,
+        user_id: &str,
+        goal: &PracticeGoal,
+    ) -> RepositoryResult<PracticeGoal> {
+        let mut tx = self.db.pool.begin().await?;
+        let goal = Self::update_in(&mut tx, user_id, goal).await?;
+        tx.commit().await?;
+
+        Ok(goal)
+    }
+
+    /// Updates a goal as part of a larger transaction, such as an import
+    pub async fn update_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        goal: &PracticeGoal,
+    ) -> RepositoryResult<PracticeGoal> {
+        let (start_date, target_date) = Self::dates(goal)?;
+        let (target_amount, target_measure, target_period) = Self::target_columns(goal)?;
 
-        let result = sqlx::query(
+        let stamp: Option<(i32, Option<NaiveDateTime>)> = sqlx::query_as(
             "UPDATE goals SET name = $2, description = $3, status = $4, start_date = $5, 
-             target_date = $6, study_ids = $7, tempo_target = $8, updated_at = CURRENT_TIMESTAMP 
-             WHERE id = $1",
+             target_date = $6, tempo_target = $7, target_amount = $8, target_measure = $9,
+             target_period = $10, clean_reps_to_complete = $11,
+             version = version + 1, updated_at = CURRENT_TIMESTAMP 
+             WHERE id = $1 AND version = $12 AND user_id = $13
+             RETURNING version, updated_at",
         )
         .bind(&goal.id)
         .bind(&goal.name)
         .bind(&goal.description)
         .bind(Self::status_to_string(&goal.status))
-        .bind(&goal.start_date)
-        .bind(&goal.target_date)
-        .bind(&study_ids_json)
+        .bind(start_date)
+        .bind(target_date)
         .bind(goal.tempo_target.map(|t| t as i32))
-        .execute(&self.db.pool)
+        .bind(target_amount)
+        .bind(target_measure)
+        .bind(target_period)
+        .bind(goal.clean_reps_to_complete.map(|r| r as i32))
+        .bind(goal.version as i32)
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some((version, updated_at)) = stamp else {
+            return Err(
+                missing_or_stale(&mut **tx, "goals", user_id, &goal.id, goal.version).await,
+            );
+        };
+
+        save_links(tx, &GOAL_STUDIES, user_id, &goal.id, &goal.study_ids).await?;
+
+        Ok(PracticeGoal {
+            version: version as u32,
+            updated_at: updated_at.map(format_timestamp),
+            ..goal.clone()
+        })
+    }
+
+    pub async fn find_changed_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<PracticeGoal>> {
+        let rows = sqlx::query_as!(
+            GoalRow,
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals WHERE user_id = $1 AND change_xid >= $2 AND deleted_at IS NULL
+             ORDER BY updated_at"#,
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(RepositoryError::NotFound(format!(
-                "Goal with id {}",
-                goal.id
-            )));
+        let mut goals = Vec::new();
+        for row in rows {
+            goals.push(row.into_goal()?);
         }
+        Ok(goals)
+    }
 
-        Ok(())
+    /// Deletes a goal, detaching it from the sessions, plans and templates
+    /// that list it unless `mode` blocks the delete while any do
+    pub async fn delete(
+        &self,
+        user_id: &str,
+        id: &str,
+        mode: DeleteMode,
+    ) -> RepositoryResult<bool> {
+        let mut tx = self.db.pool.begin().await?;
+
+        if mode == DeleteMode::BlockIfReferenced {
+            let impact = DeleteImpact::new(
+                id.to_string(),
+                count_linking(&mut tx, &SESSION_GOALS, user_id, id).await?,
+                0,
+                count_linking(&mut tx, &PLAN_GOALS, user_id, id).await?,
+                count_linking(&mut tx, &TEMPLATE_GOALS, user_id, id).await?,
+            );
+            check_unreferenced("Goal", &impact)?;
+        }
+        touch_linking(&mut tx, &SESSION_GOALS, user_id, id).await?;
+        touch_linking(&mut tx, &PLAN_GOALS, user_id, id).await?;
+        touch_linking(&mut tx, &TEMPLATE_GOALS, user_id, id).await?;
+        let deleted = trash_entity(&mut tx, "goals", EntityKind::Goal, user_id, id).await?;
+        tx.commit().await?;
+
+        Ok(deleted)
+    }
+
+    pub async fn archive(&self, user_id: &str, id: &str) -> RepositoryResult<Option<PracticeGoal>> {
+        if !archive_entity(&self.db.pool, "goals", user_id, id).await? {
+            return Ok(None);
+        }
+        self.find_by_id(user_id, id).await
+    }
+
+    /// Brings a goal back from the archive or the trash, along with its
+    /// place in the sessions, plans and templates that listed it
+    pub async fn restore(&self, user_id: &str, id: &str) -> RepositoryResult<Option<PracticeGoal>> {
+        let mut tx = self.db.pool.begin().await?;
+
+        if !Self::restore_in(&mut tx, user_id, id).await? {
+            return Ok(None);
+        }
+        tx.commit().await?;
+
+        self.find_by_id(user_id, id).await
     }
 
-    pub async fn delete(&self, id: &str) -> RepositoryResult<bool> {
-        let result = sqlx::query("DELETE FROM goals WHERE id = $1")
-            .bind(id)
+    /// Restores a goal as part of a larger transaction, returning whether
+    /// the user has it
+    pub async fn restore_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<bool> {
+        if !restore_entity(tx, "goals", EntityKind::Goal, user_id, id).await? {
+            return Ok(false);
+        }
+        touch_linking(tx, &SESSION_GOALS, user_id, id).await?;
+        touch_linking(tx, &PLAN_GOALS, user_id, id).await?;
+        touch_linking(tx, &TEMPLATE_GOALS, user_id, id).await?;
+
+        Ok(true)
+    }
+
+    pub async fn find_trashed(&self, user_id: &str) -> RepositoryResult<Vec<PracticeGoal>> {
+        let rows = sqlx::query_as!(
+            GoalRow,
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals
+             WHERE user_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut goals = Vec::new();
+        for row in rows {
+            goals.push(row.into_goal()?);
+        }
+        Ok(goals)
+    }
+
+    /// Deletes every user's goals that have been in the trash since before
+    /// `before`; their links go with them
+    pub async fn purge_deleted_before(&self, before: DateTime<Utc>) -> RepositoryResult<u64> {
+        let result = sqlx::query("DELETE FROM goals WHERE deleted_at < $1")
+            .bind(before)
             .execute(&self.db.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(result.rows_affected())
     }
 
     // Domain-specific methods - no trait constraints
-    pub async fn _find_by_status(&self, status: GoalStatus) -> RepositoryResult<Vec<PracticeGoal>> {
+    pub async fn _find_by_status(
+        &self,
+        user_id: &str,
+        status: GoalStatus,
+    ) -> RepositoryResult<Vec<PracticeGoal>> {
         let status_str = Self::status_to_string(&status);
         let rows = sqlx::query_as!(
             GoalRow,
-            "SELECT id, name, description, status, start_date, target_date, study_ids, tempo_target 
-             FROM goals WHERE status = $1 ORDER BY created_at DESC",
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals WHERE user_id = $1 AND status = $2 AND deleted_at IS NULL
+             ORDER BY created_at DESC"#,
+            user_id,
             status_str
         )
         .fetch_all(&self.db.pool)
@@ -202,24 +479,31 @@ impl GoalRepository {
         Ok(goals)
     }
 
-    pub async fn _find_by_study_id(&self, study_id: &str) -> RepositoryResult<Vec<PracticeGoal>> {
-        let study_pattern = format!("%\"{study_id}\"%");
+    pub async fn _find_by_study_id(
+        &self,
+        user_id: &str,
+        study_id: &str,
+    ) -> RepositoryResult<Vec<PracticeGoal>> {
         let rows = sqlx::query_as!(
             GoalRow,
-            "SELECT id, name, description, status, start_date, target_date, study_ids, tempo_target 
-             FROM goals WHERE study_ids LIKE $1 ORDER BY created_at DESC",
-            study_pattern
+            r#"SELECT id, name, description, status, start_date, target_date,
+             ARRAY(SELECT study_id FROM goal_studies JOIN studies ON studies.id = study_id
+                   WHERE goal_id = goals.id AND studies.deleted_at IS NULL ORDER BY position)
+             AS "study_ids!", tempo_target,
+             target_amount, target_measure, target_period, clean_reps_to_complete, version, updated_at,
+             archived_at, deleted_at FROM goals
+             WHERE user_id = $1 AND deleted_at IS NULL
+               AND id IN (SELECT goal_id FROM goal_studies WHERE study_id = $2)
+             ORDER BY created_at DESC"#,
+            user_id,
+            study_id
         )
         .fetch_all(&self.db.pool)
         .await?;
 
         let mut goals = Vec::new();
         for row in rows {
-            let goal = row.into_goal()?;
-            // Double-check the study_id is actually in the list (not just substring match)
-            if goal.study_ids.contains(&study_id.to_string()) {
-                goals.push(goal);
-            }
+            goals.push(row.into_goal()?);
         }
         Ok(goals)
     }
@@ -228,78 +512,78 @@ impl GoalRepository {
 // HTTP Handlers
 async fn create_goal(
     State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
     Json(req): Json<CreateGoalRequest>,
-) -> Result<Json<PracticeGoal>, (StatusCode, Json<ApiError>)> {
-    let goal = PracticeGoal::new(
+) -> Result<Json<PracticeGoal>, ApiError> {
+    let mut goal = PracticeGoal::new(
         req.name,
         req.description,
         req.target_date,
         req.study_ids,
         req.tempo_target,
     );
+    goal.practice_target = req.practice_target;
+    goal.clean_reps_to_complete = req.clean_reps_to_complete;
+    if let Some(id) = req.id {
+        goal.id = id;
+    }
 
-    goal_repo
-        .create(&goal)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    goal_repo.create(&user_id, &goal).await?;
 
     Ok(Json(goal))
 }
 
 async fn get_goals(
     State(goal_repo): State<Arc<GoalRepository>>,
-) -> Result<Json<Vec<PracticeGoal>>, (StatusCode, Json<ApiError>)> {
-    let goals = goal_repo
-        .find_all()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<PracticeGoal>>, ApiError> {
+    let mut goals = goal_repo.find_all(&user_id).await?;
+    goals.retain(|g| query.include_archived || g.archived_at.is_none());
 
     Ok(Json(goals))
 }
 
 async fn get_goal(
     State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<Json<PracticeGoal>, (StatusCode, Json<ApiError>)> {
-    let goal = goal_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<PracticeGoal>, ApiError> {
+    let goal = goal_repo.find_by_id(&user_id, &id).await?;
 
     match goal {
         Some(goal) => Ok(Json(goal)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Goal not found".to_string(),
-            }),
-        )),
+        None => Err(ApiError::not_found("Goal not found")),
     }
 }
 
 async fn update_goal(
     State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<UpdateGoalRequest>,
-) -> Result<Json<PracticeGoal>, (StatusCode, Json<ApiError>)> {
+) -> Result<Json<PracticeGoal>, ApiError> {
     // Get existing goal
-    let existing_goal = goal_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let existing_goal = goal_repo.find_by_id(&user_id, &id).await?;
 
     let existing_goal = match existing_goal {
         Some(goal) => goal,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Goal not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Goal not found"));
         }
     };
 
+    check_version("Goal", req.version, existing_goal.version)?;
+    if let Some(status) = &req.status {
+        if !existing_goal.status.can_transition_to(status) {
+            return Err(GoalError::InvalidTransition {
+                from: format!("{:?}", existing_goal.status),
+                to: format!("{status:?}"),
+            }
+            .into());
+        }
+    }
+
     // Create updated goal with new values or existing ones
     let updated_goal = PracticeGoal {
         id: existing_goal.id,
@@ -310,34 +594,55 @@ async fn update_goal(
         target_date: req.target_date.or(existing_goal.target_date),
         study_ids: req.study_ids.unwrap_or(existing_goal.study_ids),
         tempo_target: req.tempo_target.or(existing_goal.tempo_target),
+        practice_target: req.practice_target.unwrap_or(existing_goal.practice_target),
+        clean_reps_to_complete: req
+            .clean_reps_to_complete
+            .or(existing_goal.clean_reps_to_complete),
+        version: existing_goal.version,
+        updated_at: existing_goal.updated_at,
+        archived_at: existing_goal.archived_at,
+        deleted_at: existing_goal.deleted_at,
     };
 
-    goal_repo
-        .update(&updated_goal)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let updated_goal = goal_repo.update(&user_id, &updated_goal).await?;
 
     Ok(Json(updated_goal))
 }
 
 async fn delete_goal(
     State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let deleted = goal_repo
-        .delete(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    Query(query): Query<DeleteQuery>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = goal_repo.delete(&user_id, &id, query.mode).await?;
 
     if deleted {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Goal not found".to_string(),
-            }),
-        ))
+        Err(ApiError::not_found("Goal not found"))
+    }
+}
+
+async fn archive_goal(
+    State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PracticeGoal>, ApiError> {
+    match goal_repo.archive(&user_id, &id).await? {
+        Some(goal) => Ok(Json(goal)),
+        None => Err(ApiError::not_found("Goal not found")),
+    }
+}
+
+async fn restore_goal(
+    State(goal_repo): State<Arc<GoalRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PracticeGoal>, ApiError> {
+    match goal_repo.restore(&user_id, &id).await? {
+        Some(goal) => Ok(Json(goal)),
+        None => Err(ApiError::not_found("Goal not found")),
     }
 }
 
@@ -348,6 +653,8 @@ pub fn routes() -> Router<Arc<GoalRepository>> {
             "/goals/{id}",
             get(get_goal).put(update_goal).delete(delete_goal),
         )
+        .route("/goals/{id}/archive", post(archive_goal))
+        .route("/goals/{id}/restore", post(restore_goal))
 }
 
 // *************
@@ -357,6 +664,7 @@ pub fn routes() -> Router<Arc<GoalRepository>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::repository::RepositoryError;
     use shared::GoalStatus;
     use std::sync::Mutex;
 
@@ -416,10 +724,18 @@ mod tests {
             name: "Test Goal".to_string(),
             description: Some("Test description".to_string()),
             status: "InProgress".to_string(),
-            start_date: Some("2024-01-01".to_string()),
-            target_date: Some("2024-12-31".to_string()),
-            study_ids: r#"["study1", "study2"]"#.to_string(),
+            start_date: Some(parse_date("start_date", "2024-01-01").unwrap()),
+            target_date: Some(parse_date("target_date", "2024-12-31").unwrap()),
+            study_ids: vec!["study1".to_string(), "study2".to_string()],
             tempo_target: Some(120),
+            target_amount: Some(3),
+            target_measure: Some("Sessions".to_string()),
+            target_period: Some("Week".to_string()),
+            clean_reps_to_complete: Some(3),
+            version: 4,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         };
 
         let goal = goal_row.into_goal().unwrap();
@@ -427,17 +743,30 @@ mod tests {
         assert_eq!(goal.name, "Test Goal");
         assert_eq!(goal.status, GoalStatus::InProgress);
         assert_eq!(goal.study_ids, vec!["study1", "study2"]);
+        assert_eq!(goal.target_date.as_deref(), Some("2024-12-31"));
         assert_eq!(goal.tempo_target, Some(120));
+        assert_eq!(
+            goal.practice_target,
+            Some(PracticeTarget {
+                amount: 3,
+                measure: TargetMeasure::Sessions,
+                period: TargetPeriod::Week,
+            })
+        );
+        assert_eq!(goal.version, 4);
     }
 
     #[test]
     fn test_create_goal_request_validation() {
         let request = CreateGoalRequest {
+            id: None,
             name: "Test Goal".to_string(),
             description: Some("Description".to_string()),
             target_date: Some("2024-12-31".to_string()),
             study_ids: vec!["study1".to_string(), "study2".to_string()],
             tempo_target: Some(120),
+            practice_target: None,
+            clean_reps_to_complete: None,
         };
 
         assert_eq!(request.name, "Test Goal");
@@ -457,6 +786,9 @@ mod tests {
             target_date: None,
             study_ids: None,
             tempo_target: Some(140),
+            practice_target: None,
+            clean_reps_to_complete: None,
+            version: Some(2),
         };
 
         assert_eq!(request.name, Some("Updated Name".to_string()));
@@ -465,6 +797,29 @@ mod tests {
         assert_eq!(request.tempo_target, Some(140));
     }
 
+    #[test]
+    fn test_update_goal_request_can_remove_practice_target() {
+        let request: UpdateGoalRequest = serde_json::from_str(r#"{"name": "Scales"}"#).unwrap();
+        assert_eq!(request.practice_target, None);
+
+        let request: UpdateGoalRequest =
+            serde_json::from_str(r#"{"practice_target": null}"#).unwrap();
+        assert_eq!(request.practice_target, Some(None));
+
+        let request: UpdateGoalRequest = serde_json::from_str(
+            r#"{"practice_target": {"amount": 20, "measure": "Minutes", "period": "Day"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            request.practice_target,
+            Some(Some(PracticeTarget {
+                amount: 20,
+                measure: TargetMeasure::Minutes,
+                period: TargetPeriod::Day,
+            }))
+        );
+    }
+
     #[tokio::test]
     async fn test_mock_repository_operations() {
         let mock_repo = MockGoalRepository::new();
@@ -521,4 +876,33 @@ mod tests {
             "Completed"
         );
     }
+
+    #[test]
+    fn test_practice_target_columns() {
+        let mut goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+        assert_eq!(
+            GoalRepository::target_columns(&goal).unwrap(),
+            (None, None, None)
+        );
+
+        goal.practice_target = Some(PracticeTarget {
+            amount: 20,
+            measure: TargetMeasure::Minutes,
+            period: TargetPeriod::Day,
+        });
+        assert_eq!(
+            GoalRepository::target_columns(&goal).unwrap(),
+            (Some(20), Some("Minutes"), Some("Day"))
+        );
+
+        goal.practice_target = Some(PracticeTarget {
+            amount: 0,
+            measure: TargetMeasure::Sessions,
+            period: TargetPeriod::Week,
+        });
+        assert!(matches!(
+            GoalRepository::target_columns(&goal),
+            Err(RepositoryError::ValidationError(_))
+        ));
+    }
 }
@@ -0,0 +1,425 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::FromRow;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::{
+    auth::AuthUser,
+    repository::{Database, RepositoryResult},
+    ApiError,
+};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const REPLAYED_HEADER: &str = "idempotent-replayed";
+
+// API responses are small JSON documents, anything bigger is not worth storing
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+// How long a retry waits for the request holding its key to finish, and how
+// often it looks
+const PENDING_WAIT: Duration = Duration::from_secs(5);
+const PENDING_POLL: Duration = Duration::from_millis(100);
+
+// Database row struct - a stored response to a mutating request, None while
+// the request is still running
+#[derive(FromRow)]
+pub struct IdempotencyKeyRow {
+    pub user_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub status_code: Option<i32>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+impl IdempotencyKeyRow {
+    pub fn is_pending(&self) -> bool {
+        self.status_code.is_none()
+    }
+
+    fn is_for(&self, user_id: Option<&str>, method: &str, path: &str) -> bool {
+        self.user_id.as_deref() == user_id && self.method == method && self.path == path
+    }
+
+    // Replays the stored response, refusing keys reused for a different request
+    // or by a different user
+    pub fn into_response_for(self, user_id: Option<&str>, method: &str, path: &str) -> Response {
+        if self.user_id.as_deref() != user_id {
+            return ApiError::validation(
+                "Idempotency key was already used by another user",
+                Vec::new(),
+            )
+            .into_response();
+        }
+        if self.method != method || self.path != path {
+            return ApiError::validation(
+                format!(
+                    "Idempotency key was already used for {} {}",
+                    self.method, self.path
+                ),
+                Vec::new(),
+            )
+            .into_response();
+        }
+
+        let status = self
+            .status_code
+            .and_then(|code| u16::try_from(code).ok())
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .unwrap_or(StatusCode::OK);
+        let body = self.response_body.unwrap_or_default();
+        let has_body = !body.is_empty();
+
+        let mut response = (status, Body::from(body)).into_response();
+        let headers = response.headers_mut();
+        if has_body {
+            headers.insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+        }
+        headers.insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+        response
+    }
+}
+
+// Simple idempotency key repository - no traits, just methods
+pub struct IdempotencyRepository {
+    db: Database,
+}
+
+impl IdempotencyRepository {
+    pub fn new(pool: crate::repository::DbPool) -> Self {
+        Self {
+            db: Database::new(pool),
+        }
+    }
+
+    pub async fn find(&self, key: &str) -> RepositoryResult<Option<IdempotencyKeyRow>> {
+        let row = sqlx::query_as!(
+            IdempotencyKeyRow,
+            "SELECT user_id, method, path, status_code, response_body FROM idempotency_keys
+             WHERE key = $1",
+            key
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Claims the key for a request about to run, or returns false when
+    /// another request already has it
+    pub async fn claim(
+        &self,
+        key: &str,
+        user_id: Option<&str>,
+        method: &str,
+        path: &str,
+    ) -> RepositoryResult<bool> {
+        let result = sqlx::query(
+            "INSERT INTO idempotency_keys (key, user_id, method, path) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (key) DO NOTHING",
+        )
+        .bind(key)
+        .bind(user_id)
+        .bind(method)
+        .bind(path)
+        .execute(&self.db.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Stores the response of the request holding the key
+    pub async fn save(
+        &self,
+        key: &str,
+        status_code: StatusCode,
+        response_body: &[u8],
+    ) -> RepositoryResult<()> {
+        sqlx::query(
+            "UPDATE idempotency_keys SET status_code = $2, response_body = $3 WHERE key = $1",
+        )
+        .bind(key)
+        .bind(i32::from(status_code.as_u16()))
+        .bind(response_body)
+        .execute(&self.db.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Gives up the key of a request that failed, so it can be retried
+    pub async fn release(&self, key: &str) -> RepositoryResult<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1 AND status_code IS NULL")
+            .bind(key)
+            .execute(&self.db.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn is_mutating(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+// Middleware: mutating requests carrying an Idempotency-Key are applied once;
+// retries get the stored response back. The key is claimed before the request
+// runs, so a retry arriving meanwhile waits for its response rather than
+// running it again. Failed responses are not stored so the client can retry
+// them for real.
+pub async fn replay_or_record(
+    State(repo): State<Arc<IdempotencyRepository>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let key = match request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(key) if is_mutating(request.method()) => key.to_string(),
+        _ => return next.run(request).await,
+    };
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    // Set by the auth layer, which runs before this one
+    let user_id = request
+        .extensions()
+        .get::<AuthUser>()
+        .map(|AuthUser(user_id)| user_id.clone());
+
+    let deadline = Instant::now() + PENDING_WAIT;
+    loop {
+        match repo.claim(&key, user_id.as_deref(), &method, &path).await {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => return ApiError::from(e).into_response(),
+        }
+        match repo.find(&key).await {
+            Ok(Some(stored))
+                if !stored.is_pending() || !stored.is_for(user_id.as_deref(), &method, &path) =>
+            {
+                return stored.into_response_for(user_id.as_deref(), &method, &path)
+            }
+            Ok(Some(_)) if Instant::now() >= deadline => {
+                return ApiError::conflict(
+                    "A request with this idempotency key is still being processed",
+                )
+                .into_response()
+            }
+            // Still running, or given up since, in which case it's claimed again
+            Ok(_) => tokio::time::sleep(PENDING_POLL).await,
+            Err(e) => return ApiError::from(e).into_response(),
+        }
+    }
+
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        if let Err(e) = repo.release(&key).await {
+            eprintln!("Failed to release idempotency key {key}: {e}");
+        }
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_RESPONSE_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return ApiError::internal(format!("Failed to read response body: {e}")).into_response()
+        }
+    };
+
+    // The change has been applied and the key stays claimed, so a retry can't
+    // apply it again even if its response isn't stored; the retry is told the
+    // request is still being processed instead of getting it replayed
+    if let Err(e) = repo.save(&key, parts.status, &bytes).await {
+        eprintln!("Failed to record the response for idempotency key {key}: {e}");
+    }
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+
+    fn create_row(status_code: i32, response_body: &[u8]) -> IdempotencyKeyRow {
+        IdempotencyKeyRow {
+            user_id: Some("user-1".to_string()),
+            method: "POST".to_string(),
+            path: "/api/goals".to_string(),
+            status_code: Some(status_code),
+            response_body: Some(response_body.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_is_mutating() {
+        assert!(is_mutating(&Method::POST));
+        assert!(is_mutating(&Method::PUT));
+        assert!(is_mutating(&Method::DELETE));
+        assert!(!is_mutating(&Method::GET));
+        assert!(!is_mutating(&Method::OPTIONS));
+    }
+
+    #[test]
+    fn test_replay_returns_stored_response() {
+        let response = create_row(200, br#"{"id":"goal-1"}"#).into_response_for(
+            Some("user-1"),
+            "POST",
+            "/api/goals",
+        );
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[REPLAYED_HEADER], "true");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn test_replay_of_empty_response() {
+        let response = create_row(204, b"").into_response_for(Some("user-1"), "POST", "/api/goals");
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.headers().get(header::CONTENT_TYPE).is_none());
+    }
+
+    #[test]
+    fn test_replay_rejects_key_reused_for_another_request() {
+        let response =
+            create_row(200, b"{}").into_response_for(Some("user-1"), "PUT", "/api/goals/goal-1");
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_replay_rejects_key_reused_by_another_user() {
+        let response =
+            create_row(200, b"{}").into_response_for(Some("user-2"), "POST", "/api/goals");
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    // A POST /api/goals that counts how often it runs, taking a moment so
+    // requests overlap
+    fn counting_app(repo: Arc<IdempotencyRepository>, runs: Arc<AtomicUsize>) -> Router {
+        Router::new()
+            .route(
+                "/api/goals",
+                post(move || async move {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    StatusCode::CREATED
+                }),
+            )
+            .layer(middleware::from_fn_with_state(repo, replay_or_record))
+    }
+
+    fn keyed_post(key: &str) -> Request {
+        Request::post("/api/goals")
+            .header(IDEMPOTENCY_KEY_HEADER, key)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_with_one_key_run_once() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = counting_app(Arc::new(IdempotencyRepository::new(pool)), runs.clone());
+        let key = shared::generate_id();
+
+        let (first, second) = tokio::join!(
+            app.clone().oneshot(keyed_post(&key)),
+            app.oneshot(keyed_post(&key))
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert_eq!(second.status(), StatusCode::CREATED);
+        let replayed = [&first, &second]
+            .iter()
+            .filter(|response| response.headers().contains_key(REPLAYED_HEADER))
+            .count();
+        assert_eq!(replayed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_failing_to_record_is_not_applied_again() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let repo = Arc::new(IdempotencyRepository::new(pool.clone()));
+        let key = shared::generate_id();
+        // The change goes through, then the database goes away before its
+        // response is saved
+        let closing = pool.clone();
+        let app = Router::new()
+            .route(
+                "/api/goals",
+                post(move || async move {
+                    closing.close().await;
+                    StatusCode::CREATED
+                }),
+            )
+            .layer(middleware::from_fn_with_state(repo, replay_or_record));
+        let response = app.oneshot(keyed_post(&key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let runs = Arc::new(AtomicUsize::new(0));
+        let app = counting_app(Arc::new(IdempotencyRepository::new(pool)), runs.clone());
+        let response = app.oneshot(keyed_post(&key)).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 0);
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_gives_up_its_key() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let repo = Arc::new(IdempotencyRepository::new(pool));
+        let key = shared::generate_id();
+        let app = Router::new()
+            .route(
+                "/api/goals",
+                post(|| async { StatusCode::SERVICE_UNAVAILABLE }),
+            )
+            .layer(middleware::from_fn_with_state(
+                repo.clone(),
+                replay_or_record,
+            ));
+        let response = app.oneshot(keyed_post(&key)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let response = counting_app(repo, runs.clone())
+            .oneshot(keyed_post(&key))
+            .await
+            .unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+}
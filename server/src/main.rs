@@ -1,32 +1,40 @@
-use axum::{response::Json, routing::get, Router};
-use serde::Serialize;
+use axum::{middleware, response::Json, routing::get, Extension, Router};
 use serde_json::json;
 use sqlx::PgPool;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
+use auth::{TokenKeys, UserRepository};
+use changes::ChangeRepository;
+use export::ExportRepository;
 use goals::GoalRepository;
+use idempotency::IdempotencyRepository;
+use plans::PlanRepository;
+use practice_log::PracticeLogRepository;
 use sessions::SessionRepository;
+use stats::StatsRepository;
 use studies::StudyRepository;
+use templates::TemplateRepository;
+use trash::TrashRepository;
 
+mod auth;
+mod changes;
+mod error;
+mod export;
 mod goals;
+mod idempotency;
+mod plans;
+mod practice_log;
 mod repository;
 mod sessions;
+mod stats;
 mod studies;
+mod study_sessions;
+mod templates;
+mod trash;
 
-#[derive(Debug, Serialize)]
-pub struct ApiError {
-    message: String,
-}
-
-impl From<sqlx::Error> for ApiError {
-    fn from(err: sqlx::Error) -> Self {
-        ApiError {
-            message: err.to_string(),
-        }
-    }
-}
+pub use error::ApiError;
 
 async fn setup_database() -> Result<PgPool, sqlx::Error> {
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
@@ -45,14 +53,74 @@ async fn main() {
     dotenvy::dotenv().ok();
 
     let pool = setup_database().await.expect("Failed to setup database");
+    let auth_secret = std::env::var("AUTH_SECRET").expect("AUTH_SECRET must be set");
+    let token_keys = Arc::new(TokenKeys::new(auth_secret.as_bytes()));
 
     // Create repositories
     let goal_repo = Arc::new(GoalRepository::new(pool.clone()));
     let study_repo = Arc::new(StudyRepository::new(pool.clone()));
-    let session_repo = Arc::new(SessionRepository::new(pool));
+    let session_repo = Arc::new(SessionRepository::new(pool.clone()));
+    let plan_repo = Arc::new(PlanRepository::new(pool.clone()));
+    let template_repo = Arc::new(TemplateRepository::new(pool.clone()));
+    let change_repo = Arc::new(ChangeRepository::new(
+        pool.clone(),
+        goal_repo.clone(),
+        study_repo.clone(),
+        session_repo.clone(),
+        plan_repo.clone(),
+        template_repo.clone(),
+    ));
+    let trash_repo = Arc::new(TrashRepository::new(
+        goal_repo.clone(),
+        study_repo.clone(),
+        session_repo.clone(),
+    ));
+    let export_repo = Arc::new(ExportRepository::new(
+        pool.clone(),
+        goal_repo.clone(),
+        study_repo.clone(),
+        session_repo.clone(),
+    ));
+    let practice_log_repo = Arc::new(PracticeLogRepository::new(pool.clone(), study_repo.clone()));
+    let stats_repo = Arc::new(StatsRepository::new(pool.clone()));
+    let user_repo = Arc::new(UserRepository::new(pool.clone()));
+    let idempotency_repo = Arc::new(IdempotencyRepository::new(pool));
+
+    // Data from before accounts existed belongs to no one until an operator
+    // names its owner
+    if let Ok(email) = std::env::var("LEGACY_DATA_OWNER") {
+        let adopted = user_repo
+            .adopt_legacy_data(&email)
+            .await
+            .expect("LEGACY_DATA_OWNER must be the email of an existing account");
+        println!("Gave {adopted} rows created before accounts existed to {email}");
+    }
+
+    trash::spawn_purge_job(trash_repo.clone(), trash::retention_from_env());
 
     let health = || async { Json(json!({ "status": "ok" })) };
 
+    // Everything under /api belongs to the signed in user. Layers run bottom
+    // up, so the token is checked before idempotency keys are looked up.
+    let api = Router::new()
+        .merge(goals::routes().with_state(goal_repo))
+        .merge(studies::routes().with_state(study_repo))
+        .merge(sessions::routes().with_state(session_repo.clone()))
+        .merge(study_sessions::routes().with_state(session_repo))
+        .merge(plans::routes().with_state(plan_repo))
+        .merge(templates::routes().with_state(template_repo))
+        .merge(changes::routes().with_state(change_repo))
+        .merge(trash::routes().with_state(trash_repo))
+        .merge(export::routes().with_state(export_repo))
+        .merge(practice_log::routes().with_state(practice_log_repo))
+        .merge(stats::routes().with_state(stats_repo))
+        .merge(auth::me_routes().with_state(user_repo.clone()))
+        .layer(middleware::from_fn_with_state(
+            idempotency_repo,
+            idempotency::replay_or_record,
+        ))
+        .layer(middleware::from_extractor::<auth::AuthUser>());
+
     let app = Router::new()
         .route(
             "/",
@@ -61,9 +129,9 @@ async fn main() {
             }),
         )
         .route("/health", get(health))
-        .nest("/api", goals::routes().with_state(goal_repo))
-        .nest("/api", studies::routes().with_state(study_repo))
-        .nest("/api", sessions::routes().with_state(session_repo))
+        .nest("/auth", auth::routes().with_state(user_repo))
+        .nest("/api", api)
+        .layer(Extension(token_keys))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -94,18 +162,14 @@ mod tests {
     #[test]
     fn test_api_error_from_string() {
         let error_msg = "Test error message";
-        let api_error = ApiError {
-            message: error_msg.to_string(),
-        };
+        let api_error = ApiError::internal(error_msg.to_string());
 
         assert_eq!(api_error.message, error_msg);
     }
 
     #[test]
     fn test_api_error_debug() {
-        let api_error = ApiError {
-            message: "Debug test".to_string(),
-        };
+        let api_error = ApiError::internal("Debug test".to_string());
 
         let debug_str = format!("{api_error:?}");
         assert!(debug_str.contains("Debug test"));
@@ -113,9 +177,7 @@ mod tests {
 
     #[test]
     fn test_api_error_serialization() {
-        let api_error = ApiError {
-            message: "Serialization test".to_string(),
-        };
+        let api_error = ApiError::internal("Serialization test".to_string());
 
         let serialized = serde_json::to_string(&api_error).unwrap();
         assert!(serialized.contains("Serialization test"));
@@ -0,0 +1,536 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
+use shared::{EntityKind, PracticePlan, Recurrence, Weekday};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    repository::{
+        check_version, format_timestamp, missing_or_stale, parse_date, record_tombstone,
+        save_links, Database, RepositoryError, RepositoryResult, PLAN_GOALS, PLAN_STUDIES,
+    },
+    ApiError,
+};
+
+// Type alias to simplify complex return type
+type PlanRowData = (Vec<&'static str>, NaiveTime, NaiveDate, Option<NaiveDate>);
+
+// Database row struct
+#[derive(FromRow)]
+pub struct PlanRow {
+    pub id: String,
+    pub name: String,
+    pub intention: String,
+    pub weekdays: Vec<String>,
+    pub time_of_day: NaiveTime,
+    pub time_zone: String,
+    pub goal_ids: Vec<String>,  // from plan_goals, in order
+    pub study_ids: Vec<String>, // from plan_studies, in order
+    pub planned_minutes: i32,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub version: i32,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl PlanRow {
+    pub fn into_plan(self) -> RepositoryResult<PracticePlan> {
+        let weekdays = self
+            .weekdays
+            .iter()
+            .map(|name| weekday_from_name(name))
+            .collect::<RepositoryResult<Vec<_>>>()?;
+
+        Ok(PracticePlan {
+            id: self.id,
+            name: self.name,
+            recurrence: Recurrence {
+                weekdays,
+                time_of_day: self.time_of_day.format("%H:%M").to_string(),
+                time_zone: self.time_zone,
+            },
+            intention: self.intention,
+            goal_ids: self.goal_ids,
+            study_ids: self.study_ids,
+            planned_minutes: self.planned_minutes as u32,
+            start_date: self.start_date.to_string(),
+            end_date: self.end_date.map(|d| d.to_string()),
+            version: self.version as u32,
+            updated_at: self.updated_at.map(format_timestamp),
+        })
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "Monday",
+        Weekday::Tuesday => "Tuesday",
+        Weekday::Wednesday => "Wednesday",
+        Weekday::Thursday => "Thursday",
+        Weekday::Friday => "Friday",
+        Weekday::Saturday => "Saturday",
+        Weekday::Sunday => "Sunday",
+    }
+}
+
+fn weekday_from_name(name: &str) -> RepositoryResult<Weekday> {
+    match name {
+        "Monday" => Ok(Weekday::Monday),
+        "Tuesday" => Ok(Weekday::Tuesday),
+        "Wednesday" => Ok(Weekday::Wednesday),
+        "Thursday" => Ok(Weekday::Thursday),
+        "Friday" => Ok(Weekday::Friday),
+        "Saturday" => Ok(Weekday::Saturday),
+        "Sunday" => Ok(Weekday::Sunday),
+        _ => Err(RepositoryError::ValidationError(format!(
+            "Unknown weekday: {name}"
+        ))),
+    }
+}
+
+// Request types
+#[derive(Debug, Deserialize)]
+pub struct CreatePlanRequest {
+    // Client generated id, so optimistic local records keep their identity
+    pub id: Option<String>,
+    pub name: String,
+    pub recurrence: Recurrence,
+    #[serde(default)]
+    pub intention: String,
+    #[serde(default)]
+    pub goal_ids: Vec<String>,
+    #[serde(default)]
+    pub study_ids: Vec<String>,
+    pub planned_minutes: u32,
+    pub start_date: String,
+    pub end_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePlanRequest {
+    pub name: Option<String>,
+    pub recurrence: Option<Recurrence>,
+    pub intention: Option<String>,
+    pub goal_ids: Option<Vec<String>>,
+    pub study_ids: Option<Vec<String>>,
+    pub planned_minutes: Option<u32>,
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    // The version the client's edit was based on, stale edits are rejected
+    pub version: Option<u32>,
+}
+
+// Simple Plan repository - no traits, just methods
+pub struct PlanRepository {
+    db: Database,
+}
+
+impl PlanRepository {
+    pub fn new(pool: crate::repository::DbPool) -> Self {
+        Self {
+            db: Database::new(pool),
+        }
+    }
+
+    // Checks a plan and turns it into its weekdays, time and date columns
+    fn plan_to_row_data(plan: &PracticePlan) -> RepositoryResult<PlanRowData> {
+        if plan.planned_minutes == 0 {
+            return Err(RepositoryError::ValidationError(
+                "planned_minutes must be greater than 0".to_string(),
+            ));
+        }
+        let time_of_day = NaiveTime::parse_from_str(&plan.recurrence.time_of_day, "%H:%M")
+            .map_err(|_| {
+                RepositoryError::ValidationError(format!(
+                    "time_of_day must be a time like 18:30, got {}",
+                    plan.recurrence.time_of_day
+                ))
+            })?;
+        if plan.recurrence.zone().is_none() {
+            return Err(RepositoryError::ValidationError(format!(
+                "Unknown time zone: {}",
+                plan.recurrence.time_zone
+            )));
+        }
+        let start_date = parse_date("start_date", &plan.start_date)?;
+        let end_date = plan
+            .end_date
+            .as_deref()
+            .map(|d| parse_date("end_date", d))
+            .transpose()?;
+        if end_date.is_some_and(|end| end < start_date) {
+            return Err(RepositoryError::ValidationError(
+                "end_date must not be before start_date".to_string(),
+            ));
+        }
+
+        let mut weekdays: Vec<&'static str> = Vec::new();
+        for weekday in &plan.recurrence.weekdays {
+            let name = weekday_name(*weekday);
+            if !weekdays.contains(&name) {
+                weekdays.push(name);
+            }
+        }
+
+        Ok((weekdays, time_of_day, start_date, end_date))
+    }
+
+    async fn save_plan_links(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        plan: &PracticePlan,
+    ) -> RepositoryResult<()> {
+        save_links(tx, &PLAN_GOALS, user_id, &plan.id, &plan.goal_ids).await?;
+        save_links(tx, &PLAN_STUDIES, user_id, &plan.id, &plan.study_ids).await
+    }
+
+    pub async fn create(&self, user_id: &str, plan: &PracticePlan) -> RepositoryResult<()> {
+        let (weekdays, time_of_day, start_date, end_date) = Self::plan_to_row_data(plan)?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO plans (id, name, intention, weekdays, time_of_day, time_zone, planned_minutes, start_date, end_date, user_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(&plan.id)
+        .bind(&plan.name)
+        .bind(&plan.intention)
+        .bind(&weekdays)
+        .bind(time_of_day)
+        .bind(&plan.recurrence.time_zone)
+        .bind(plan.planned_minutes as i32)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::save_plan_links(&mut tx, user_id, plan).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<PracticePlan>> {
+        let row = sqlx::query_as!(
+            PlanRow,
+            r#"SELECT id, name, intention, weekdays, time_of_day, time_zone,
+             ARRAY(SELECT goal_id FROM plan_goals JOIN goals ON goals.id = goal_id WHERE plan_id = plans.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM plan_studies JOIN studies ON studies.id = study_id WHERE plan_id = plans.id AND studies.deleted_at IS NULL ORDER BY position) AS "study_ids!",
+             planned_minutes, start_date, end_date, version, updated_at
+             FROM plans WHERE id = $1 AND user_id = $2"#,
+            id,
+            user_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        row.map(PlanRow::into_plan).transpose()
+    }
+
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Vec<PracticePlan>> {
+        let rows = sqlx::query_as!(
+            PlanRow,
+            r#"SELECT id, name, intention, weekdays, time_of_day, time_zone,
+             ARRAY(SELECT goal_id FROM plan_goals JOIN goals ON goals.id = goal_id WHERE plan_id = plans.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM plan_studies JOIN studies ON studies.id = study_id WHERE plan_id = plans.id AND studies.deleted_at IS NULL ORDER BY position) AS "study_ids!",
+             planned_minutes, start_date, end_date, version, updated_at
+             FROM plans WHERE user_id = $1 ORDER BY created_at DESC"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        rows.into_iter().map(PlanRow::into_plan).collect()
+    }
+
+    pub async fn update(
+        &self,
+        user_id: &str,
+        plan: &PracticePlan,
+    ) -> RepositoryResult<PracticePlan> {
+        let (weekdays, time_of_day, start_date, end_date) = Self::plan_to_row_data(plan)?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        let stamp: Option<(i32, Option<NaiveDateTime>)> = sqlx::query_as(
+            "UPDATE plans SET name = $2, intention = $3, weekdays = $4, time_of_day = $5,
+             time_zone = $6, planned_minutes = $7, start_date = $8, end_date = $9,
+             version = version + 1, updated_at = CURRENT_TIMESTAMP
+             WHERE id = $1 AND version = $10 AND user_id = $11
+             RETURNING version, updated_at",
+        )
+        .bind(&plan.id)
+        .bind(&plan.name)
+        .bind(&plan.intention)
+        .bind(&weekdays)
+        .bind(time_of_day)
+        .bind(&plan.recurrence.time_zone)
+        .bind(plan.planned_minutes as i32)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(plan.version as i32)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((version, updated_at)) = stamp else {
+            return Err(missing_or_stale(&mut *tx, "plans", user_id, &plan.id, plan.version).await);
+        };
+
+        Self::save_plan_links(&mut tx, user_id, plan).await?;
+        tx.commit().await?;
+
+        Ok(PracticePlan {
+            version: version as u32,
+            updated_at: updated_at.map(format_timestamp),
+            ..plan.clone()
+        })
+    }
+
+    pub async fn find_changed_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<PracticePlan>> {
+        let rows = sqlx::query_as!(
+            PlanRow,
+            r#"SELECT id, name, intention, weekdays, time_of_day, time_zone,
+             ARRAY(SELECT goal_id FROM plan_goals JOIN goals ON goals.id = goal_id WHERE plan_id = plans.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM plan_studies JOIN studies ON studies.id = study_id WHERE plan_id = plans.id AND studies.deleted_at IS NULL ORDER BY position) AS "study_ids!",
+             planned_minutes, start_date, end_date, version, updated_at
+             FROM plans WHERE user_id = $1 AND change_xid >= $2 ORDER BY updated_at"#,
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        rows.into_iter().map(PlanRow::into_plan).collect()
+    }
+
+    pub async fn delete(&self, user_id: &str, id: &str) -> RepositoryResult<bool> {
+        let mut tx = self.db.pool.begin().await?;
+
+        // The plan's sessions are kept but lose their plan_id, so syncing
+        // clients need to see them change
+        sqlx::query(
+            "UPDATE sessions SET version = version + 1, updated_at = CURRENT_TIMESTAMP
+             WHERE plan_id = $1 AND user_id = $2",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM plans WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() > 0 {
+            record_tombstone(&mut *tx, user_id, EntityKind::Plan, id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// HTTP Handlers
+async fn create_plan(
+    State(plan_repo): State<Arc<PlanRepository>>,
+    AuthUser(user_id): AuthUser,
+    Json(req): Json<CreatePlanRequest>,
+) -> Result<Json<PracticePlan>, ApiError> {
+    let mut plan = PracticePlan::new(
+        req.name,
+        req.recurrence,
+        req.intention,
+        req.planned_minutes,
+        req.start_date,
+    );
+    plan.goal_ids = req.goal_ids;
+    plan.study_ids = req.study_ids;
+    plan.end_date = req.end_date;
+    if let Some(id) = req.id {
+        plan.id = id;
+    }
+
+    plan_repo.create(&user_id, &plan).await?;
+
+    Ok(Json(plan))
+}
+
+async fn get_plans(
+    State(plan_repo): State<Arc<PlanRepository>>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<Vec<PracticePlan>>, ApiError> {
+    let plans = plan_repo.find_all(&user_id).await?;
+
+    Ok(Json(plans))
+}
+
+async fn get_plan(
+    State(plan_repo): State<Arc<PlanRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PracticePlan>, ApiError> {
+    let plan = plan_repo.find_by_id(&user_id, &id).await?;
+
+    match plan {
+        Some(plan) => Ok(Json(plan)),
+        None => Err(ApiError::not_found("Plan not found")),
+    }
+}
+
+async fn update_plan(
+    State(plan_repo): State<Arc<PlanRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdatePlanRequest>,
+) -> Result<Json<PracticePlan>, ApiError> {
+    let Some(existing_plan) = plan_repo.find_by_id(&user_id, &id).await? else {
+        return Err(ApiError::not_found("Plan not found"));
+    };
+
+    check_version("Plan", req.version, existing_plan.version)?;
+
+    // Create updated plan with new values or existing ones
+    let updated_plan = PracticePlan {
+        id: existing_plan.id,
+        name: req.name.unwrap_or(existing_plan.name),
+        recurrence: req.recurrence.unwrap_or(existing_plan.recurrence),
+        intention: req.intention.unwrap_or(existing_plan.intention),
+        goal_ids: req.goal_ids.unwrap_or(existing_plan.goal_ids),
+        study_ids: req.study_ids.unwrap_or(existing_plan.study_ids),
+        planned_minutes: req.planned_minutes.unwrap_or(existing_plan.planned_minutes),
+        start_date: req.start_date.unwrap_or(existing_plan.start_date),
+        end_date: req.end_date.or(existing_plan.end_date),
+        version: existing_plan.version,
+        updated_at: existing_plan.updated_at,
+    };
+
+    let updated_plan = plan_repo.update(&user_id, &updated_plan).await?;
+
+    Ok(Json(updated_plan))
+}
+
+async fn delete_plan(
+    State(plan_repo): State<Arc<PlanRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = plan_repo.delete(&user_id, &id).await?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("Plan not found"))
+    }
+}
+
+pub fn routes() -> Router<Arc<PlanRepository>> {
+    Router::new()
+        .route("/plans", get(get_plans).post(create_plan))
+        .route(
+            "/plans/{id}",
+            get(get_plan).put(update_plan).delete(delete_plan),
+        )
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_plan() -> PracticePlan {
+        PracticePlan::new(
+            "Evenings".to_string(),
+            Recurrence {
+                weekdays: vec![Weekday::Monday, Weekday::Wednesday, Weekday::Monday],
+                time_of_day: "18:30".to_string(),
+                time_zone: "Europe/London".to_string(),
+            },
+            "Scales then repertoire".to_string(),
+            30,
+            "2025-04-28".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_plan_row_conversion() {
+        let row = PlanRow {
+            id: "plan-1".to_string(),
+            name: "Evenings".to_string(),
+            intention: "Scales".to_string(),
+            weekdays: vec!["Monday".to_string(), "Friday".to_string()],
+            time_of_day: NaiveTime::from_hms_opt(18, 30, 0).unwrap(),
+            time_zone: "Europe/London".to_string(),
+            goal_ids: vec!["goal-1".to_string()],
+            study_ids: vec!["study-1".to_string(), "study-2".to_string()],
+            planned_minutes: 30,
+            start_date: NaiveDate::from_ymd_opt(2025, 4, 28).unwrap(),
+            end_date: None,
+            version: 3,
+            updated_at: None,
+        };
+
+        let plan = row.into_plan().unwrap();
+        assert_eq!(
+            plan.recurrence,
+            Recurrence {
+                weekdays: vec![Weekday::Monday, Weekday::Friday],
+                time_of_day: "18:30".to_string(),
+                time_zone: "Europe/London".to_string(),
+            }
+        );
+        assert_eq!(plan.study_ids, vec!["study-1", "study-2"]);
+        assert_eq!(plan.start_date, "2025-04-28");
+        assert_eq!(plan.version, 3);
+    }
+
+    #[test]
+    fn test_plan_to_row_data() {
+        let (weekdays, time_of_day, start_date, end_date) =
+            PlanRepository::plan_to_row_data(&create_plan()).unwrap();
+        assert_eq!(weekdays, vec!["Monday", "Wednesday"]);
+        assert_eq!(time_of_day, NaiveTime::from_hms_opt(18, 30, 0).unwrap());
+        assert_eq!(start_date, NaiveDate::from_ymd_opt(2025, 4, 28).unwrap());
+        assert_eq!(end_date, None);
+    }
+
+    #[test]
+    fn test_plan_to_row_data_rejects_invalid_plans() {
+        let mut plan = create_plan();
+        plan.recurrence.time_of_day = "half six".to_string();
+        assert!(PlanRepository::plan_to_row_data(&plan).is_err());
+
+        let mut plan = create_plan();
+        plan.recurrence.time_zone = "Mars/Olympus_Mons".to_string();
+        assert!(PlanRepository::plan_to_row_data(&plan).is_err());
+
+        let mut plan = create_plan();
+        plan.planned_minutes = 0;
+        assert!(PlanRepository::plan_to_row_data(&plan).is_err());
+
+        let mut plan = create_plan();
+        plan.end_date = Some("2025-04-01".to_string());
+        assert!(PlanRepository::plan_to_row_data(&plan).is_err());
+    }
+}
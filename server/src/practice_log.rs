@@ -0,0 +1,485 @@
+use axum::{
+    extract::{DefaultBodyLimit, Query, State},
+    response::Json,
+    routing::post,
+    Router,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{
+    utils::derived_id, PracticeSession, SessionState, Study, StudySession, StudySessionState,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    export::IMPORT_BODY_LIMIT,
+    repository::{format_instant, Database, RepositoryError, RepositoryResult},
+    sessions::SessionRepository,
+    studies::StudyRepository,
+    ApiError,
+};
+
+// Spreadsheets give the day but not the time, so a day's first session starts
+// at noon UTC, which is the same day in most time zones, and the rest follow it
+const START_HOUR: u32 = 12;
+
+/// Which of the spreadsheet's columns holds what, by the header in its
+/// first row
+#[derive(Debug, Deserialize)]
+pub struct ColumnMapping {
+    pub date: String,
+    pub minutes: String,
+    pub piece: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    // chrono format of the dates, e.g. "%d/%m/%Y"
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PracticeLogRequest {
+    pub columns: ColumnMapping,
+    pub csv: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PracticeLogQuery {
+    // Nothing is saved unless this is turned off
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+/// A spreadsheet row that makes a session
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoggedPractice {
+    pub row: usize, // as numbered in the spreadsheet, the header being row 1
+    pub date: NaiveDate,
+    pub duration_in_seconds: u32,
+    pub piece: String,
+    pub notes: Option<String>,
+    // When the previous row on the same date ended, or noon UTC for the first
+    pub start_time: DateTime<Utc>,
+    // The study practiced, None in a dry run when it would be created
+    pub study_id: Option<String>,
+    // Set once the session has been created
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+/// What an import of a practice log did, or would do in a dry run
+#[derive(Debug, Default, Serialize)]
+pub struct PracticeLogReport {
+    pub dry_run: bool,
+    pub sessions: Vec<LoggedPractice>,
+    // Pieces that matched a study the user already has, by name
+    pub existing_studies: Vec<Study>,
+    // Pieces without a study, which are (or would be) created
+    pub new_studies: Vec<String>,
+    pub skipped: Vec<SkippedRow>,
+    // Rows whose session an earlier import already created, which are left out
+    pub already_imported: Vec<usize>,
+}
+
+/// Reads the spreadsheet's rows into practice, setting aside rows that
+/// can't be read. Fails only when a mapped column isn't there.
+pub fn parse_practice_log(
+    csv: &str,
+    columns: &ColumnMapping,
+) -> RepositoryResult<(Vec<LoggedPractice>, Vec<SkippedRow>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(csv.as_bytes());
+    let headers = reader
+        .headers()
+        .map_err(|e| RepositoryError::ValidationError(format!("Failed to read CSV header: {e}")))?
+        .clone();
+    let column = |name: &str| {
+        headers.iter().position(|h| h == name).ok_or_else(|| {
+            RepositoryError::ValidationError(format!("CSV has no column named '{name}'"))
+        })
+    };
+    let date_column = column(&columns.date)?;
+    let minutes_column = column(&columns.minutes)?;
+    let piece_column = column(&columns.piece)?;
+    let notes_column = columns.notes.as_deref().map(column).transpose()?;
+
+    let mut logged = Vec::new();
+    let mut skipped = Vec::new();
+    // Where the next session on each date starts
+    let mut next_start: HashMap<NaiveDate, DateTime<Utc>> = HashMap::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let parsed = record
+            .map_err(|e| format!("can't be read: {e}"))
+            .and_then(|record| {
+                let field = |column: usize| record.get(column).unwrap_or_default();
+                let date = NaiveDate::parse_from_str(field(date_column), &columns.date_format)
+                    .map_err(|_| format!("'{}' isn't a date", field(date_column)))?;
+                let duration_in_seconds = parse_minutes(field(minutes_column))?;
+                let piece = field(piece_column);
+                if piece.is_empty() {
+                    return Err("has no piece".to_string());
+                }
+                let notes = notes_column
+                    .map(field)
+                    .filter(|notes| !notes.is_empty())
+                    .map(str::to_string);
+
+                Ok((date, duration_in_seconds, piece.to_string(), notes))
+            });
+        match parsed {
+            Ok((date, duration_in_seconds, piece, notes)) => {
+                let start_time = *next_start.entry(date).or_insert_with(|| {
+                    date.and_hms_opt(START_HOUR, 0, 0)
+                        .expect("START_HOUR is a valid hour")
+                        .and_utc()
+                });
+                next_start.insert(
+                    date,
+                    start_time + chrono::Duration::seconds(i64::from(duration_in_seconds)),
+                );
+                logged.push(LoggedPractice {
+                    row,
+                    date,
+                    duration_in_seconds,
+                    piece,
+                    notes,
+                    start_time,
+                    study_id: None,
+                    session_id: None,
+                });
+            }
+            Err(reason) => skipped.push(SkippedRow { row, reason }),
+        }
+    }
+
+    Ok((logged, skipped))
+}
+
+// Whole or decimal minutes, e.g. "30" or "12.5", in seconds
+fn parse_minutes(minutes: &str) -> Result<u32, String> {
+    let not_minutes = || format!("'{minutes}' isn't a number of minutes");
+    let value: f64 = minutes.parse().map_err(|_| not_minutes())?;
+    let seconds = (value * 60.0).round();
+    if !seconds.is_finite() || seconds < 1.0 || seconds > f64::from(u32::MAX) {
+        return Err(not_minutes());
+    }
+    Ok(seconds as u32)
+}
+
+/// The id of a row's session, the same however often the spreadsheet is
+/// imported. `occurrence` counts the earlier rows with the same contents, so
+/// identical rows still make a session each.
+pub fn logged_session_id(user_id: &str, practice: &LoggedPractice, occurrence: usize) -> String {
+    derived_id(&format!(
+        "practice-log/{user_id}/{}/{}/{}/{}/{occurrence}",
+        practice.date,
+        practice.piece,
+        practice.duration_in_seconds,
+        practice.notes.as_deref().unwrap_or_default()
+    ))
+}
+
+/// An Ended session with one completed study session for the piece
+pub fn logged_session(
+    practice: &LoggedPractice,
+    session_id: &str,
+    study_id: &str,
+) -> PracticeSession {
+    let start = practice.start_time;
+    let end = start + chrono::Duration::seconds(i64::from(practice.duration_in_seconds));
+    let (start_time, end_time) = (format_instant(start), format_instant(end));
+
+    let mut session = PracticeSession::new(Vec::new(), practice.piece.clone());
+    session.id = session_id.to_string();
+    session.notes = practice.notes.clone();
+    let mut study_session = StudySession::new(study_id.to_string(), session.id.clone());
+    study_session.id = derived_id(&format!("session/{session_id}/{study_id}"));
+    study_session.state = StudySessionState::Completed {
+        start_time: start_time.clone(),
+        end_time: end_time.clone(),
+        elapsed_seconds: practice.duration_in_seconds,
+    };
+    session.study_sessions.push(study_session);
+    session.state = SessionState::Ended {
+        start_time,
+        end_time,
+        duration_in_seconds: practice.duration_in_seconds,
+    };
+    session
+}
+
+// Importing spreadsheets across the other repositories - no traits, just methods
+pub struct PracticeLogRepository {
+    db: Database,
+    studies: Arc<StudyRepository>,
+}
+
+impl PracticeLogRepository {
+    pub fn new(pool: crate::repository::DbPool, studies: Arc<StudyRepository>) -> Self {
+        Self {
+            db: Database::new(pool),
+            studies,
+        }
+    }
+
+    /// Matches each piece to a study by name, then, unless this is a dry
+    /// run, creates the missing studies and a session per row. Rows an
+    /// earlier import already created are reported rather than created
+    /// again. Everything is saved in one transaction, so an import that fails
+    /// saves none of it.
+    pub async fn import(
+        &self,
+        user_id: &str,
+        request: &PracticeLogRequest,
+        dry_run: bool,
+    ) -> RepositoryResult<PracticeLogReport> {
+        let (sessions, skipped) = parse_practice_log(&request.csv, &request.columns)?;
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        let session_ids: Vec<String> = sessions
+            .iter()
+            .map(|practice| {
+                let occurrence = occurrences
+                    .entry(logged_session_id(user_id, practice, 0))
+                    .or_default();
+                *occurrence += 1;
+                logged_session_id(user_id, practice, *occurrence - 1)
+            })
+            .collect();
+        let mut tx = self.db.pool.begin().await?;
+
+        // Trashed sessions count too, or emptying the trash would bring them back
+        let imported: Vec<String> =
+            sqlx::query_scalar("SELECT id FROM sessions WHERE user_id = $1 AND id = ANY($2)")
+                .bind(user_id)
+                .bind(&session_ids)
+                .fetch_all(&mut *tx)
+                .await?;
+
+        // By lowercase name, the first spelling in the spreadsheet naming new ones
+        let mut study_ids: HashMap<String, Option<String>> = HashMap::new();
+        let mut report = PracticeLogReport {
+            dry_run,
+            skipped,
+            ..PracticeLogReport::default()
+        };
+        for practice in &sessions {
+            let key = practice.piece.to_lowercase();
+            if study_ids.contains_key(&key) {
+                continue;
+            }
+            let existing = self
+                .studies
+                .find_by_name_pattern(user_id, &practice.piece)
+                .await?
+                .into_iter()
+                .find(|study| study.name.to_lowercase() == key);
+            match existing {
+                Some(study) => {
+                    study_ids.insert(key, Some(study.id.clone()));
+                    report.existing_studies.push(study);
+                }
+                None if dry_run => {
+                    study_ids.insert(key, None);
+                    report.new_studies.push(practice.piece.clone());
+                }
+                None => {
+                    let study = Study::new(practice.piece.clone(), None);
+                    StudyRepository::create_in(&mut tx, user_id, &study).await?;
+                    study_ids.insert(key, Some(study.id));
+                    report.new_studies.push(practice.piece.clone());
+                }
+            }
+        }
+
+        for (mut practice, session_id) in sessions.into_iter().zip(session_ids) {
+            if imported.contains(&session_id) {
+                report.already_imported.push(practice.row);
+                continue;
+            }
+            practice.study_id = study_ids[&practice.piece.to_lowercase()].clone();
+            if let (false, Some(study_id)) = (dry_run, &practice.study_id) {
+                let session = logged_session(&practice, &session_id, study_id);
+                // Another import of the same spreadsheet may have got there first
+                if !SessionRepository::create_if_new_in(&mut tx, user_id, &session).await? {
+                    report.already_imported.push(practice.row);
+                    continue;
+                }
+                practice.session_id = Some(session_id);
+            }
+            report.sessions.push(practice);
+        }
+        tx.commit().await?;
+
+        Ok(report)
+    }
+}
+
+// HTTP Handlers
+async fn import_practice_log(
+    State(practice_log_repo): State<Arc<PracticeLogRepository>>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<PracticeLogQuery>,
+    Json(request): Json<PracticeLogRequest>,
+) -> Result<Json<PracticeLogReport>, ApiError> {
+    let report = practice_log_repo
+        .import(&user_id, &request, query.dry_run)
+        .await?;
+
+    Ok(Json(report))
+}
+
+pub fn routes() -> Router<Arc<PracticeLogRepository>> {
+    Router::new().route(
+        "/import/practice-log",
+        post(import_practice_log).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+    )
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> ColumnMapping {
+        serde_json::from_value(serde_json::json!({
+            "date": "Date",
+            "minutes": "Minutes",
+            "piece": "Piece",
+            "notes": "Notes",
+            "date_format": "%d/%m/%Y"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_practice_log_rows_are_read_or_skipped() {
+        let csv = "Date,Minutes,Piece,Notes,Teacher\n\
+                   01/05/2025,30,Nocturne op. 9,\"Slow, then up to tempo\",Ann\n\
+                   02/05/2025,12.5,  Scales  ,,Ann\n\
+                   May 3rd,20,Scales,,Ann\n\
+                   04/05/2025,0,Scales,,Ann\n\
+                   05/05/2025,15,,,Ann\n";
+
+        let (logged, skipped) = parse_practice_log(csv, &columns()).unwrap();
+        assert_eq!(logged.len(), 2);
+        assert_eq!(logged[0].row, 2);
+        assert_eq!(logged[0].date, NaiveDate::from_ymd_opt(2025, 5, 1).unwrap());
+        assert_eq!(logged[0].duration_in_seconds, 1800);
+        assert_eq!(logged[0].notes.as_deref(), Some("Slow, then up to tempo"));
+        assert_eq!(logged[1].piece, "Scales");
+        assert_eq!(logged[1].duration_in_seconds, 750);
+        assert_eq!(logged[1].notes, None);
+
+        let rows: Vec<usize> = skipped.iter().map(|s| s.row).collect();
+        assert_eq!(rows, vec![4, 5, 6]);
+        assert_eq!(skipped[0].reason, "'May 3rd' isn't a date");
+    }
+
+    #[test]
+    fn test_practice_log_needs_every_mapped_column() {
+        let error = parse_practice_log("Date,Minutes,Piece\n", &columns()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Validation error: CSV has no column named 'Notes'"
+        );
+    }
+
+    #[test]
+    fn test_practice_log_rows_on_a_date_follow_each_other() {
+        let csv = "Date,Minutes,Piece,Notes\n\
+                   01/05/2025,30,Scales,\n\
+                   02/05/2025,20,Scales,\n\
+                   01/05/2025,15,Nocturne,\n";
+
+        let (logged, _) = parse_practice_log(csv, &columns()).unwrap();
+        let starts: Vec<String> = logged
+            .iter()
+            .map(|practice| format_instant(practice.start_time))
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                "2025-05-01T12:00:00Z",
+                "2025-05-02T12:00:00Z",
+                "2025-05-01T12:30:00Z"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_logged_session_is_ended_with_its_duration() {
+        let (logged, _) = parse_practice_log(
+            "Date,Minutes,Piece,Notes\n01/05/2025,30,Nocturne,\n",
+            &columns(),
+        )
+        .unwrap();
+        let session_id = logged_session_id("user", &logged[0], 0);
+
+        let session = logged_session(&logged[0], &session_id, "study");
+        assert_eq!(session.id, session_id);
+        assert_eq!(session.duration_in_seconds(), Some(1800));
+        assert_eq!(session.start_time(), Some("2025-05-01T12:00:00Z"));
+        assert_eq!(session.end_time(), Some("2025-05-01T12:30:00Z"));
+        let study_session = &session.study_sessions[0];
+        assert_eq!(study_session.study_id, "study");
+        assert_eq!(study_session.session_id, session.id);
+        assert!(study_session.is_completed());
+    }
+
+    #[tokio::test]
+    async fn test_practice_log_imported_twice_creates_sessions_once() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let user_id = crate::repository::create_test_user(&pool).await;
+        let repo =
+            PracticeLogRepository::new(pool.clone(), Arc::new(StudyRepository::new(pool.clone())));
+        // The same practice twice on one day still makes two sessions
+        let request = PracticeLogRequest {
+            columns: columns(),
+            csv: "Date,Minutes,Piece,Notes\n\
+                  01/05/2025,30,Scales,\n\
+                  01/05/2025,30,Scales,\n"
+                .to_string(),
+        };
+
+        let first = repo.import(&user_id, &request, false).await.unwrap();
+        assert_eq!(first.sessions.len(), 2);
+        assert!(first.already_imported.is_empty());
+
+        let dry_run = repo.import(&user_id, &request, true).await.unwrap();
+        assert!(dry_run.sessions.is_empty());
+        assert_eq!(dry_run.already_imported, vec![2, 3]);
+
+        let second = repo.import(&user_id, &request, false).await.unwrap();
+        assert!(second.sessions.is_empty());
+        assert_eq!(second.already_imported, vec![2, 3]);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE user_id = $1")
+            .bind(&user_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+}
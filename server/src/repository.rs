@@ -1,3 +1,7 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Deserializer};
+use shared::{DeleteImpact, DeleteMode, EntityKind, SessionError};
 use sqlx::{Pool, Postgres};
 
 pub type DbPool = Pool<Postgres>;
@@ -13,16 +17,425 @@ pub enum RepositoryError {
     Serialization(#[from] serde_json::Error),
     #[error("Validation error: {0}")]
     ValidationError(String),
+    #[error("Version conflict: {0}")]
+    VersionConflict(String),
+    #[error("{0}")]
+    InvalidTransition(SessionError),
+    // A delete refused because other entities still refer to the target
+    #[error("{0}")]
+    Referenced(String),
+    // An id the client picked, or an email, that is already used by something else
+    #[error("{0}")]
+    Conflict(String),
 }
 
-impl From<RepositoryError> for crate::ApiError {
-    fn from(err: RepositoryError) -> Self {
-        crate::ApiError {
-            message: err.to_string(),
+impl RepositoryError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            RepositoryError::NotFound(_) | RepositoryError::Database(sqlx::Error::RowNotFound) => {
+                StatusCode::NOT_FOUND
+            }
+            RepositoryError::ValidationError(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            RepositoryError::VersionConflict(_)
+            | RepositoryError::InvalidTransition(_)
+            | RepositoryError::Referenced(_)
+            | RepositoryError::Conflict(_) => StatusCode::CONFLICT,
+            RepositoryError::Database(_) | RepositoryError::Serialization(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
         }
     }
 }
 
+/// Rejects an update based on an older version than the one stored
+pub fn check_version(entity: &str, expected: Option<u32>, current: u32) -> RepositoryResult<()> {
+    match expected {
+        Some(expected) if expected != current => Err(RepositoryError::VersionConflict(format!(
+            "{entity} is at version {current}, update was based on version {expected}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Explains why a versioned update matched no row: the entity is either gone
+/// or was changed since the version the update was based on
+pub async fn missing_or_stale<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    table: &str,
+    user_id: &str,
+    id: &str,
+    expected: u32,
+) -> RepositoryError {
+    let current: Result<Option<i32>, sqlx::Error> = sqlx::query_scalar(&format!(
+        "SELECT version FROM {table} WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .fetch_optional(executor)
+    .await;
+
+    match current {
+        Ok(Some(current)) => RepositoryError::VersionConflict(format!(
+            "{table} {id} is at version {current}, update was based on version {expected}"
+        )),
+        Ok(None) => RepositoryError::NotFound(format!("{table} with id {id}")),
+        Err(e) => RepositoryError::Database(e),
+    }
+}
+
+pub fn entity_type_name(kind: EntityKind) -> &'static str {
+    match kind {
+        EntityKind::Goal => "Goal",
+        EntityKind::Study => "Study",
+        EntityKind::Session => "Session",
+        EntityKind::Plan => "Plan",
+        EntityKind::Template => "Template",
+    }
+}
+
+pub fn entity_type_from_name(name: &str) -> RepositoryResult<EntityKind> {
+    match name {
+        "Goal" => Ok(EntityKind::Goal),
+        "Study" => Ok(EntityKind::Study),
+        "Session" => Ok(EntityKind::Session),
+        "Plan" => Ok(EntityKind::Plan),
+        "Template" => Ok(EntityKind::Template),
+        _ => Err(RepositoryError::ValidationError(format!(
+            "Unknown entity type: {name}"
+        ))),
+    }
+}
+
+/// Remembers a deleted entity, so incremental syncs can tell clients about it
+pub async fn record_tombstone<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    user_id: &str,
+    kind: EntityKind,
+    id: &str,
+) -> RepositoryResult<()> {
+    sqlx::query(
+        "INSERT INTO tombstones (entity_type, entity_id, user_id) VALUES ($1, $2, $3)
+         ON CONFLICT (entity_type, entity_id)
+         DO UPDATE SET deleted_at = CURRENT_TIMESTAMP, user_id = $3",
+    )
+    .bind(entity_type_name(kind))
+    .bind(id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Moves an entity to the trash. It is hidden everywhere, and syncing clients
+/// drop it, until it is restored or purged.
+pub async fn trash_entity(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    table: &str,
+    kind: EntityKind,
+    user_id: &str,
+    id: &str,
+) -> RepositoryResult<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {table} SET deleted_at = CURRENT_TIMESTAMP, version = version + 1,
+         updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    if result.rows_affected() > 0 {
+        record_tombstone(&mut **tx, user_id, kind, id).await?;
+    }
+    Ok(result.rows_affected() > 0)
+}
+
+/// Hides an entity from the default lists; it still syncs
+pub async fn archive_entity<'e>(
+    executor: impl sqlx::PgExecutor<'e>,
+    table: &str,
+    user_id: &str,
+    id: &str,
+) -> RepositoryResult<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {table} SET archived_at = COALESCE(archived_at, CURRENT_TIMESTAMP),
+         version = version + 1, updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Brings an entity back from the archive or the trash. The version bump
+/// sends it to syncing clients again.
+pub async fn restore_entity(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    table: &str,
+    kind: EntityKind,
+    user_id: &str,
+    id: &str,
+) -> RepositoryResult<bool> {
+    let result = sqlx::query(&format!(
+        "UPDATE {table} SET archived_at = NULL, deleted_at = NULL, version = version + 1,
+         updated_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM tombstones WHERE entity_type = $1 AND entity_id = $2 AND user_id = $3",
+    )
+    .bind(entity_type_name(kind))
+    .bind(id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.and_utc().to_rfc3339()
+}
+
+/// Parses an RFC 3339 time sent by a client for storing in a TIMESTAMPTZ column
+pub fn parse_instant(field: &str, value: &str) -> RepositoryResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|instant| instant.with_timezone(&Utc))
+        .map_err(|e| RepositoryError::ValidationError(format!("Invalid {field} {value:?}: {e}")))
+}
+
+/// Formats a stored TIMESTAMPTZ the way clients send times, in UTC
+pub fn format_instant(instant: DateTime<Utc>) -> String {
+    instant.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses a calendar day such as "2025-05-01" sent by a client
+pub fn parse_date(field: &str, value: &str) -> RepositoryResult<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| RepositoryError::ValidationError(format!("Invalid {field} {value:?}: {e}")))
+}
+
+/// Reads an optional request field so that a field left out stays None and
+/// one sent as null becomes Some(None), for fields an update can clear
+pub fn explicit_null<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// A join table from an entity to the entities it lists, such as a goal's
+/// studies. Clients send the list as an array of ids and get it back in the
+/// same order.
+pub struct Link {
+    pub table: &'static str,
+    pub owner_table: &'static str,
+    pub owner_column: &'static str,
+    pub linked_table: &'static str,
+    pub linked_column: &'static str,
+}
+
+pub const GOAL_STUDIES: Link = Link {
+    table: "goal_studies",
+    owner_table: "goals",
+    owner_column: "goal_id",
+    linked_table: "studies",
+    linked_column: "study_id",
+};
+
+pub const SESSION_GOALS: Link = Link {
+    table: "session_goals",
+    owner_table: "sessions",
+    owner_column: "session_id",
+    linked_table: "goals",
+    linked_column: "goal_id",
+};
+
+pub const PLAN_GOALS: Link = Link {
+    table: "plan_goals",
+    owner_table: "plans",
+    owner_column: "plan_id",
+    linked_table: "goals",
+    linked_column: "goal_id",
+};
+
+pub const PLAN_STUDIES: Link = Link {
+    table: "plan_studies",
+    owner_table: "plans",
+    owner_column: "plan_id",
+    linked_table: "studies",
+    linked_column: "study_id",
+};
+
+pub const TEMPLATE_GOALS: Link = Link {
+    table: "template_goals",
+    owner_table: "session_templates",
+    owner_column: "template_id",
+    linked_table: "goals",
+    linked_column: "goal_id",
+};
+
+// Blocks carry planned minutes too, so they are saved by the template
+// repository; the link is only used to touch templates on study deletion
+pub const TEMPLATE_BLOCKS: Link = Link {
+    table: "template_blocks",
+    owner_table: "session_templates",
+    owner_column: "template_id",
+    linked_table: "studies",
+    linked_column: "study_id",
+};
+
+/// Replaces the ids an entity links to. Every id must be one of the user's
+/// entities; repeats after the first are ignored.
+pub async fn save_links(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    link: &Link,
+    user_id: &str,
+    owner_id: &str,
+    ids: &[String],
+) -> RepositoryResult<()> {
+    let mut unique: Vec<&str> = Vec::new();
+    for id in ids {
+        if !unique.contains(&id.as_str()) {
+            unique.push(id);
+        }
+    }
+
+    // Links to trashed entities aren't sent by clients, but are kept for a restore
+    sqlx::query(&format!(
+        "DELETE FROM {table} WHERE {owner_column} = $1
+         AND {linked_column} NOT IN (SELECT id FROM {linked_table} WHERE deleted_at IS NOT NULL)",
+        table = link.table,
+        owner_column = link.owner_column,
+        linked_column = link.linked_column,
+        linked_table = link.linked_table,
+    ))
+    .bind(owner_id)
+    .execute(&mut **tx)
+    .await?;
+
+    let inserted = sqlx::query(&format!(
+        "INSERT INTO {table} ({owner_column}, {linked_column}, position)
+         SELECT $1, linked.id, linked.position::INTEGER
+         FROM unnest($2::VARCHAR[]) WITH ORDINALITY AS linked(id, position)
+         JOIN {linked_table} ON {linked_table}.id = linked.id AND {linked_table}.user_id = $3
+           AND {linked_table}.deleted_at IS NULL",
+        table = link.table,
+        owner_column = link.owner_column,
+        linked_column = link.linked_column,
+        linked_table = link.linked_table,
+    ))
+    .bind(owner_id)
+    .bind(&unique)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    if inserted.rows_affected() < unique.len() as u64 {
+        return Err(RepositoryError::ValidationError(format!(
+            "{} refers to {} that don't exist",
+            link.linked_column, link.linked_table
+        )));
+    }
+    Ok(())
+}
+
+/// Bumps the version of every entity linking to one that is about to be
+/// deleted. Deleting removes the links, and the bump lets syncing clients
+/// see the shorter lists.
+pub async fn touch_linking(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    link: &Link,
+    user_id: &str,
+    linked_id: &str,
+) -> RepositoryResult<()> {
+    sqlx::query(&format!(
+        "UPDATE {owner_table} SET version = version + 1, updated_at = CURRENT_TIMESTAMP
+         WHERE user_id = $2
+           AND id IN (SELECT {owner_column} FROM {table} WHERE {linked_column} = $1)",
+        owner_table = link.owner_table,
+        owner_column = link.owner_column,
+        table = link.table,
+        linked_column = link.linked_column,
+    ))
+    .bind(linked_id)
+    .bind(user_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+// Study sessions are saved by the session repository; the link is only used
+// to count and touch sessions on study deletion
+pub const STUDY_SESSIONS: Link = Link {
+    table: "study_sessions",
+    owner_table: "sessions",
+    owner_column: "session_id",
+    linked_table: "studies",
+    linked_column: "study_id",
+};
+
+/// Counts the user's entities linking to one through `link`
+pub async fn count_linking(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    link: &Link,
+    user_id: &str,
+    linked_id: &str,
+) -> RepositoryResult<u32> {
+    let count: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(DISTINCT {table}.{owner_column}) FROM {table}
+         JOIN {owner_table} ON {owner_table}.id = {table}.{owner_column}
+         WHERE {table}.{linked_column} = $1 AND {owner_table}.user_id = $2",
+        table = link.table,
+        owner_table = link.owner_table,
+        owner_column = link.owner_column,
+        linked_column = link.linked_column,
+    ))
+    .bind(linked_id)
+    .bind(user_id)
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    // Archived entities are left out of lists unless asked for
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    // What to do with the entities referring to the one deleted
+    #[serde(default)]
+    pub mode: DeleteMode,
+}
+
+/// Refuses a delete in `block_if_referenced` mode while anything refers to
+/// the entity
+pub fn check_unreferenced(entity: &str, impact: &DeleteImpact) -> RepositoryResult<()> {
+    match &impact.summary {
+        Some(summary) => Err(RepositoryError::Referenced(format!(
+            "{entity} is {summary}"
+        ))),
+        None => Ok(()),
+    }
+}
+
 /// Simple shared database helper
 pub struct Database {
     pub pool: DbPool,
@@ -34,6 +447,33 @@ impl Database {
     }
 }
 
+/// A migrated pool on the database in DATABASE_URL, for tests that need
+/// Postgres. None when it isn't set, and those tests return early.
+#[cfg(test)]
+pub async fn test_pool() -> Option<DbPool> {
+    let database_url = std::env::var("DATABASE_URL").ok()?;
+    let pool = DbPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to DATABASE_URL");
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("Failed to migrate the test database");
+    Some(pool)
+}
+
+/// A new account for a test to own its rows, returning its id
+#[cfg(test)]
+pub async fn create_test_user(pool: &DbPool) -> String {
+    let user_id = shared::generate_id();
+    sqlx::query("INSERT INTO users (id, email, password_hash) VALUES ($1, $1 || '@test', '')")
+        .bind(&user_id)
+        .execute(pool)
+        .await
+        .expect("Failed to create test user");
+    user_id
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,6 +491,59 @@ mod tests {
         assert_eq!(error.to_string(), "Entity not found: test_id");
     }
 
+    #[test]
+    fn test_check_unreferenced() {
+        let unused = DeleteImpact::new("goal-1".to_string(), 0, 0, 0, 0);
+        assert!(check_unreferenced("Goal", &unused).is_ok());
+
+        let used = DeleteImpact::new("goal-1".to_string(), 14, 0, 1, 0);
+        let err = check_unreferenced("Goal", &used).unwrap_err();
+        assert_eq!(err.to_string(), "Goal is used by 14 sessions and 1 plan");
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(check_version("Goal", None, 3).is_ok());
+        assert!(check_version("Goal", Some(3), 3).is_ok());
+
+        let error = check_version("Goal", Some(2), 3).unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert!(error.to_string().contains("version 3"));
+    }
+
+    #[test]
+    fn test_entity_type_names_round_trip() {
+        for kind in [
+            EntityKind::Goal,
+            EntityKind::Study,
+            EntityKind::Session,
+            EntityKind::Plan,
+            EntityKind::Template,
+        ] {
+            let name = entity_type_name(kind);
+            assert_eq!(entity_type_from_name(name).unwrap(), kind);
+        }
+        assert!(entity_type_from_name("Widget").is_err());
+    }
+
+    #[test]
+    fn test_instants_are_stored_in_utc() {
+        let instant = parse_instant("start_time", "2025-01-01T13:00:00+01:00").unwrap();
+        assert_eq!(format_instant(instant), "2025-01-01T12:00:00Z");
+
+        let error = parse_instant("start_time", "yesterday").unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn test_dates_are_calendar_days() {
+        let date = parse_date("target_date", "2025-05-01").unwrap();
+        assert_eq!(date.to_string(), "2025-05-01");
+
+        assert!(parse_date("target_date", "01/05/2025").is_err());
+    }
+
     #[test]
     fn test_database_creation() {
         // This is a simple test to ensure Database struct compiles
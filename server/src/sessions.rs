@@ -1,86 +1,152 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
+use chrono::{DateTime, NaiveDateTime, Utc};
 use serde::Deserialize;
 use shared::session::SessionState;
-use shared::PracticeSession;
+use shared::{EntityKind, PauseInterval, PracticeSession, SessionError};
 use sqlx::FromRow;
 use std::sync::Arc;
 
 use crate::{
-    repository::{Database, RepositoryError, RepositoryResult},
+    auth::AuthUser,
+    repository::{
+        archive_entity, check_version, format_instant, format_timestamp, missing_or_stale,
+        parse_instant, restore_entity, save_links, trash_entity, Database, ListQuery,
+        RepositoryError, RepositoryResult, SESSION_GOALS,
+    },
+    study_sessions::{study_session_to_row_data, StudySessionRow},
     ApiError,
 };
 
 // Type alias to simplify complex return type
-type SessionRowData = (String, String, Option<String>, Option<String>, Option<i32>);
+type SessionRowData = (
+    String,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<i32>,
+    Option<DateTime<Utc>>,
+    Option<i32>,
+);
 
 // Database row struct - flattened representation for storage
 #[derive(FromRow)]
 pub struct SessionRow {
     #[allow(dead_code)]
     pub id: String,
-    pub goal_ids: String, // JSON string
+    pub goal_ids: Vec<String>, // from session_goals, in order
     pub intention: String,
     pub notes: Option<String>,
-    pub session_state: String, // "NotStarted", "Started", "Ended"
-    pub start_time: Option<String>,
-    pub end_time: Option<String>,
+    pub session_state: String, // "NotStarted", "Started", "Paused", "Ended"
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
     pub duration_in_seconds: Option<i32>,
+    pub paused_at: Option<DateTime<Utc>>,
+    pub accumulated_seconds: Option<i32>,
+    pub active_study_session_id: Option<String>,
+    pub plan_id: Option<String>,
+    pub scheduled_for: Option<DateTime<Utc>>,
+    pub version: i32,
+    pub updated_at: Option<NaiveDateTime>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+// Completed pause interval row
+#[derive(FromRow)]
+pub struct SessionPauseRow {
+    pub session_id: String,
+    pub paused_at: DateTime<Utc>,
+    pub resumed_at: DateTime<Utc>,
+}
+
+impl SessionPauseRow {
+    pub fn into_pause(self) -> PauseInterval {
+        PauseInterval {
+            paused_at: format_instant(self.paused_at),
+            resumed_at: format_instant(self.resumed_at),
+        }
+    }
 }
 
 impl SessionRow {
     pub fn into_session(self) -> RepositoryResult<PracticeSession> {
-        let goal_ids: Vec<String> = serde_json::from_str(&self.goal_ids)?;
+        let start_time = self.start_time.map(format_instant);
+        let end_time = self.end_time.map(format_instant);
+        let paused_at = self.paused_at.map(format_instant);
 
         // Create SessionData with the original database ID using the view model approach
         let session = PracticeSession {
             id: self.id,
-            goal_ids,
+            goal_ids: self.goal_ids,
             intention: self.intention,
             state: match self.session_state.as_str() {
                 "NotStarted" => SessionState::NotStarted,
                 "Started" => SessionState::Started {
-                    start_time: self.start_time.clone().ok_or_else(|| {
+                    start_time: start_time.clone().ok_or_else(|| {
                         RepositoryError::ValidationError(
                             "Started session missing start_time".to_string(),
                         )
                     })?,
                 },
+                "Paused" => SessionState::Paused {
+                    start_time: start_time.clone().ok_or_else(|| {
+                        RepositoryError::ValidationError(
+                            "Paused session missing start_time".to_string(),
+                        )
+                    })?,
+                    paused_at: paused_at.ok_or_else(|| {
+                        RepositoryError::ValidationError(
+                            "Paused session missing paused_at".to_string(),
+                        )
+                    })?,
+                    accumulated_seconds: self.accumulated_seconds.unwrap_or(0) as u32,
+                },
                 "PendingReflection" => SessionState::PendingReflection {
-                    start_time: self.start_time.clone().ok_or_else(|| {
+                    start_time: start_time.clone().ok_or_else(|| {
                         RepositoryError::ValidationError(
                             "PendingReflection session missing start_time".to_string(),
                         )
                     })?,
-                    end_time: self.end_time.clone().ok_or_else(|| {
+                    end_time: end_time.clone().ok_or_else(|| {
                         RepositoryError::ValidationError(
                             "PendingReflection session missing end_time".to_string(),
                         )
                     })?,
                 },
                 "Ended" => SessionState::Ended {
-                    start_time: self.start_time.clone().ok_or_else(|| {
+                    start_time: start_time.clone().ok_or_else(|| {
                         RepositoryError::ValidationError(
                             "Ended session missing start_time".to_string(),
                         )
                     })?,
-                    end_time: self.end_time.clone().ok_or_else(|| {
+                    end_time: end_time.clone().ok_or_else(|| {
                         RepositoryError::ValidationError(
                             "Ended session missing end_time".to_string(),
                         )
                     })?,
                     duration_in_seconds: self.duration_in_seconds.unwrap_or(0) as u32,
                 },
-                _ => SessionState::NotStarted,
+                other => {
+                    return Err(RepositoryError::ValidationError(format!(
+                        "Unknown session state: {other}"
+                    )))
+                }
             },
             notes: self.notes.clone(),
             study_sessions: Vec::new(),
-            active_study_session_id: None,
+            active_study_session_id: self.active_study_session_id,
+            pauses: Vec::new(),
+            plan_id: self.plan_id,
+            scheduled_for: self.scheduled_for.map(format_instant),
+            version: self.version as u32,
+            updated_at: self.updated_at.map(format_timestamp),
+            archived_at: self.archived_at.map(format_instant),
+            deleted_at: self.deleted_at.map(format_instant),
         };
 
         // Return the session directly
@@ -91,10 +157,15 @@ impl SessionRow {
 // Request types
 #[derive(Debug, Deserialize)]
 pub struct CreateSessionRequest {
+    // Client generated id, so optimistic local records keep their identity
+    pub id: Option<String>,
     pub goal_ids: Vec<String>,
     pub intention: String,
     #[allow(dead_code)]
     pub notes: Option<String>,
+    // Set when a practice plan scheduled the session
+    pub plan_id: Option<String>,
+    pub scheduled_for: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -105,6 +176,8 @@ pub struct UpdateSessionRequest {
     pub intention: Option<String>,
     #[allow(dead_code)]
     pub notes: Option<String>,
+    // The version the client's edit was based on, stale edits are rejected
+    pub version: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -112,6 +185,16 @@ pub struct StartSessionRequest {
     pub start_time: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PauseSessionRequest {
+    pub paused_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResumeSessionRequest {
+    pub resumed_at: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct EndSessionRequest {
     pub end_time: String,
@@ -135,12 +218,13 @@ impl SessionRepository {
     }
 
     fn session_to_row_data(session: &PracticeSession) -> RepositoryResult<SessionRowData> {
-        let goal_ids_json = serde_json::to_string(&session.goal_ids)?;
-
         let (state_str, start_time, end_time, duration_in_seconds) = match &session.state {
             SessionState::NotStarted => ("NotStarted".to_string(), None, None, None),
             SessionState::Started { start_time } => {
-                ("Started".to_string(), Some(start_time.clone()), None, None)
+                ("Started".to_string(), Some(start_time), None, None)
+            }
+            SessionState::Paused { start_time, .. } => {
+                ("Paused".to_string(), Some(start_time), None, None)
             }
             SessionState::Ended {
                 start_time,
@@ -148,8 +232,8 @@ impl SessionRepository {
                 duration_in_seconds,
             } => (
                 "Ended".to_string(),
-                Some(start_time.clone()),
-                Some(end_time.clone()),
+                Some(start_time),
+                Some(end_time),
                 Some(*duration_in_seconds as i32),
             ),
             SessionState::PendingReflection {
@@ -157,66 +241,396 @@ impl SessionRepository {
                 end_time,
             } => (
                 "PendingReflection".to_string(),
-                Some(start_time.clone()),
-                Some(end_time.clone()),
+                Some(start_time),
+                Some(end_time),
                 None,
             ),
         };
+        let start_time = start_time
+            .map(|t| parse_instant("start_time", t))
+            .transpose()?;
+        let end_time = end_time.map(|t| parse_instant("end_time", t)).transpose()?;
+
+        let (paused_at, accumulated_seconds) = match &session.state {
+            SessionState::Paused {
+                paused_at,
+                accumulated_seconds,
+                ..
+            } => (
+                Some(parse_instant("paused_at", paused_at)?),
+                Some(*accumulated_seconds as i32),
+            ),
+            _ => (None, None),
+        };
 
         Ok((
-            goal_ids_json,
             state_str,
             start_time,
             end_time,
             duration_in_seconds,
+            paused_at,
+            accumulated_seconds,
         ))
     }
 
-    pub async fn create(&self, session: &PracticeSession) -> RepositoryResult<()> {
-        let (goal_ids_json, state_str, start_time, end_time, duration_in_seconds) =
-            Self::session_to_row_data(session)?;
+    async fn ensure_plan_exists(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        plan_id: &str,
+    ) -> RepositoryResult<()> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM plans WHERE id = $1 AND user_id = $2)",
+        )
+        .bind(plan_id)
+        .bind(user_id)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(RepositoryError::ValidationError(
+                "plan_id refers to plans that don't exist".to_string(),
+            ))
+        }
+    }
 
+    // Creating the session of an occurrence that has one already succeeds when
+    // it is the same session, as when a second device schedules it
+    async fn ensure_scheduled_as(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        session: &PracticeSession,
+        scheduled_for: Option<DateTime<Utc>>,
+    ) -> RepositoryResult<()> {
+        let existing: Option<String> =
+            sqlx::query_scalar("SELECT id FROM sessions WHERE plan_id = $1 AND scheduled_for = $2")
+                .bind(&session.plan_id)
+                .bind(scheduled_for)
+                .fetch_optional(&mut **tx)
+                .await?;
+
+        match existing {
+            Some(id) if id == session.id => Ok(()),
+            _ => Err(RepositoryError::Conflict(format!(
+                "Plan {} already has a session at {}",
+                session.plan_id.as_deref().unwrap_or_default(),
+                session.scheduled_for.as_deref().unwrap_or_default()
+            ))),
+        }
+    }
+
+    // Fail if another of the user's sessions is already running
+    // Only one session per user can be running. Locking the user's row makes
+    // concurrent starts take turns, so two of them can't both pass the check.
+    async fn ensure_no_other_started(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session_id: &str,
+    ) -> RepositoryResult<()> {
+        sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+            .bind(user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        let other: Option<String> = sqlx::query_scalar(
+            "SELECT id FROM sessions
+             WHERE user_id = $1 AND session_state = 'Started' AND id <> $2
+               AND deleted_at IS NULL
+             LIMIT 1",
+        )
+        .bind(user_id)
+        .bind(session_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        match other {
+            Some(_) => Err(RepositoryError::InvalidTransition(
+                SessionError::AnotherSessionStarted,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // Replace the stored pause intervals for a session
+    async fn save_pauses(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        session: &PracticeSession,
+    ) -> RepositoryResult<()> {
+        sqlx::query("DELETE FROM session_pauses WHERE session_id = $1")
+            .bind(&session.id)
+            .execute(&mut **tx)
+            .await?;
+
+        for pause in &session.pauses {
+            sqlx::query(
+                "INSERT INTO session_pauses (session_id, paused_at, resumed_at) VALUES ($1, $2, $3)",
+            )
+            .bind(&session.id)
+            .bind(parse_instant("paused_at", &pause.paused_at)?)
+            .bind(parse_instant("resumed_at", &pause.resumed_at)?)
+            .execute(&mut **tx)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    // Upsert the session's study sessions, removing any the session no longer has
+    async fn save_study_sessions(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        session: &PracticeSession,
+    ) -> RepositoryResult<()> {
+        let study_session_ids: Vec<String> = session
+            .study_sessions
+            .iter()
+            .map(|s| s.id.clone())
+            .collect();
+
+        // Study sessions of trashed studies aren't sent, but are kept for a restore
         sqlx::query(
-            "INSERT INTO sessions (id, goal_ids, intention, notes, session_state, start_time, end_time, duration_in_seconds) 
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+            "DELETE FROM study_sessions WHERE session_id = $1 AND NOT (id = ANY($2))
+             AND study_id NOT IN (SELECT id FROM studies WHERE deleted_at IS NOT NULL)",
+        )
+        .bind(&session.id)
+        .bind(&study_session_ids)
+        .execute(&mut **tx)
+        .await?;
+
+        for (position, study_session) in session.study_sessions.iter().enumerate() {
+            if study_session.planned_minutes == Some(0) {
+                return Err(RepositoryError::ValidationError(
+                    "planned_minutes must be greater than 0".to_string(),
+                ));
+            }
+            let (state_str, start_time, resumed_at, end_time, elapsed_seconds) =
+                study_session_to_row_data(study_session)?;
+
+            let result = sqlx::query(
+                "INSERT INTO study_sessions (id, session_id, study_id, score, state, start_time, resumed_at, end_time, elapsed_seconds, tempo, clean, position, planned_minutes) 
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13) 
+                 ON CONFLICT (id) DO UPDATE SET study_id = $3, score = $4, state = $5, start_time = $6, 
+                 resumed_at = $7, end_time = $8, elapsed_seconds = $9, tempo = $10, clean = $11,
+                 position = $12, planned_minutes = $13, updated_at = CURRENT_TIMESTAMP
+                 WHERE study_sessions.session_id = EXCLUDED.session_id",
+            )
+            .bind(&study_session.id)
+            .bind(&session.id)
+            .bind(&study_session.study_id)
+            .bind(study_session.score.map(|s| s as i32))
+            .bind(&state_str)
+            .bind(start_time)
+            .bind(resumed_at)
+            .bind(end_time)
+            .bind(elapsed_seconds)
+            .bind(study_session.tempo.map(|t| t as i32))
+            .bind(study_session.clean)
+            .bind(position as i32)
+            .bind(study_session.planned_minutes.map(|m| m as i32))
+            .execute(&mut **tx)
+            .await?;
+
+            // The id is taken by a study session of another session
+            if result.rows_affected() == 0 {
+                return Err(RepositoryError::Conflict(format!(
+                    "Study session id {} is already in use",
+                    study_session.id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Load study sessions and pause intervals for the given sessions
+    async fn attach_children(&self, sessions: &mut [PracticeSession]) -> RepositoryResult<()> {
+        self.attach_pauses(sessions).await?;
+        self.attach_study_sessions(sessions).await
+    }
+
+    async fn attach_study_sessions(
+        &self,
+        sessions: &mut [PracticeSession],
+    ) -> RepositoryResult<()> {
+        if sessions.is_empty() {
+            return Ok(());
+        }
+
+        let session_ids: Vec<String> = sessions.iter().map(|s| s.id.clone()).collect();
+        let rows = sqlx::query_as!(
+            StudySessionRow,
+            "SELECT id, session_id, study_id, score, state, start_time, resumed_at, end_time, elapsed_seconds,
+             tempo, clean, planned_minutes FROM study_sessions WHERE session_id = ANY($1)
+             AND study_id NOT IN (SELECT id FROM studies WHERE deleted_at IS NOT NULL)
+             ORDER BY position, created_at, id",
+            &session_ids
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        for row in rows {
+            if let Some(session) = sessions.iter_mut().find(|s| s.id == row.session_id) {
+                session.study_sessions.push(row.into_study_session()?);
+            }
+        }
+        Ok(())
+    }
+
+    // Load pause intervals for the given sessions
+    async fn attach_pauses(&self, sessions: &mut [PracticeSession]) -> RepositoryResult<()> {
+        if sessions.is_empty() {
+            return Ok(());
+        }
+
+        let session_ids: Vec<String> = sessions.iter().map(|s| s.id.clone()).collect();
+        let rows = sqlx::query_as!(
+            SessionPauseRow,
+            "SELECT session_id, paused_at, resumed_at FROM session_pauses
+             WHERE session_id = ANY($1) ORDER BY id",
+            &session_ids
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        for row in rows {
+            if let Some(session) = sessions.iter_mut().find(|s| s.id == row.session_id) {
+                session.pauses.push(row.into_pause());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn create(&self, user_id: &str, session: &PracticeSession) -> RepositoryResult<()> {
+        let mut tx = self.db.pool.begin().await?;
+        Self::create_in(&mut tx, user_id, session).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Creates a session as part of a larger transaction, such as an import
+    pub async fn create_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+    ) -> RepositoryResult<()> {
+        let scheduled_for = session
+            .scheduled_for
+            .as_deref()
+            .map(|t| parse_instant("scheduled_for", t))
+            .transpose()?;
+
+        if let Some(plan_id) = &session.plan_id {
+            Self::ensure_plan_exists(tx, user_id, plan_id).await?;
+        }
+        if matches!(session.state, SessionState::Started { .. }) {
+            Self::ensure_no_other_started(tx, user_id, &session.id).await?;
+        }
+
+        // A plan occurrence has one session, however many devices schedule it
+        let inserted = Self::insert_in(
+            tx,
+            user_id,
+            session,
+            scheduled_for,
+            "ON CONFLICT (plan_id, scheduled_for) WHERE plan_id IS NOT NULL DO NOTHING",
         )
+        .await?;
+        if !inserted {
+            return Self::ensure_scheduled_as(tx, session, scheduled_for).await;
+        }
+        Ok(())
+    }
+
+    /// Creates a session whose id is derived from what it records, unless
+    /// one with that id was already created. Returns whether it was created.
+    pub async fn create_if_new_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+    ) -> RepositoryResult<bool> {
+        Self::insert_in(tx, user_id, session, None, "ON CONFLICT DO NOTHING").await
+    }
+
+    // Inserts the session and what belongs to it, unless `on_conflict` skips it
+    async fn insert_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+        scheduled_for: Option<DateTime<Utc>>,
+        on_conflict: &str,
+    ) -> RepositoryResult<bool> {
+        let (state_str, start_time, end_time, duration_in_seconds, paused_at, accumulated_seconds) =
+            Self::session_to_row_data(session)?;
+        let result = sqlx::query(&format!(
+            "INSERT INTO sessions (id, intention, notes, session_state, start_time, end_time, duration_in_seconds, paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, user_id) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+             {on_conflict}"
+        ))
         .bind(&session.id)
-        .bind(&goal_ids_json)
         .bind(&session.intention)
         .bind(&session.notes)
         .bind(&state_str)
-        .bind(&start_time)
-        .bind(&end_time)
+        .bind(start_time)
+        .bind(end_time)
         .bind(duration_in_seconds)
-        .execute(&self.db.pool)
+        .bind(paused_at)
+        .bind(accumulated_seconds)
+        .bind(&session.active_study_session_id)
+        .bind(&session.plan_id)
+        .bind(scheduled_for)
+        .bind(user_id)
+        .execute(&mut **tx)
         .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
 
-        Ok(())
+        save_links(tx, &SESSION_GOALS, user_id, &session.id, &session.goal_ids).await?;
+        Self::save_pauses(tx, session).await?;
+        Self::save_study_sessions(tx, session).await?;
+        Ok(true)
     }
 
-    pub async fn find_by_id(&self, id: &str) -> RepositoryResult<Option<PracticeSession>> {
+    pub async fn find_by_id(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<PracticeSession>> {
         // Type-safe approach: Use sqlx! macro for compile-time verification
         let row = sqlx::query_as!(
             SessionRow,
-            "SELECT id, goal_ids, intention, notes, session_state, start_time, end_time, duration_in_seconds 
-             FROM sessions WHERE id = $1",
-            id
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL"#,
+            id,
+            user_id
         )
         .fetch_optional(&self.db.pool)
         .await?;
 
         match row {
-            Some(row) => Ok(Some(row.into_session()?)),
+            Some(row) => {
+                let mut session = row.into_session()?;
+                self.attach_children(std::slice::from_mut(&mut session))
+                    .await?;
+                Ok(Some(session))
+            }
             None => Ok(None),
         }
     }
 
-    pub async fn find_all(&self) -> RepositoryResult<Vec<PracticeSession>> {
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Vec<PracticeSession>> {
         // Type-safe approach: Use sqlx! macro for compile-time verification
         let rows = sqlx::query_as!(
             SessionRow,
-            "SELECT id, goal_ids, intention, notes, session_state, start_time, end_time, duration_in_seconds 
-             FROM sessions ORDER BY created_at DESC"
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC"#,
+            user_id
         )
         .fetch_all(&self.db.pool)
         .await?;
@@ -225,76 +639,219 @@ impl SessionRepository {
         for row in rows {
             sessions.push(row.into_session()?);
         }
+        self.attach_children(&mut sessions).await?;
         Ok(sessions)
     }
 
-    pub async fn update(&self, session: &PracticeSession) -> RepositoryResult<()> {
-        let (goal_ids_json, state_str, start_time, end_time, duration_in_seconds) =
+    pub async fn update(
+        &self,
+        user_id: &str,
+        session: &PracticeSession,
+    ) -> RepositoryResult<PracticeSession> {
+        let mut tx = self.db.pool.begin().await?;
+        let session = Self::update_in(&mut tx, user_id, session).await?;
+        tx.commit().await?;
+
+        Ok(session)
+    }
+
+    /// Updates a session as part of a larger transaction, such as an import
+    pub async fn update_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        session: &PracticeSession,
+    ) -> RepositoryResult<PracticeSession> {
+        let (state_str, start_time, end_time, duration_in_seconds, paused_at, accumulated_seconds) =
             Self::session_to_row_data(session)?;
 
-        let result = sqlx::query(
-            "UPDATE sessions SET goal_ids = $2, intention = $3, notes = $4, session_state = $5, 
-             start_time = $6, end_time = $7, duration_in_seconds = $8, updated_at = CURRENT_TIMESTAMP 
-             WHERE id = $1",
+        if matches!(session.state, SessionState::Started { .. }) {
+            Self::ensure_no_other_started(tx, user_id, &session.id).await?;
+        }
+
+        let stamp: Option<(i32, Option<NaiveDateTime>)> = sqlx::query_as(
+            "UPDATE sessions SET intention = $2, notes = $3, session_state = $4, 
+             start_time = $5, end_time = $6, duration_in_seconds = $7, paused_at = $8, 
+             accumulated_seconds = $9, active_study_session_id = $10, version = version + 1,
+             updated_at = CURRENT_TIMESTAMP 
+             WHERE id = $1 AND version = $11 AND user_id = $12
+             RETURNING version, updated_at",
         )
         .bind(&session.id)
-        .bind(&goal_ids_json)
         .bind(&session.intention)
         .bind(&session.notes)
         .bind(&state_str)
-        .bind(&start_time)
-        .bind(&end_time)
+        .bind(start_time)
+        .bind(end_time)
         .bind(duration_in_seconds)
-        .execute(&self.db.pool)
+        .bind(paused_at)
+        .bind(accumulated_seconds)
+        .bind(&session.active_study_session_id)
+        .bind(session.version as i32)
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(RepositoryError::NotFound(format!(
-                "Session with id {}",
-                &session.id
-            )));
+        let Some((version, updated_at)) = stamp else {
+            return Err(missing_or_stale(
+                &mut **tx,
+                "sessions",
+                user_id,
+                &session.id,
+                session.version,
+            )
+            .await);
+        };
+
+        save_links(tx, &SESSION_GOALS, user_id, &session.id, &session.goal_ids).await?;
+        Self::save_pauses(tx, session).await?;
+        Self::save_study_sessions(tx, session).await?;
+
+        Ok(PracticeSession {
+            version: version as u32,
+            updated_at: updated_at.map(format_timestamp),
+            ..session.clone()
+        })
+    }
+
+    pub async fn find_changed_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<PracticeSession>> {
+        let rows = sqlx::query_as!(
+            SessionRow,
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions WHERE user_id = $1 AND change_xid >= $2 AND deleted_at IS NULL
+             ORDER BY updated_at"#,
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row.into_session()?);
         }
+        self.attach_children(&mut sessions).await?;
+        Ok(sessions)
+    }
 
-        Ok(())
+    pub async fn delete(&self, user_id: &str, id: &str) -> RepositoryResult<bool> {
+        let mut tx = self.db.pool.begin().await?;
+
+        let deleted = trash_entity(&mut tx, "sessions", EntityKind::Session, user_id, id).await?;
+        tx.commit().await?;
+
+        Ok(deleted)
     }
 
-    pub async fn delete(&self, id: &str) -> RepositoryResult<bool> {
-        let result = sqlx::query("DELETE FROM sessions WHERE id = $1")
-            .bind(id)
+    pub async fn archive(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<PracticeSession>> {
+        if !archive_entity(&self.db.pool, "sessions", user_id, id).await? {
+            return Ok(None);
+        }
+        self.find_by_id(user_id, id).await
+    }
+
+    pub async fn restore(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<PracticeSession>> {
+        let mut tx = self.db.pool.begin().await?;
+
+        if !restore_entity(&mut tx, "sessions", EntityKind::Session, user_id, id).await? {
+            return Ok(None);
+        }
+        tx.commit().await?;
+
+        self.find_by_id(user_id, id).await
+    }
+
+    pub async fn find_trashed(&self, user_id: &str) -> RepositoryResult<Vec<PracticeSession>> {
+        let rows = sqlx::query_as!(
+            SessionRow,
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions
+             WHERE user_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let mut sessions = Vec::new();
+        for row in rows {
+            sessions.push(row.into_session()?);
+        }
+        self.attach_children(&mut sessions).await?;
+        Ok(sessions)
+    }
+
+    /// Deletes every user's sessions that have been in the trash since before
+    /// `before`, with their study sessions and pauses
+    pub async fn purge_deleted_before(&self, before: DateTime<Utc>) -> RepositoryResult<u64> {
+        let result = sqlx::query("DELETE FROM sessions WHERE deleted_at < $1")
+            .bind(before)
             .execute(&self.db.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(result.rows_affected())
     }
 
     // Domain-specific methods
-    pub async fn _find_by_goal_id(&self, goal_id: &str) -> RepositoryResult<Vec<PracticeSession>> {
-        let goal_pattern = format!("%\"{goal_id}\"%");
+    pub async fn _find_by_goal_id(
+        &self,
+        user_id: &str,
+        goal_id: &str,
+    ) -> RepositoryResult<Vec<PracticeSession>> {
         let rows = sqlx::query_as!(
             SessionRow,
-            "SELECT id, goal_ids, intention, notes, session_state, start_time, end_time, duration_in_seconds 
-             FROM sessions WHERE goal_ids LIKE $1 ORDER BY created_at DESC",
-            goal_pattern
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions
+             WHERE user_id = $1 AND deleted_at IS NULL
+               AND id IN (SELECT session_id FROM session_goals WHERE goal_id = $2)
+             ORDER BY created_at DESC"#,
+            user_id,
+            goal_id
         )
         .fetch_all(&self.db.pool)
         .await?;
 
         let mut sessions = Vec::new();
         for row in rows {
-            let session = row.into_session()?;
-            // Double-check the goal_id is actually in the list (not just substring match)
-            if session.goal_ids.contains(&goal_id.to_string()) {
-                sessions.push(session);
-            }
+            sessions.push(row.into_session()?);
         }
+        self.attach_children(&mut sessions).await?;
         Ok(sessions)
     }
 
-    pub async fn _find_by_state(&self, state: &str) -> RepositoryResult<Vec<PracticeSession>> {
+    pub async fn _find_by_state(
+        &self,
+        user_id: &str,
+        state: &str,
+    ) -> RepositoryResult<Vec<PracticeSession>> {
         let rows = sqlx::query_as!(
             SessionRow,
-            "SELECT id, goal_ids, intention, notes, session_state, start_time, end_time, duration_in_seconds 
-             FROM sessions WHERE session_state = $1 ORDER BY created_at DESC",
+            r#"SELECT id, ARRAY(SELECT goal_id FROM session_goals JOIN goals ON goals.id = goal_id
+                   WHERE session_id = sessions.id AND goals.deleted_at IS NULL ORDER BY position)
+             AS "goal_ids!", intention, notes, session_state, start_time, end_time, duration_in_seconds,
+             paused_at, accumulated_seconds, active_study_session_id, plan_id, scheduled_for, version, updated_at,
+             archived_at, deleted_at FROM sessions WHERE user_id = $1 AND session_state = $2 AND deleted_at IS NULL
+             ORDER BY created_at DESC"#,
+            user_id,
             state
         )
         .fetch_all(&self.db.pool)
@@ -304,6 +861,7 @@ impl SessionRepository {
         for row in rows {
             sessions.push(row.into_session()?);
         }
+        self.attach_children(&mut sessions).await?;
         Ok(sessions)
     }
 }
@@ -324,76 +882,67 @@ fn update_session_goal_ids(session: &mut PracticeSession, goal_ids: Vec<String>)
 // HTTP Handlers
 async fn create_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Json(req): Json<CreateSessionRequest>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
-    let session = PracticeSession::new(req.goal_ids, req.intention);
+) -> Result<Json<PracticeSession>, ApiError> {
+    let mut session = PracticeSession::new(req.goal_ids, req.intention);
+    if let Some(id) = req.id {
+        session.id = id;
+    }
+    session.plan_id = req.plan_id;
+    session.scheduled_for = req.scheduled_for;
 
     // Note: For now, we'll ignore the notes field in creation
     // The session creation in shared/session.rs doesn't support initial notes
     // This would require extending the constructor or adding a separate update call
 
-    session_repo
-        .create(&session)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    session_repo.create(&user_id, &session).await?;
 
     Ok(Json(session))
 }
 
 async fn get_sessions(
     State(session_repo): State<Arc<SessionRepository>>,
-) -> Result<Json<Vec<PracticeSession>>, (StatusCode, Json<ApiError>)> {
-    let sessions = session_repo
-        .find_all()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<PracticeSession>>, ApiError> {
+    let mut sessions = session_repo.find_all(&user_id).await?;
+    sessions.retain(|s| query.include_archived || s.archived_at.is_none());
 
     Ok(Json(sessions))
 }
 
 async fn get_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
-    let session = session_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
 
     match session {
         Some(session) => Ok(Json(session)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Session not found".to_string(),
-            }),
-        )),
+        None => Err(ApiError::not_found("Session not found")),
     }
 }
 
 async fn update_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<UpdateSessionRequest>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
+) -> Result<Json<PracticeSession>, ApiError> {
     // Get existing session
-    let existing_session = session_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let existing_session = session_repo.find_by_id(&user_id, &id).await?;
 
     let mut session = match existing_session {
         Some(session) => session,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Session not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Session not found"));
         }
     };
 
+    check_version("Session", req.version, session.version)?;
+
     // Update fields if provided - cleaner organization
     if let Some(notes) = req.notes {
         update_session_notes(&mut session, Some(notes));
@@ -408,113 +957,115 @@ async fn update_session(
     }
 
     // Save the updated session
-    session_repo
-        .update(&session)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let session = session_repo.update(&user_id, &session).await?;
 
     Ok(Json(session))
 }
 
 async fn start_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<StartSessionRequest>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
-    let session = session_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
 
     let mut session = match session {
         Some(session) => session,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Session not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Session not found"));
         }
     };
 
     // Start the session
-    session.start(req.start_time).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                message: e.to_string(),
-            }),
-        )
-    })?;
+    session.start(req.start_time).map_err(ApiError::from)?;
 
-    session_repo
-        .update(&session)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let session = session_repo.update(&user_id, &session).await?;
+
+    Ok(Json(session))
+}
+
+async fn pause_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<PauseSessionRequest>,
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
+
+    let mut session = match session {
+        Some(session) => session,
+        None => {
+            return Err(ApiError::not_found("Session not found"));
+        }
+    };
+
+    // Pause the session
+    session.pause(req.paused_at).map_err(ApiError::from)?;
+
+    let session = session_repo.update(&user_id, &session).await?;
+
+    Ok(Json(session))
+}
+
+async fn resume_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<ResumeSessionRequest>,
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
+
+    let mut session = match session {
+        Some(session) => session,
+        None => {
+            return Err(ApiError::not_found("Session not found"));
+        }
+    };
+
+    // Resume the session, recording the completed pause interval
+    session.resume(req.resumed_at).map_err(ApiError::from)?;
+
+    let session = session_repo.update(&user_id, &session).await?;
 
     Ok(Json(session))
 }
 
 async fn end_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<EndSessionRequest>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
-    let session = session_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
 
     let mut session = match session {
         Some(session) => session,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Session not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Session not found"));
         }
     };
 
     // End the session
-    session.end(req.end_time).map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                message: e.to_string(),
-            }),
-        )
-    })?;
+    session.end(req.end_time).map_err(ApiError::from)?;
 
-    session_repo
-        .update(&session)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let session = session_repo.update(&user_id, &session).await?;
 
     Ok(Json(session))
 }
 
 async fn complete_reflection(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<CompleteReflectionRequest>,
-) -> Result<Json<PracticeSession>, (StatusCode, Json<ApiError>)> {
-    let session = session_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<PracticeSession>, ApiError> {
+    let session = session_repo.find_by_id(&user_id, &id).await?;
 
     let mut session = match session {
         Some(session) => session,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Session not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Session not found"));
         }
     };
 
@@ -524,41 +1075,46 @@ async fn complete_reflection(
     }
 
     // Complete the reflection (transitions PendingReflection -> Ended)
-    session.complete_reflection().map_err(|e| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ApiError {
-                message: e.to_string(),
-            }),
-        )
-    })?;
+    session.complete_reflection().map_err(ApiError::from)?;
 
-    session_repo
-        .update(&session)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let session = session_repo.update(&user_id, &session).await?;
 
     Ok(Json(session))
 }
 
 async fn delete_session(
     State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let deleted = session_repo
-        .delete(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<StatusCode, ApiError> {
+    let deleted = session_repo.delete(&user_id, &id).await?;
 
     if deleted {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Session not found".to_string(),
-            }),
-        ))
+        Err(ApiError::not_found("Session not found"))
+    }
+}
+
+async fn archive_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PracticeSession>, ApiError> {
+    match session_repo.archive(&user_id, &id).await? {
+        Some(session) => Ok(Json(session)),
+        None => Err(ApiError::not_found("Session not found")),
+    }
+}
+
+async fn restore_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<PracticeSession>, ApiError> {
+    match session_repo.restore(&user_id, &id).await? {
+        Some(session) => Ok(Json(session)),
+        None => Err(ApiError::not_found("Session not found")),
     }
 }
 
@@ -570,11 +1126,21 @@ pub fn routes() -> Router<Arc<SessionRepository>> {
             get(get_session).put(update_session).delete(delete_session),
         )
         .route("/sessions/{id}/start", axum::routing::post(start_session))
+        .route("/sessions/{id}/pause", axum::routing::post(pause_session))
+        .route("/sessions/{id}/resume", axum::routing::post(resume_session))
         .route("/sessions/{id}/end", axum::routing::post(end_session))
         .route(
             "/sessions/{id}/complete",
             axum::routing::post(complete_reflection),
         )
+        .route(
+            "/sessions/{id}/archive",
+            axum::routing::post(archive_session),
+        )
+        .route(
+            "/sessions/{id}/restore",
+            axum::routing::post(restore_session),
+        )
 }
 
 // *************
@@ -587,6 +1153,10 @@ mod tests {
     use shared::PracticeSession;
     use std::sync::Mutex;
 
+    fn instant(time: &str) -> DateTime<Utc> {
+        parse_instant("time", time).unwrap()
+    }
+
     // Simple mock repository for testing
     struct MockSessionRepository {
         sessions: Mutex<Vec<PracticeSession>>,
@@ -640,13 +1210,22 @@ mod tests {
     fn test_session_row_conversion_not_started() {
         let session_row = SessionRow {
             id: "test-id".to_string(),
-            goal_ids: r#"["goal1", "goal2"]"#.to_string(),
+            goal_ids: vec!["goal1".to_string(), "goal2".to_string()],
             intention: "Test intention".to_string(),
             notes: Some("Test notes".to_string()),
             session_state: "NotStarted".to_string(),
             start_time: None,
             end_time: None,
             duration_in_seconds: None,
+            paused_at: None,
+            accumulated_seconds: None,
+            active_study_session_id: None,
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         };
 
         let session = session_row.into_session().unwrap();
@@ -660,17 +1239,54 @@ mod tests {
         assert!(matches!(session.state, SessionState::NotStarted));
     }
 
+    #[test]
+    fn test_session_row_conversion_rejects_unknown_state() {
+        let session_row = SessionRow {
+            id: "test-id".to_string(),
+            goal_ids: vec![],
+            intention: "Test intention".to_string(),
+            notes: None,
+            session_state: "Running".to_string(),
+            start_time: None,
+            end_time: None,
+            duration_in_seconds: None,
+            paused_at: None,
+            accumulated_seconds: None,
+            active_study_session_id: None,
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
+        };
+
+        assert!(matches!(
+            session_row.into_session(),
+            Err(RepositoryError::ValidationError(_))
+        ));
+    }
+
     #[test]
     fn test_session_row_conversion_started() {
         let session_row = SessionRow {
             id: "test-id".to_string(),
-            goal_ids: r#"["goal1"]"#.to_string(),
+            goal_ids: vec!["goal1".to_string()],
             intention: "Test intention".to_string(),
             notes: None,
             session_state: "Started".to_string(),
-            start_time: Some("2025-01-01T12:00:00Z".to_string()),
+            start_time: Some(instant("2025-01-01T12:00:00Z")),
             end_time: None,
             duration_in_seconds: None,
+            paused_at: None,
+            accumulated_seconds: None,
+            active_study_session_id: None,
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         };
 
         let session = session_row.into_session().unwrap();
@@ -685,13 +1301,22 @@ mod tests {
     fn test_session_row_conversion_ended() {
         let session_row = SessionRow {
             id: "test-id".to_string(),
-            goal_ids: r#"["goal1"]"#.to_string(),
+            goal_ids: vec!["goal1".to_string()],
             intention: "Test intention".to_string(),
             notes: Some("Final notes".to_string()),
             session_state: "Ended".to_string(),
-            start_time: Some("2025-01-01T12:00:00Z".to_string()),
-            end_time: Some("2025-01-01T13:00:00Z".to_string()),
+            start_time: Some(instant("2025-01-01T12:00:00Z")),
+            end_time: Some(instant("2025-01-01T13:00:00Z")),
             duration_in_seconds: Some(3600),
+            paused_at: None,
+            accumulated_seconds: None,
+            active_study_session_id: None,
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         };
 
         let session = session_row.into_session().unwrap();
@@ -703,12 +1328,67 @@ mod tests {
         assert_eq!(session.duration(), Some("60m".to_string()));
     }
 
+    #[test]
+    fn test_session_row_conversion_paused() {
+        let session_row = SessionRow {
+            id: "test-id".to_string(),
+            goal_ids: vec!["goal1".to_string()],
+            intention: "Test intention".to_string(),
+            notes: None,
+            session_state: "Paused".to_string(),
+            start_time: Some(instant("2025-01-01T12:00:00Z")),
+            end_time: None,
+            duration_in_seconds: None,
+            paused_at: Some(instant("2025-01-01T12:20:00Z")),
+            accumulated_seconds: Some(1200),
+            active_study_session_id: None,
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
+        };
+
+        let session = session_row.into_session().unwrap();
+        assert!(session.is_paused());
+        assert_eq!(
+            session.state,
+            SessionState::Paused {
+                start_time: "2025-01-01T12:00:00Z".to_string(),
+                paused_at: "2025-01-01T12:20:00Z".to_string(),
+                accumulated_seconds: 1200,
+            }
+        );
+
+        let (state_str, start_time, _, _, paused_at, accumulated_seconds) =
+            SessionRepository::session_to_row_data(&session).unwrap();
+        assert_eq!(state_str, "Paused");
+        assert_eq!(start_time, Some(instant("2025-01-01T12:00:00Z")));
+        assert_eq!(paused_at, Some(instant("2025-01-01T12:20:00Z")));
+        assert_eq!(accumulated_seconds, Some(1200));
+    }
+
+    #[test]
+    fn test_session_times_must_be_rfc3339() {
+        let mut session = PracticeSession::new(vec![], "Test".to_string());
+        session.start("after lunch".to_string()).unwrap();
+
+        assert!(matches!(
+            SessionRepository::session_to_row_data(&session),
+            Err(RepositoryError::ValidationError(_))
+        ));
+    }
+
     #[test]
     fn test_create_session_request() {
         let request = CreateSessionRequest {
+            id: None,
             goal_ids: vec!["goal1".to_string(), "goal2".to_string()],
             intention: "Test session".to_string(),
             notes: Some("Notes".to_string()),
+            plan_id: None,
+            scheduled_for: None,
         };
 
         assert_eq!(request.goal_ids, vec!["goal1", "goal2"]);
@@ -757,13 +1437,99 @@ mod tests {
     fn test_session_to_row_data() {
         let session = PracticeSession::new(vec!["goal1".to_string()], "Test".to_string());
 
-        let (goal_ids_json, state_str, start_time, end_time, duration_in_seconds) =
+        let (state_str, start_time, end_time, duration_in_seconds, paused_at, accumulated_seconds) =
             SessionRepository::session_to_row_data(&session).unwrap();
 
-        assert_eq!(goal_ids_json, r#"["goal1"]"#);
         assert_eq!(state_str, "NotStarted");
         assert_eq!(start_time, None);
         assert_eq!(end_time, None);
         assert_eq!(duration_in_seconds, None);
+        assert_eq!(paused_at, None);
+        assert_eq!(accumulated_seconds, None);
+    }
+
+    #[tokio::test]
+    async fn test_study_session_of_another_session_is_left_untouched() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let session_repo = SessionRepository::new(pool.clone());
+        let study_repo = crate::studies::StudyRepository::new(pool.clone());
+        let owner = crate::repository::create_test_user(&pool).await;
+        let other_user = crate::repository::create_test_user(&pool).await;
+
+        let study = shared::Study::new("Scales".to_string(), None);
+        study_repo.create(&owner, &study).await.unwrap();
+        let mut session = PracticeSession::new(vec![], "Scales".to_string());
+        let mut study_session = shared::StudySession::new(study.id.clone(), session.id.clone());
+        study_session.score = Some(4);
+        session.study_sessions.push(study_session.clone());
+        session_repo.create(&owner, &session).await.unwrap();
+
+        // Another user's session claims the same study session id
+        let other_study = shared::Study::new("Arpeggios".to_string(), None);
+        study_repo.create(&other_user, &other_study).await.unwrap();
+        let mut other = PracticeSession::new(vec![], "Arpeggios".to_string());
+        session_repo.create(&other_user, &other).await.unwrap();
+        other.study_sessions.push(shared::StudySession {
+            id: study_session.id.clone(),
+            score: Some(1),
+            ..shared::StudySession::new(other_study.id, other.id.clone())
+        });
+
+        let result = session_repo.update(&other_user, &other).await;
+        assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+        let stored = session_repo
+            .find_by_id(&owner, &session.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.study_sessions, vec![study_session]);
+    }
+
+    #[tokio::test]
+    async fn test_plan_occurrence_keeps_one_session() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let session_repo = SessionRepository::new(pool.clone());
+        let plan_repo = crate::plans::PlanRepository::new(pool.clone());
+        let user = crate::repository::create_test_user(&pool).await;
+
+        let plan = shared::PracticePlan::new(
+            "Evenings".to_string(),
+            shared::Recurrence {
+                weekdays: vec![shared::Weekday::Monday],
+                time_of_day: "18:30".to_string(),
+                time_zone: "UTC".to_string(),
+            },
+            "Scales".to_string(),
+            30,
+            "2025-05-05".to_string(),
+        );
+        plan_repo.create(&user, &plan).await.unwrap();
+        let at = DateTime::parse_from_rfc3339("2025-05-05T18:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        // Scheduled on two devices, the same session is sent twice
+        let session = plan.session_for(at);
+        session_repo.create(&user, &session).await.unwrap();
+        session_repo.create(&user, &session).await.unwrap();
+
+        let mut duplicate = PracticeSession::new(vec![], "Scales".to_string());
+        duplicate.plan_id = session.plan_id.clone();
+        duplicate.scheduled_for = session.scheduled_for.clone();
+        let result = session_repo.create(&user, &duplicate).await;
+        assert!(matches!(result, Err(RepositoryError::Conflict(_))));
+
+        let scheduled: Vec<String> = session_repo
+            .find_all(&user)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        assert_eq!(scheduled, vec![session.id]);
     }
 }
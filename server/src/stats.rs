@@ -0,0 +1,490 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    error::field_error,
+    repository::{Database, RepositoryError, RepositoryResult},
+    ApiError,
+};
+
+// Days sent by /stats/daily when no range is given, ending today
+const DEFAULT_DAILY_DAYS: i64 = 30;
+// Longest range /stats/daily accepts, a year including a leap day
+const MAX_DAILY_DAYS: i64 = 366;
+
+/// Seconds practiced on one goal or study
+#[derive(Debug, Serialize, FromRow, PartialEq)]
+pub struct TimeTotal {
+    pub id: String,
+    pub name: String,
+    pub total_seconds: i64,
+}
+
+/// Seconds practiced on one day, zero for days without practice
+#[derive(Debug, Serialize, FromRow, PartialEq)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub total_seconds: i64,
+    pub session_count: i64,
+}
+
+/// Totals over all of the user's ended sessions. Days are calendar days in
+/// `time_zone` and weeks start on Monday.
+#[derive(Debug, Serialize)]
+pub struct StatsSummary {
+    pub time_zone: String,
+    pub today: NaiveDate,
+    pub today_seconds: i64,
+    pub this_week_seconds: i64,
+    pub this_month_seconds: i64,
+    pub total_seconds: i64,
+    pub session_count: i64,
+    pub average_session_seconds: i64,
+    pub current_streak_days: i64,
+    pub longest_streak_days: i64,
+    // Most practiced first
+    pub time_per_goal: Vec<TimeTotal>,
+    pub time_per_study: Vec<TimeTotal>,
+}
+
+/// Totals over the ended sessions linked to one goal
+#[derive(Debug, Serialize)]
+pub struct GoalStats {
+    pub goal_id: String,
+    pub name: String,
+    pub time_zone: String,
+    pub total_seconds: i64,
+    pub session_count: i64,
+    pub first_practiced_on: Option<NaiveDate>,
+    pub last_practiced_on: Option<NaiveDate>,
+    // Most practiced first
+    pub time_per_study: Vec<TimeTotal>,
+}
+
+// Database row structs
+#[derive(FromRow)]
+struct PeriodTotalsRow {
+    session_count: i64,
+    total_seconds: i64,
+    today_seconds: i64,
+    this_week_seconds: i64,
+    this_month_seconds: i64,
+}
+
+#[derive(FromRow)]
+struct GoalTotalsRow {
+    session_count: i64,
+    total_seconds: i64,
+    first_practiced_on: Option<NaiveDate>,
+    last_practiced_on: Option<NaiveDate>,
+}
+
+// A run of consecutive practice days
+#[derive(FromRow)]
+struct StreakRow {
+    last_day: NaiveDate,
+    days: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    // IANA name such as "Europe/London", UTC when omitted
+    pub tz: Option<String>,
+    // Inclusive, only used by /stats/daily
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+// A time zone Postgres knows and what day it is there
+struct LocalDay {
+    time_zone: String,
+    today: NaiveDate,
+}
+
+// The current streak counts until a whole day goes by without practice.
+// `runs` is most recent first.
+fn streaks(runs: &[StreakRow], today: NaiveDate) -> (i64, i64) {
+    let longest = runs.iter().map(|run| run.days).max().unwrap_or(0);
+    let current = match runs.first() {
+        Some(run) if run.last_day == today || run.last_day + Duration::days(1) == today => run.days,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+fn week_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+}
+
+fn month_start(today: NaiveDate) -> NaiveDate {
+    today.with_day(1).unwrap_or(today)
+}
+
+// The inclusive range of days /stats/daily reports on
+fn daily_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), ApiError> {
+    let to = to.unwrap_or(today);
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_DAILY_DAYS - 1));
+
+    if from > to {
+        return Err(ApiError::validation(
+            "Invalid date range",
+            vec![field_error("from", "from must not be after to")],
+        ));
+    }
+    if (to - from).num_days() >= MAX_DAILY_DAYS {
+        return Err(ApiError::validation(
+            "Invalid date range",
+            vec![field_error(
+                "to",
+                &format!("Ranges are limited to {MAX_DAILY_DAYS} days"),
+            )],
+        ));
+    }
+    Ok((from, to))
+}
+
+// Aggregates over sessions - no traits, just methods
+pub struct StatsRepository {
+    db: Database,
+}
+
+impl StatsRepository {
+    pub fn new(pool: crate::repository::DbPool) -> Self {
+        Self {
+            db: Database::new(pool),
+        }
+    }
+
+    async fn local_day(&self, time_zone: Option<&str>) -> RepositoryResult<LocalDay> {
+        let time_zone = time_zone.unwrap_or("UTC");
+        let today = sqlx::query_scalar!(
+            r#"SELECT (now() AT TIME ZONE name)::date AS "today!"
+               FROM pg_timezone_names WHERE name = $1"#,
+            time_zone
+        )
+        .fetch_optional(&self.db.pool)
+        .await?
+        .ok_or_else(|| {
+            RepositoryError::ValidationError(format!("Unknown time zone: {time_zone}"))
+        })?;
+
+        Ok(LocalDay {
+            time_zone: time_zone.to_string(),
+            today,
+        })
+    }
+
+    pub async fn summary(
+        &self,
+        user_id: &str,
+        time_zone: Option<&str>,
+    ) -> RepositoryResult<StatsSummary> {
+        let LocalDay { time_zone, today } = self.local_day(time_zone).await?;
+
+        let totals = sqlx::query_as!(
+            PeriodTotalsRow,
+            r#"SELECT COUNT(*) AS "session_count!",
+                      COALESCE(SUM(duration_in_seconds), 0)::BIGINT AS "total_seconds!",
+                      COALESCE(SUM(duration_in_seconds)
+                          FILTER (WHERE (start_time AT TIME ZONE $2)::date = $3), 0)::BIGINT AS "today_seconds!",
+                      COALESCE(SUM(duration_in_seconds)
+                          FILTER (WHERE (start_time AT TIME ZONE $2)::date BETWEEN $4 AND $3), 0)::BIGINT AS "this_week_seconds!",
+                      COALESCE(SUM(duration_in_seconds)
+                          FILTER (WHERE (start_time AT TIME ZONE $2)::date BETWEEN $5 AND $3), 0)::BIGINT AS "this_month_seconds!"
+               FROM sessions
+               WHERE user_id = $1 AND session_state = 'Ended' AND deleted_at IS NULL"#,
+            user_id,
+            time_zone,
+            today,
+            week_start(today),
+            month_start(today)
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        // Consecutive days share the same difference between day and row number
+        let runs = sqlx::query_as!(
+            StreakRow,
+            r#"WITH days AS (
+                   SELECT DISTINCT (start_time AT TIME ZONE $2)::date AS day
+                   FROM sessions
+                   WHERE user_id = $1 AND session_state = 'Ended' AND deleted_at IS NULL
+                     AND (start_time AT TIME ZONE $2)::date <= $3
+               ), runs AS (
+                   SELECT day, day - (ROW_NUMBER() OVER (ORDER BY day))::INTEGER AS run
+                   FROM days
+               )
+               SELECT MAX(day) AS "last_day!", COUNT(*) AS "days!"
+               FROM runs GROUP BY run ORDER BY MAX(day) DESC"#,
+            user_id,
+            time_zone,
+            today
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        let (current_streak_days, longest_streak_days) = streaks(&runs, today);
+
+        // A session counts in full towards every goal it was for
+        let time_per_goal = sqlx::query_as!(
+            TimeTotal,
+            r#"SELECT g.id AS "id!", g.name AS "name!",
+                      SUM(s.duration_in_seconds)::BIGINT AS "total_seconds!"
+               FROM sessions s
+               JOIN session_goals sg ON sg.session_id = s.id
+               JOIN goals g ON g.id = sg.goal_id AND g.deleted_at IS NULL
+               WHERE s.user_id = $1 AND s.session_state = 'Ended' AND s.deleted_at IS NULL
+               GROUP BY g.id, g.name
+               ORDER BY 3 DESC, 2"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        let time_per_study = sqlx::query_as!(
+            TimeTotal,
+            r#"SELECT st.id AS "id!", st.name AS "name!",
+                      SUM(ss.elapsed_seconds)::BIGINT AS "total_seconds!"
+               FROM study_sessions ss
+               JOIN sessions s ON s.id = ss.session_id
+               JOIN studies st ON st.id = ss.study_id AND st.user_id = s.user_id
+                 AND st.deleted_at IS NULL
+               WHERE s.user_id = $1 AND s.session_state = 'Ended' AND s.deleted_at IS NULL
+               GROUP BY st.id, st.name
+               ORDER BY 3 DESC, 2"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(StatsSummary {
+            time_zone,
+            today,
+            today_seconds: totals.today_seconds,
+            this_week_seconds: totals.this_week_seconds,
+            this_month_seconds: totals.this_month_seconds,
+            total_seconds: totals.total_seconds,
+            session_count: totals.session_count,
+            average_session_seconds: match totals.session_count {
+                0 => 0,
+                count => totals.total_seconds / count,
+            },
+            current_streak_days,
+            longest_streak_days,
+            time_per_goal,
+            time_per_study,
+        })
+    }
+
+    pub async fn daily(
+        &self,
+        user_id: &str,
+        time_zone: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> RepositoryResult<Vec<DailyTotal>> {
+        let days = sqlx::query_as!(
+            DailyTotal,
+            r#"SELECT day::date AS "date!",
+                      COALESCE(SUM(s.duration_in_seconds), 0)::BIGINT AS "total_seconds!",
+                      COUNT(s.id) AS "session_count!"
+               FROM generate_series($3::date, $4::date, INTERVAL '1 day') AS day
+               LEFT JOIN sessions s
+                 ON s.user_id = $1 AND s.session_state = 'Ended' AND s.deleted_at IS NULL
+                AND (s.start_time AT TIME ZONE $2)::date = day::date
+               GROUP BY day
+               ORDER BY day"#,
+            user_id,
+            time_zone,
+            from,
+            to
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(days)
+    }
+
+    pub async fn goal(
+        &self,
+        user_id: &str,
+        goal_id: &str,
+        time_zone: Option<&str>,
+    ) -> RepositoryResult<Option<GoalStats>> {
+        let LocalDay { time_zone, .. } = self.local_day(time_zone).await?;
+
+        let name = sqlx::query_scalar!(
+            "SELECT name FROM goals WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            goal_id,
+            user_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+        let Some(name) = name else {
+            return Ok(None);
+        };
+
+        let totals = sqlx::query_as!(
+            GoalTotalsRow,
+            r#"SELECT COUNT(*) AS "session_count!",
+                      COALESCE(SUM(duration_in_seconds), 0)::BIGINT AS "total_seconds!",
+                      MIN((start_time AT TIME ZONE $3)::date) AS first_practiced_on,
+                      MAX((start_time AT TIME ZONE $3)::date) AS last_practiced_on
+               FROM sessions
+               WHERE user_id = $1 AND session_state = 'Ended' AND deleted_at IS NULL
+                 AND id IN (SELECT session_id FROM session_goals WHERE goal_id = $2)"#,
+            user_id,
+            goal_id,
+            time_zone
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        let time_per_study = sqlx::query_as!(
+            TimeTotal,
+            r#"SELECT st.id AS "id!", st.name AS "name!",
+                      SUM(ss.elapsed_seconds)::BIGINT AS "total_seconds!"
+               FROM study_sessions ss
+               JOIN sessions s ON s.id = ss.session_id
+               JOIN studies st ON st.id = ss.study_id AND st.user_id = s.user_id
+                 AND st.deleted_at IS NULL
+               WHERE s.user_id = $1 AND s.session_state = 'Ended' AND s.deleted_at IS NULL AND s.id IN (SELECT session_id FROM session_goals WHERE goal_id = $2)
+               GROUP BY st.id, st.name
+               ORDER BY 3 DESC, 2"#,
+            user_id,
+            goal_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(Some(GoalStats {
+            goal_id: goal_id.to_string(),
+            name,
+            time_zone,
+            total_seconds: totals.total_seconds,
+            session_count: totals.session_count,
+            first_practiced_on: totals.first_practiced_on,
+            last_practiced_on: totals.last_practiced_on,
+            time_per_study,
+        }))
+    }
+}
+
+// HTTP Handlers
+async fn get_summary(
+    State(stats_repo): State<Arc<StatsRepository>>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<StatsSummary>, ApiError> {
+    let summary = stats_repo.summary(&user_id, query.tz.as_deref()).await?;
+    Ok(Json(summary))
+}
+
+async fn get_daily(
+    State(stats_repo): State<Arc<StatsRepository>>,
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Vec<DailyTotal>>, ApiError> {
+    let LocalDay { time_zone, today } = stats_repo.local_day(query.tz.as_deref()).await?;
+    let (from, to) = daily_range(query.from, query.to, today)?;
+
+    let days = stats_repo.daily(&user_id, &time_zone, from, to).await?;
+    Ok(Json(days))
+}
+
+async fn get_goal_stats(
+    State(stats_repo): State<Arc<StatsRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(goal_id): Path<String>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<GoalStats>, ApiError> {
+    match stats_repo
+        .goal(&user_id, &goal_id, query.tz.as_deref())
+        .await?
+    {
+        Some(stats) => Ok(Json(stats)),
+        None => Err(ApiError::not_found("Goal not found")),
+    }
+}
+
+pub fn routes() -> Router<Arc<StatsRepository>> {
+    Router::new()
+        .route("/stats/summary", get(get_summary))
+        .route("/stats/daily", get(get_daily))
+        .route("/stats/goals/{id}", get(get_goal_stats))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    fn day(date: &str) -> NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn test_streaks() {
+        let runs = vec![
+            StreakRow {
+                last_day: day("2025-04-30"),
+                days: 2,
+            },
+            StreakRow {
+                last_day: day("2025-04-22"),
+                days: 3,
+            },
+        ];
+
+        // Nothing yet today, but practicing later still continues the streak
+        assert_eq!(streaks(&runs, day("2025-05-01")), (2, 3));
+        // A day without practice ends it
+        assert_eq!(streaks(&runs, day("2025-05-02")), (0, 3));
+        assert_eq!(streaks(&[], day("2025-05-02")), (0, 0));
+    }
+
+    #[test]
+    fn test_period_starts() {
+        // Thursday 1 May 2025
+        let today = day("2025-05-01");
+        assert_eq!(week_start(today), day("2025-04-28"));
+        assert_eq!(month_start(today), day("2025-05-01"));
+        assert_eq!(week_start(day("2025-04-28")), day("2025-04-28"));
+    }
+
+    #[test]
+    fn test_daily_range() {
+        let today = day("2025-05-01");
+        assert_eq!(
+            daily_range(None, None, today).unwrap(),
+            (day("2025-04-02"), today)
+        );
+        assert_eq!(
+            daily_range(Some(day("2025-04-28")), None, today).unwrap(),
+            (day("2025-04-28"), today)
+        );
+
+        let error = daily_range(Some(today), Some(day("2025-04-01")), today).unwrap_err();
+        assert_eq!(error.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error.details[0].field, "from");
+
+        let error = daily_range(Some(day("2024-01-01")), None, today).unwrap_err();
+        assert_eq!(error.details[0].field, "to");
+    }
+}
@@ -1,17 +1,24 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use serde::Deserialize;
-use shared::Study;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{DeleteImpact, DeleteMode, EntityKind, Study};
 use sqlx::FromRow;
 use std::sync::Arc;
 
 use crate::{
-    repository::{Database, RepositoryError, RepositoryResult},
+    auth::AuthUser,
+    repository::{
+        archive_entity, check_unreferenced, check_version, count_linking, format_instant,
+        format_timestamp, missing_or_stale, restore_entity, touch_linking, trash_entity, Database,
+        DeleteQuery, ListQuery, RepositoryResult, GOAL_STUDIES, PLAN_STUDIES, STUDY_SESSIONS,
+        TEMPLATE_BLOCKS,
+    },
     ApiError,
 };
 
@@ -21,6 +28,10 @@ pub struct StudyRow {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
+    pub version: i32,
+    pub updated_at: Option<NaiveDateTime>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl StudyRow {
@@ -29,6 +40,41 @@ impl StudyRow {
             id: self.id,
             name: self.name,
             description: self.description,
+            version: self.version as u32,
+            updated_at: self.updated_at.map(format_timestamp),
+            archived_at: self.archived_at.map(format_instant),
+            deleted_at: self.deleted_at.map(format_instant),
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub struct TempoRow {
+    pub study_session_id: String,
+    pub session_id: String,
+    pub tempo: i32,
+    pub clean: bool,
+    pub played_at: Option<DateTime<Utc>>,
+}
+
+/// One logged tempo for a study, oldest first in the history
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TempoEntry {
+    pub study_session_id: String,
+    pub session_id: String,
+    pub tempo: u32,
+    pub clean: bool,
+    pub played_at: Option<String>,
+}
+
+impl TempoRow {
+    pub fn into_entry(self) -> TempoEntry {
+        TempoEntry {
+            study_session_id: self.study_session_id,
+            session_id: self.session_id,
+            tempo: self.tempo as u32,
+            clean: self.clean,
+            played_at: self.played_at.map(format_instant),
         }
     }
 }
@@ -36,6 +82,8 @@ impl StudyRow {
 // Request types
 #[derive(Debug, Deserialize)]
 pub struct CreateStudyRequest {
+    // Client generated id, so optimistic local records keep their identity
+    pub id: Option<String>,
     pub name: String,
     pub description: Option<String>,
 }
@@ -44,6 +92,8 @@ pub struct CreateStudyRequest {
 pub struct UpdateStudyRequest {
     pub name: Option<String>,
     pub description: Option<String>,
+    // The version the client's edit was based on, stale edits are rejected
+    pub version: Option<u32>,
 }
 
 // Simple Study repository - no traits, just methods
@@ -58,22 +108,39 @@ impl StudyRepository {
         }
     }
 
-    pub async fn create(&self, study: &Study) -> RepositoryResult<()> {
-        sqlx::query("INSERT INTO studies (id, name, description) VALUES ($1, $2, $3)")
+    pub async fn create(&self, user_id: &str, study: &Study) -> RepositoryResult<()> {
+        let mut tx = self.db.pool.begin().await?;
+        Self::create_in(&mut tx, user_id, study).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Creates a study as part of a larger transaction, such as an import
+    pub async fn create_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        study: &Study,
+    ) -> RepositoryResult<()> {
+        sqlx::query("INSERT INTO studies (id, name, description, user_id) VALUES ($1, $2, $3, $4)")
             .bind(&study.id)
             .bind(&study.name)
             .bind(&study.description)
-            .execute(&self.db.pool)
+            .bind(user_id)
+            .execute(&mut **tx)
             .await?;
 
         Ok(())
     }
 
-    pub async fn find_by_id(&self, id: &str) -> RepositoryResult<Option<Study>> {
+    pub async fn find_by_id(&self, user_id: &str, id: &str) -> RepositoryResult<Option<Study>> {
         let row = sqlx::query_as!(
             StudyRow,
-            "SELECT id, name, description FROM studies WHERE id = $1",
-            id
+            "SELECT id, name, description, version, updated_at, archived_at, deleted_at
+             FROM studies
+             WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
+            id,
+            user_id
         )
         .fetch_optional(&self.db.pool)
         .await?;
@@ -81,10 +148,13 @@ impl StudyRepository {
         Ok(row.map(|r| r.into_study()))
     }
 
-    pub async fn find_all(&self) -> RepositoryResult<Vec<Study>> {
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Vec<Study>> {
         let rows = sqlx::query_as!(
             StudyRow,
-            "SELECT id, name, description FROM studies ORDER BY created_at DESC"
+            "SELECT id, name, description, version, updated_at, archived_at, deleted_at
+             FROM studies
+             WHERE user_id = $1 AND deleted_at IS NULL ORDER BY created_at DESC",
+            user_id
         )
         .fetch_all(&self.db.pool)
         .await?;
@@ -92,43 +162,227 @@ impl StudyRepository {
         Ok(rows.into_iter().map(|row| row.into_study()).collect())
     }
 
-    pub async fn update(&self, study: &Study) -> RepositoryResult<()> {
-        let result = sqlx::query(
-            "UPDATE studies SET name = $2, description = $3, updated_at = CURRENT_TIMESTAMP 
-             WHERE id = $1",
+    pub async fn update(&self, user_id: &str, study: &Study) -> RepositoryResult<Study> {
+        let mut tx = self.db.pool.begin().await?;
+        let study = Self::update_in(&mut tx, user_id, study).await?;
+        tx.commit().await?;
+
+        Ok(study)
+    }
+
+    /// Updates a study as part of a larger transaction, such as an import
+    pub async fn update_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        study: &Study,
+    ) -> RepositoryResult<Study> {
+        let stamp: Option<(i32, Option<NaiveDateTime>)> = sqlx::query_as(
+            "UPDATE studies SET name = $2, description = $3, version = version + 1,
+             updated_at = CURRENT_TIMESTAMP 
+             WHERE id = $1 AND version = $4 AND user_id = $5
+             RETURNING version, updated_at",
         )
         .bind(&study.id)
         .bind(&study.name)
         .bind(&study.description)
-        .execute(&self.db.pool)
+        .bind(study.version as i32)
+        .bind(user_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let Some((version, updated_at)) = stamp else {
+            return Err(
+                missing_or_stale(&mut **tx, "studies", user_id, &study.id, study.version).await,
+            );
+        };
+
+        Ok(Study {
+            version: version as u32,
+            updated_at: updated_at.map(format_timestamp),
+            ..study.clone()
+        })
+    }
+
+    pub async fn find_changed_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<Study>> {
+        let rows = sqlx::query_as!(
+            StudyRow,
+            "SELECT id, name, description, version, updated_at, archived_at, deleted_at
+             FROM studies
+             WHERE user_id = $1 AND change_xid >= $2 AND deleted_at IS NULL ORDER BY updated_at",
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
         .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(RepositoryError::NotFound(format!(
-                "Study with id {}",
-                study.id
-            )));
+        Ok(rows.into_iter().map(|row| row.into_study()).collect())
+    }
+
+    /// Moves a study to the trash, detaching it from the goals, plans,
+    /// templates and sessions that list it unless `mode` blocks the delete
+    /// while anything refers to it. The links themselves are kept until the
+    /// purge, so a restore puts it back where it was.
+    pub async fn delete(
+        &self,
+        user_id: &str,
+        id: &str,
+        mode: DeleteMode,
+    ) -> RepositoryResult<bool> {
+        let mut tx = self.db.pool.begin().await?;
+
+        if mode == DeleteMode::BlockIfReferenced {
+            let impact = DeleteImpact::new(
+                id.to_string(),
+                count_linking(&mut tx, &STUDY_SESSIONS, user_id, id).await?,
+                count_linking(&mut tx, &GOAL_STUDIES, user_id, id).await?,
+                count_linking(&mut tx, &PLAN_STUDIES, user_id, id).await?,
+                count_linking(&mut tx, &TEMPLATE_BLOCKS, user_id, id).await?,
+            );
+            check_unreferenced("Study", &impact)?;
         }
+        touch_linking(&mut tx, &GOAL_STUDIES, user_id, id).await?;
+        touch_linking(&mut tx, &PLAN_STUDIES, user_id, id).await?;
+        touch_linking(&mut tx, &TEMPLATE_BLOCKS, user_id, id).await?;
+        touch_linking(&mut tx, &STUDY_SESSIONS, user_id, id).await?;
+        sqlx::query(
+            "UPDATE sessions SET active_study_session_id = NULL
+             WHERE user_id = $2
+               AND active_study_session_id IN (SELECT id FROM study_sessions WHERE study_id = $1)",
+        )
+        .bind(id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        let deleted = trash_entity(&mut tx, "studies", EntityKind::Study, user_id, id).await?;
+        tx.commit().await?;
 
-        Ok(())
+        Ok(deleted)
     }
 
-    pub async fn delete(&self, id: &str) -> RepositoryResult<bool> {
-        let result = sqlx::query("DELETE FROM studies WHERE id = $1")
-            .bind(id)
-            .execute(&self.db.pool)
+    pub async fn archive(&self, user_id: &str, id: &str) -> RepositoryResult<Option<Study>> {
+        if !archive_entity(&self.db.pool, "studies", user_id, id).await? {
+            return Ok(None);
+        }
+        self.find_by_id(user_id, id).await
+    }
+
+    /// Brings a study back from the archive or the trash, along with its
+    /// study sessions and its place in goals, plans and templates
+    pub async fn restore(&self, user_id: &str, id: &str) -> RepositoryResult<Option<Study>> {
+        let mut tx = self.db.pool.begin().await?;
+
+        if !Self::restore_in(&mut tx, user_id, id).await? {
+            return Ok(None);
+        }
+        tx.commit().await?;
+
+        self.find_by_id(user_id, id).await
+    }
+
+    /// Restores a study as part of a larger transaction, returning whether
+    /// the user has it
+    pub async fn restore_in(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<bool> {
+        if !restore_entity(tx, "studies", EntityKind::Study, user_id, id).await? {
+            return Ok(false);
+        }
+        touch_linking(tx, &GOAL_STUDIES, user_id, id).await?;
+        touch_linking(tx, &PLAN_STUDIES, user_id, id).await?;
+        touch_linking(tx, &TEMPLATE_BLOCKS, user_id, id).await?;
+        touch_linking(tx, &STUDY_SESSIONS, user_id, id).await?;
+
+        Ok(true)
+    }
+
+    pub async fn find_trashed(&self, user_id: &str) -> RepositoryResult<Vec<Study>> {
+        let rows = sqlx::query_as!(
+            StudyRow,
+            "SELECT id, name, description, version, updated_at, archived_at, deleted_at
+             FROM studies
+             WHERE user_id = $1 AND deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.into_study()).collect())
+    }
+
+    /// Deletes every user's studies that have been in the trash since before
+    /// `before`, with their study sessions
+    pub async fn purge_deleted_before(&self, before: DateTime<Utc>) -> RepositoryResult<u64> {
+        let mut tx = self.db.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE sessions SET active_study_session_id = NULL
+             WHERE active_study_session_id IN (
+                SELECT ss.id FROM study_sessions ss JOIN studies st ON st.id = ss.study_id
+                WHERE st.deleted_at < $1)",
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "DELETE FROM study_sessions
+             WHERE study_id IN (SELECT id FROM studies WHERE deleted_at < $1)",
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        let result = sqlx::query("DELETE FROM studies WHERE deleted_at < $1")
+            .bind(before)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(result.rows_affected())
     }
 
     // Domain-specific methods - no trait constraints
-    pub async fn _find_by_name_pattern(&self, pattern: &str) -> RepositoryResult<Vec<Study>> {
-        let search_pattern = format!("%{pattern}%");
+    pub async fn tempo_history(
+        &self,
+        user_id: &str,
+        study_id: &str,
+    ) -> RepositoryResult<Vec<TempoEntry>> {
+        let rows = sqlx::query_as!(
+            TempoRow,
+            r#"SELECT ss.id AS study_session_id, ss.session_id, ss.tempo AS "tempo!", ss.clean,
+             COALESCE(ss.end_time, ss.start_time, s.start_time) AS played_at
+             FROM study_sessions ss JOIN sessions s ON s.id = ss.session_id
+             WHERE s.user_id = $1 AND ss.study_id = $2 AND ss.tempo IS NOT NULL
+               AND s.deleted_at IS NULL
+             ORDER BY played_at NULLS LAST, ss.created_at, ss.id"#,
+            user_id,
+            study_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TempoRow::into_entry).collect())
+    }
+
+    /// Studies whose name contains the text, ignoring case. `%` and `_` in
+    /// the text match themselves rather than any characters.
+    pub async fn find_by_name_pattern(
+        &self,
+        user_id: &str,
+        pattern: &str,
+    ) -> RepositoryResult<Vec<Study>> {
+        let search_pattern = format!("%{}%", escape_like(pattern));
         let rows = sqlx::query_as!(
             StudyRow,
-            "SELECT id, name, description FROM studies 
-             WHERE LOWER(name) LIKE LOWER($1) ORDER BY created_at DESC",
+            "SELECT id, name, description, version, updated_at, archived_at, deleted_at
+             FROM studies 
+             WHERE user_id = $1 AND LOWER(name) LIKE LOWER($2) AND deleted_at IS NULL
+             ORDER BY created_at DESC",
+            user_id,
             search_pattern
         )
         .fetch_all(&self.db.pool)
@@ -138,111 +392,138 @@ impl StudyRepository {
     }
 }
 
+// Backslash is LIKE's default escape character
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 // HTTP Handlers
 async fn create_study(
     State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
     Json(req): Json<CreateStudyRequest>,
-) -> Result<Json<Study>, (StatusCode, Json<ApiError>)> {
-    let study = Study::new(req.name, req.description);
+) -> Result<Json<Study>, ApiError> {
+    let mut study = Study::new(req.name, req.description);
+    if let Some(id) = req.id {
+        study.id = id;
+    }
 
-    study_repo
-        .create(&study)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    study_repo.create(&user_id, &study).await?;
 
     Ok(Json(study))
 }
 
 async fn get_studies(
     State(study_repo): State<Arc<StudyRepository>>,
-) -> Result<Json<Vec<Study>>, (StatusCode, Json<ApiError>)> {
-    let studies = study_repo
-        .find_all()
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    AuthUser(user_id): AuthUser,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<Study>>, ApiError> {
+    let mut studies = study_repo.find_all(&user_id).await?;
+    studies.retain(|s| query.include_archived || s.archived_at.is_none());
 
     Ok(Json(studies))
 }
 
 async fn get_study(
     State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<Json<Study>, (StatusCode, Json<ApiError>)> {
-    let study = study_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+) -> Result<Json<Study>, ApiError> {
+    let study = study_repo.find_by_id(&user_id, &id).await?;
 
     match study {
         Some(study) => Ok(Json(study)),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Study not found".to_string(),
-            }),
-        )),
+        None => Err(ApiError::not_found("Study not found")),
     }
 }
 
 async fn update_study(
     State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
     Json(req): Json<UpdateStudyRequest>,
-) -> Result<Json<Study>, (StatusCode, Json<ApiError>)> {
+) -> Result<Json<Study>, ApiError> {
     // Get existing study
-    let existing_study = study_repo
-        .find_by_id(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let existing_study = study_repo.find_by_id(&user_id, &id).await?;
 
     let existing_study = match existing_study {
         Some(study) => study,
         None => {
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(ApiError {
-                    message: "Study not found".to_string(),
-                }),
-            ));
+            return Err(ApiError::not_found("Study not found"));
         }
     };
 
+    check_version("Study", req.version, existing_study.version)?;
+
     // Create updated study with new values or existing ones
     let updated_study = Study {
         id: existing_study.id,
         name: req.name.unwrap_or(existing_study.name),
         description: req.description.or(existing_study.description),
+        version: existing_study.version,
+        updated_at: existing_study.updated_at,
+        archived_at: existing_study.archived_at,
+        deleted_at: existing_study.deleted_at,
     };
 
-    study_repo
-        .update(&updated_study)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    let updated_study = study_repo.update(&user_id, &updated_study).await?;
 
     Ok(Json(updated_study))
 }
 
 async fn delete_study(
     State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
     Path(id): Path<String>,
-) -> Result<StatusCode, (StatusCode, Json<ApiError>)> {
-    let deleted = study_repo
-        .delete(&id)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(e.into())))?;
+    Query(query): Query<DeleteQuery>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = study_repo.delete(&user_id, &id, query.mode).await?;
 
     if deleted {
         Ok(StatusCode::NO_CONTENT)
     } else {
-        Err((
-            StatusCode::NOT_FOUND,
-            Json(ApiError {
-                message: "Study not found".to_string(),
-            }),
-        ))
+        Err(ApiError::not_found("Study not found"))
+    }
+}
+
+async fn archive_study(
+    State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Study>, ApiError> {
+    match study_repo.archive(&user_id, &id).await? {
+        Some(study) => Ok(Json(study)),
+        None => Err(ApiError::not_found("Study not found")),
     }
 }
 
+async fn restore_study(
+    State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Study>, ApiError> {
+    match study_repo.restore(&user_id, &id).await? {
+        Some(study) => Ok(Json(study)),
+        None => Err(ApiError::not_found("Study not found")),
+    }
+}
+
+async fn get_tempo_history(
+    State(study_repo): State<Arc<StudyRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<TempoEntry>>, ApiError> {
+    if study_repo.find_by_id(&user_id, &id).await?.is_none() {
+        return Err(ApiError::not_found("Study not found"));
+    }
+
+    let history = study_repo.tempo_history(&user_id, &id).await?;
+
+    Ok(Json(history))
+}
+
 pub fn routes() -> Router<Arc<StudyRepository>> {
     Router::new()
         .route("/studies", get(get_studies).post(create_study))
@@ -250,6 +531,9 @@ pub fn routes() -> Router<Arc<StudyRepository>> {
             "/studies/{id}",
             get(get_study).put(update_study).delete(delete_study),
         )
+        .route("/studies/{id}/archive", post(archive_study))
+        .route("/studies/{id}/restore", post(restore_study))
+        .route("/studies/{id}/tempo", get(get_tempo_history))
 }
 
 // *************
@@ -259,6 +543,7 @@ pub fn routes() -> Router<Arc<StudyRepository>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::repository::RepositoryError;
     use std::sync::Mutex;
 
     // Simple mock repository for testing
@@ -316,6 +601,10 @@ mod tests {
             id: "test-id".to_string(),
             name: "Test Study".to_string(),
             description: Some("Test description".to_string()),
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         };
 
         let study = study_row.into_study();
@@ -324,9 +613,28 @@ mod tests {
         assert_eq!(study.description, Some("Test description".to_string()));
     }
 
+    #[test]
+    fn test_tempo_row_conversion() {
+        let row = TempoRow {
+            study_session_id: "study-session-1".to_string(),
+            session_id: "session-1".to_string(),
+            tempo: 132,
+            clean: true,
+            played_at: Some(
+                crate::repository::parse_instant("played_at", "2025-01-01T12:30:00+01:00").unwrap(),
+            ),
+        };
+
+        let entry = row.into_entry();
+        assert_eq!(entry.tempo, 132);
+        assert!(entry.clean);
+        assert_eq!(entry.played_at.as_deref(), Some("2025-01-01T11:30:00Z"));
+    }
+
     #[test]
     fn test_create_study_request_validation() {
         let request = CreateStudyRequest {
+            id: None,
             name: "Test Study".to_string(),
             description: Some("Description".to_string()),
         };
@@ -340,6 +648,7 @@ mod tests {
         let request = UpdateStudyRequest {
             name: Some("Updated Name".to_string()),
             description: None,
+            version: None,
         };
 
         assert_eq!(request.name, Some("Updated Name".to_string()));
@@ -349,6 +658,7 @@ mod tests {
     #[test]
     fn test_create_study_request_no_description() {
         let request = CreateStudyRequest {
+            id: None,
             name: "Test Study".to_string(),
             description: None,
         };
@@ -391,4 +701,34 @@ mod tests {
         let found = mock_repo.find_by_id(&study_id).await.unwrap();
         assert!(found.is_none());
     }
+
+    #[tokio::test]
+    async fn test_name_pattern_matches_wildcards_literally() {
+        let Some(pool) = crate::repository::test_pool().await else {
+            return;
+        };
+        let study_repo = StudyRepository::new(pool.clone());
+        let user = crate::repository::create_test_user(&pool).await;
+
+        let percent = Study::new("100% Scales".to_string(), None);
+        let underscore = Study::new("Etude_1".to_string(), None);
+        let plain = Study::new("Etude 12".to_string(), None);
+        for study in [&percent, &underscore, &plain] {
+            study_repo.create(&user, study).await.unwrap();
+        }
+
+        let found = study_repo.find_by_name_pattern(&user, "%").await.unwrap();
+        assert_eq!(
+            vec![percent.id],
+            found.into_iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+        let found = study_repo
+            .find_by_name_pattern(&user, "etude_")
+            .await
+            .unwrap();
+        assert_eq!(
+            vec![underscore.id],
+            found.into_iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+    }
 }
@@ -0,0 +1,350 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use shared::{PracticeSession, StudySession, StudySessionState};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    repository::{format_instant, parse_instant, RepositoryError, RepositoryResult},
+    sessions::SessionRepository,
+    ApiError,
+};
+
+// Type alias to simplify complex return type
+type StudySessionRowData = (
+    String,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    i32,
+);
+
+// Database row struct - flattened representation for storage
+#[derive(FromRow)]
+pub struct StudySessionRow {
+    pub id: String,
+    pub session_id: String,
+    pub study_id: String,
+    pub score: Option<i32>,
+    pub state: String, // "NotStarted", "Active", "Paused", "Completed"
+    pub start_time: Option<DateTime<Utc>>,
+    pub resumed_at: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub elapsed_seconds: i32,
+    pub tempo: Option<i32>,
+    pub clean: bool,
+    pub planned_minutes: Option<i32>,
+}
+
+impl StudySessionRow {
+    pub fn into_study_session(self) -> RepositoryResult<StudySession> {
+        let missing = |field: &str| {
+            RepositoryError::ValidationError(format!(
+                "{} study session missing {field}",
+                self.state
+            ))
+        };
+        let elapsed_seconds = self.elapsed_seconds as u32;
+        let start_time = self.start_time.map(format_instant);
+        let resumed_at = self.resumed_at.map(format_instant);
+        let end_time = self.end_time.map(format_instant);
+
+        let state = match self.state.as_str() {
+            "NotStarted" => StudySessionState::NotStarted,
+            "Active" => StudySessionState::Active {
+                start_time: start_time.ok_or_else(|| missing("start_time"))?,
+                resumed_at: resumed_at.ok_or_else(|| missing("resumed_at"))?,
+                elapsed_seconds,
+            },
+            "Paused" => StudySessionState::Paused {
+                start_time: start_time.ok_or_else(|| missing("start_time"))?,
+                elapsed_seconds,
+            },
+            "Completed" => StudySessionState::Completed {
+                start_time: start_time.ok_or_else(|| missing("start_time"))?,
+                end_time: end_time.ok_or_else(|| missing("end_time"))?,
+                elapsed_seconds,
+            },
+            other => {
+                return Err(RepositoryError::ValidationError(format!(
+                    "Unknown study session state: {other}"
+                )))
+            }
+        };
+
+        Ok(StudySession {
+            id: self.id,
+            study_id: self.study_id,
+            session_id: self.session_id,
+            score: self.score.map(|s| s as u32),
+            state,
+            tempo: self.tempo.map(|t| t as u32),
+            clean: self.clean,
+            planned_minutes: self.planned_minutes.map(|m| m as u32),
+        })
+    }
+}
+
+pub fn study_session_to_row_data(
+    study_session: &StudySession,
+) -> RepositoryResult<StudySessionRowData> {
+    let instant = |field: &str, time: &str| parse_instant(field, time).map(Some);
+
+    Ok(match &study_session.state {
+        StudySessionState::NotStarted => ("NotStarted".to_string(), None, None, None, 0),
+        StudySessionState::Active {
+            start_time,
+            resumed_at,
+            elapsed_seconds,
+        } => (
+            "Active".to_string(),
+            instant("start_time", start_time)?,
+            instant("resumed_at", resumed_at)?,
+            None,
+            *elapsed_seconds as i32,
+        ),
+        StudySessionState::Paused {
+            start_time,
+            elapsed_seconds,
+        } => (
+            "Paused".to_string(),
+            instant("start_time", start_time)?,
+            None,
+            None,
+            *elapsed_seconds as i32,
+        ),
+        StudySessionState::Completed {
+            start_time,
+            end_time,
+            elapsed_seconds,
+        } => (
+            "Completed".to_string(),
+            instant("start_time", start_time)?,
+            None,
+            instant("end_time", end_time)?,
+            *elapsed_seconds as i32,
+        ),
+    })
+}
+
+// Keep the parent's active study in line with the study session the client sent
+fn apply_study_session(session: &mut PracticeSession, study_session: StudySession) {
+    let id = study_session.id.clone();
+    let is_active = study_session.is_active();
+
+    if session.study_sessions.iter().any(|s| s.id == id) {
+        session.update_study_session(study_session);
+    } else {
+        session.push_study_session(study_session);
+    }
+
+    if is_active {
+        session.active_study_session_id = Some(id);
+    } else if session.active_study_session_id.as_deref() == Some(id.as_str()) {
+        session.active_study_session_id = None;
+    }
+}
+
+async fn find_session(
+    session_repo: &SessionRepository,
+    user_id: &str,
+    session_id: &str,
+) -> Result<PracticeSession, ApiError> {
+    let session = session_repo.find_by_id(user_id, session_id).await?;
+
+    session.ok_or_else(|| ApiError::not_found("Session not found"))
+}
+
+// HTTP Handlers
+async fn get_study_sessions(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<StudySession>>, ApiError> {
+    let session = find_session(&session_repo, &user_id, &session_id).await?;
+    Ok(Json(session.study_sessions))
+}
+
+async fn create_study_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(session_id): Path<String>,
+    Json(mut study_session): Json<StudySession>,
+) -> Result<Json<StudySession>, ApiError> {
+    let mut session = find_session(&session_repo, &user_id, &session_id).await?;
+
+    study_session.session_id = session_id;
+    apply_study_session(&mut session, study_session.clone());
+
+    session_repo.update(&user_id, &session).await?;
+
+    Ok(Json(study_session))
+}
+
+async fn update_study_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path((session_id, study_session_id)): Path<(String, String)>,
+    Json(mut study_session): Json<StudySession>,
+) -> Result<Json<StudySession>, ApiError> {
+    let mut session = find_session(&session_repo, &user_id, &session_id).await?;
+
+    if !session
+        .study_sessions
+        .iter()
+        .any(|s| s.id == study_session_id)
+    {
+        return Err(ApiError::not_found("Study session not found"));
+    }
+
+    study_session.id = study_session_id;
+    study_session.session_id = session_id;
+    apply_study_session(&mut session, study_session.clone());
+
+    session_repo.update(&user_id, &session).await?;
+
+    Ok(Json(study_session))
+}
+
+async fn delete_study_session(
+    State(session_repo): State<Arc<SessionRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path((session_id, study_session_id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let mut session = find_session(&session_repo, &user_id, &session_id).await?;
+
+    let count_before = session.study_sessions.len();
+    session.study_sessions.retain(|s| s.id != study_session_id);
+    if session.study_sessions.len() == count_before {
+        return Err(ApiError::not_found("Study session not found"));
+    }
+    if session.active_study_session_id.as_deref() == Some(study_session_id.as_str()) {
+        session.active_study_session_id = None;
+    }
+
+    session_repo.update(&user_id, &session).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub fn routes() -> Router<Arc<SessionRepository>> {
+    Router::new()
+        .route(
+            "/sessions/{id}/studies",
+            get(get_study_sessions).post(create_study_session),
+        )
+        .route(
+            "/sessions/{id}/studies/{study_session_id}",
+            put(update_study_session).delete(delete_study_session),
+        )
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instant(time: &str) -> DateTime<Utc> {
+        parse_instant("time", time).unwrap()
+    }
+
+    fn create_row(state: &str) -> StudySessionRow {
+        StudySessionRow {
+            id: "study-session-1".to_string(),
+            session_id: "session-1".to_string(),
+            study_id: "study-1".to_string(),
+            score: Some(7),
+            state: state.to_string(),
+            start_time: Some(instant("2025-01-01T12:00:00Z")),
+            resumed_at: Some(instant("2025-01-01T12:10:00Z")),
+            end_time: Some(instant("2025-01-01T12:30:00Z")),
+            elapsed_seconds: 600,
+            tempo: Some(96),
+            clean: true,
+            planned_minutes: Some(5),
+        }
+    }
+
+    #[test]
+    fn test_study_session_row_conversion_active() {
+        let study_session = create_row("Active").into_study_session().unwrap();
+        assert_eq!(study_session.score, Some(7));
+        assert_eq!(
+            study_session.state,
+            StudySessionState::Active {
+                start_time: "2025-01-01T12:00:00Z".to_string(),
+                resumed_at: "2025-01-01T12:10:00Z".to_string(),
+                elapsed_seconds: 600,
+            }
+        );
+    }
+
+    #[test]
+    fn test_study_session_row_conversion_completed() {
+        let study_session = create_row("Completed").into_study_session().unwrap();
+        assert!(study_session.is_completed());
+        assert_eq!(study_session.tempo, Some(96));
+        assert_eq!(study_session.planned_minutes, Some(5));
+        assert!(study_session.is_clean_at(96));
+        assert_eq!(
+            study_session.elapsed_seconds_at("2025-01-01T13:00:00Z"),
+            600
+        );
+    }
+
+    #[test]
+    fn test_study_session_row_conversion_rejects_unknown_state() {
+        assert!(create_row("Bogus").into_study_session().is_err());
+
+        let mut row = create_row("Active");
+        row.resumed_at = None;
+        assert!(row.into_study_session().is_err());
+    }
+
+    #[test]
+    fn test_study_session_to_row_data_round_trip() {
+        let study_session = create_row("Paused").into_study_session().unwrap();
+        let (state, start_time, resumed_at, end_time, elapsed_seconds) =
+            study_session_to_row_data(&study_session).unwrap();
+
+        assert_eq!(state, "Paused");
+        assert_eq!(start_time, Some(instant("2025-01-01T12:00:00Z")));
+        assert_eq!(resumed_at, None);
+        assert_eq!(end_time, None);
+        assert_eq!(elapsed_seconds, 600);
+    }
+
+    #[test]
+    fn test_apply_study_session_tracks_active_study() {
+        let mut session = PracticeSession::new(vec![], "Test".to_string());
+        let mut study_session = StudySession::new("study-1".to_string(), session.id.clone());
+        study_session
+            .start("2025-01-01T12:00:00Z".to_string())
+            .unwrap();
+
+        apply_study_session(&mut session, study_session.clone());
+        assert_eq!(session.study_sessions.len(), 1);
+        assert_eq!(
+            session.active_study_session_id.as_deref(),
+            Some(study_session.id.as_str())
+        );
+
+        study_session
+            .complete("2025-01-01T12:20:00Z".to_string())
+            .unwrap();
+        apply_study_session(&mut session, study_session);
+        assert_eq!(session.study_sessions.len(), 1);
+        assert!(session.study_sessions[0].is_completed());
+        assert!(session.active_study_session_id.is_none());
+    }
+}
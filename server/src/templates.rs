@@ -0,0 +1,443 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use shared::{EntityKind, SessionTemplate, TemplateBlock};
+use sqlx::FromRow;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser,
+    repository::{
+        check_version, format_timestamp, missing_or_stale, record_tombstone, save_links, Database,
+        RepositoryError, RepositoryResult, TEMPLATE_GOALS,
+    },
+    ApiError,
+};
+
+// Database row struct
+#[derive(FromRow)]
+pub struct TemplateRow {
+    pub id: String,
+    pub name: String,
+    pub intention: String,
+    pub goal_ids: Vec<String>, // from template_goals, in order
+    // from template_blocks, in order
+    pub block_study_ids: Vec<String>,
+    pub block_minutes: Vec<i32>,
+    pub version: i32,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl TemplateRow {
+    pub fn into_template(self) -> SessionTemplate {
+        let blocks = self
+            .block_study_ids
+            .into_iter()
+            .zip(self.block_minutes)
+            .map(|(study_id, minutes)| TemplateBlock {
+                study_id,
+                planned_minutes: minutes as u32,
+            })
+            .collect();
+
+        SessionTemplate {
+            id: self.id,
+            name: self.name,
+            intention: self.intention,
+            goal_ids: self.goal_ids,
+            blocks,
+            version: self.version as u32,
+            updated_at: self.updated_at.map(format_timestamp),
+        }
+    }
+}
+
+// Request types
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplateRequest {
+    // Client generated id, so optimistic local records keep their identity
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub intention: String,
+    #[serde(default)]
+    pub goal_ids: Vec<String>,
+    #[serde(default)]
+    pub blocks: Vec<TemplateBlock>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTemplateRequest {
+    pub name: Option<String>,
+    pub intention: Option<String>,
+    pub goal_ids: Option<Vec<String>>,
+    pub blocks: Option<Vec<TemplateBlock>>,
+    // The version the client's edit was based on, stale edits are rejected
+    pub version: Option<u32>,
+}
+
+// Simple Template repository - no traits, just methods
+pub struct TemplateRepository {
+    db: Database,
+}
+
+impl TemplateRepository {
+    pub fn new(pool: crate::repository::DbPool) -> Self {
+        Self {
+            db: Database::new(pool),
+        }
+    }
+
+    fn validate_blocks(blocks: &[TemplateBlock]) -> RepositoryResult<()> {
+        if blocks.iter().any(|block| block.planned_minutes == 0) {
+            return Err(RepositoryError::ValidationError(
+                "planned_minutes must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Replaces a template's goals and blocks. Every block's study must be one
+    // of the user's studies.
+    async fn save_template_children(
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+        user_id: &str,
+        template: &SessionTemplate,
+    ) -> RepositoryResult<()> {
+        save_links(
+            tx,
+            &TEMPLATE_GOALS,
+            user_id,
+            &template.id,
+            &template.goal_ids,
+        )
+        .await?;
+
+        sqlx::query("DELETE FROM template_blocks WHERE template_id = $1")
+            .bind(&template.id)
+            .execute(&mut **tx)
+            .await?;
+
+        let study_ids: Vec<&str> = template
+            .blocks
+            .iter()
+            .map(|b| b.study_id.as_str())
+            .collect();
+        let minutes: Vec<i32> = template
+            .blocks
+            .iter()
+            .map(|b| b.planned_minutes as i32)
+            .collect();
+        let inserted = sqlx::query(
+            "INSERT INTO template_blocks (template_id, position, study_id, planned_minutes)
+             SELECT $1, block.position::INTEGER, block.study_id, block.minutes
+             FROM unnest($2::VARCHAR[], $3::INTEGER[]) WITH ORDINALITY AS block(study_id, minutes, position)
+             JOIN studies ON studies.id = block.study_id AND studies.user_id = $4",
+        )
+        .bind(&template.id)
+        .bind(&study_ids)
+        .bind(&minutes)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+        if inserted.rows_affected() < template.blocks.len() as u64 {
+            return Err(RepositoryError::ValidationError(
+                "study_id refers to studies that don't exist".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn create(&self, user_id: &str, template: &SessionTemplate) -> RepositoryResult<()> {
+        Self::validate_blocks(&template.blocks)?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO session_templates (id, name, intention, user_id) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(&template.intention)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        Self::save_template_children(&mut tx, user_id, template).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(
+        &self,
+        user_id: &str,
+        id: &str,
+    ) -> RepositoryResult<Option<SessionTemplate>> {
+        let row = sqlx::query_as!(
+            TemplateRow,
+            r#"SELECT id, name, intention,
+             ARRAY(SELECT goal_id FROM template_goals JOIN goals ON goals.id = goal_id WHERE template_id = session_templates.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_study_ids!",
+             ARRAY(SELECT planned_minutes FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_minutes!",
+             version, updated_at
+             FROM session_templates WHERE id = $1 AND user_id = $2"#,
+            id,
+            user_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+
+        Ok(row.map(TemplateRow::into_template))
+    }
+
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Vec<SessionTemplate>> {
+        let rows = sqlx::query_as!(
+            TemplateRow,
+            r#"SELECT id, name, intention,
+             ARRAY(SELECT goal_id FROM template_goals JOIN goals ON goals.id = goal_id WHERE template_id = session_templates.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_study_ids!",
+             ARRAY(SELECT planned_minutes FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_minutes!",
+             version, updated_at
+             FROM session_templates WHERE user_id = $1 ORDER BY created_at DESC"#,
+            user_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TemplateRow::into_template).collect())
+    }
+
+    pub async fn update(
+        &self,
+        user_id: &str,
+        template: &SessionTemplate,
+    ) -> RepositoryResult<SessionTemplate> {
+        Self::validate_blocks(&template.blocks)?;
+
+        let mut tx = self.db.pool.begin().await?;
+
+        let stamp: Option<(i32, Option<NaiveDateTime>)> = sqlx::query_as(
+            "UPDATE session_templates SET name = $2, intention = $3, version = version + 1,
+             updated_at = CURRENT_TIMESTAMP
+             WHERE id = $1 AND version = $4 AND user_id = $5
+             RETURNING version, updated_at",
+        )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(&template.intention)
+        .bind(template.version as i32)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((version, updated_at)) = stamp else {
+            return Err(missing_or_stale(
+                &mut *tx,
+                "session_templates",
+                user_id,
+                &template.id,
+                template.version,
+            )
+            .await);
+        };
+
+        Self::save_template_children(&mut tx, user_id, template).await?;
+        tx.commit().await?;
+
+        Ok(SessionTemplate {
+            version: version as u32,
+            updated_at: updated_at.map(format_timestamp),
+            ..template.clone()
+        })
+    }
+
+    pub async fn find_changed_since(
+        &self,
+        user_id: &str,
+        since: i64,
+    ) -> RepositoryResult<Vec<SessionTemplate>> {
+        let rows = sqlx::query_as!(
+            TemplateRow,
+            r#"SELECT id, name, intention,
+             ARRAY(SELECT goal_id FROM template_goals JOIN goals ON goals.id = goal_id WHERE template_id = session_templates.id AND goals.deleted_at IS NULL ORDER BY position) AS "goal_ids!",
+             ARRAY(SELECT study_id FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_study_ids!",
+             ARRAY(SELECT planned_minutes FROM template_blocks JOIN studies ON studies.id = study_id WHERE template_id = session_templates.id AND studies.deleted_at IS NULL ORDER BY position) AS "block_minutes!",
+             version, updated_at
+             FROM session_templates WHERE user_id = $1 AND change_xid >= $2 ORDER BY updated_at"#,
+            user_id,
+            since
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TemplateRow::into_template).collect())
+    }
+
+    pub async fn delete(&self, user_id: &str, id: &str) -> RepositoryResult<bool> {
+        let mut tx = self.db.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM session_templates WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() > 0 {
+            record_tombstone(&mut *tx, user_id, EntityKind::Template, id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// HTTP Handlers
+async fn create_template(
+    State(template_repo): State<Arc<TemplateRepository>>,
+    AuthUser(user_id): AuthUser,
+    Json(req): Json<CreateTemplateRequest>,
+) -> Result<Json<SessionTemplate>, ApiError> {
+    let mut template = SessionTemplate::new(req.name, req.intention);
+    template.goal_ids = req.goal_ids;
+    template.blocks = req.blocks;
+    if let Some(id) = req.id {
+        template.id = id;
+    }
+
+    template_repo.create(&user_id, &template).await?;
+
+    Ok(Json(template))
+}
+
+async fn get_templates(
+    State(template_repo): State<Arc<TemplateRepository>>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<Vec<SessionTemplate>>, ApiError> {
+    let templates = template_repo.find_all(&user_id).await?;
+
+    Ok(Json(templates))
+}
+
+async fn get_template(
+    State(template_repo): State<Arc<TemplateRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<Json<SessionTemplate>, ApiError> {
+    let template = template_repo.find_by_id(&user_id, &id).await?;
+
+    match template {
+        Some(template) => Ok(Json(template)),
+        None => Err(ApiError::not_found("Template not found")),
+    }
+}
+
+async fn update_template(
+    State(template_repo): State<Arc<TemplateRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateTemplateRequest>,
+) -> Result<Json<SessionTemplate>, ApiError> {
+    let Some(existing_template) = template_repo.find_by_id(&user_id, &id).await? else {
+        return Err(ApiError::not_found("Template not found"));
+    };
+
+    check_version("Template", req.version, existing_template.version)?;
+
+    // Create updated template with new values or existing ones
+    let updated_template = SessionTemplate {
+        id: existing_template.id,
+        name: req.name.unwrap_or(existing_template.name),
+        intention: req.intention.unwrap_or(existing_template.intention),
+        goal_ids: req.goal_ids.unwrap_or(existing_template.goal_ids),
+        blocks: req.blocks.unwrap_or(existing_template.blocks),
+        version: existing_template.version,
+        updated_at: existing_template.updated_at,
+    };
+
+    let updated_template = template_repo.update(&user_id, &updated_template).await?;
+
+    Ok(Json(updated_template))
+}
+
+async fn delete_template(
+    State(template_repo): State<Arc<TemplateRepository>>,
+    AuthUser(user_id): AuthUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let deleted = template_repo.delete(&user_id, &id).await?;
+
+    if deleted {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ApiError::not_found("Template not found"))
+    }
+}
+
+pub fn routes() -> Router<Arc<TemplateRepository>> {
+    Router::new()
+        .route("/templates", get(get_templates).post(create_template))
+        .route(
+            "/templates/{id}",
+            get(get_template)
+                .put(update_template)
+                .delete(delete_template),
+        )
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_row_conversion() {
+        let row = TemplateRow {
+            id: "template-1".to_string(),
+            name: "Warm up".to_string(),
+            intention: "Loosen up".to_string(),
+            goal_ids: vec!["goal-1".to_string()],
+            block_study_ids: vec!["scales".to_string(), "scales".to_string()],
+            block_minutes: vec![5, 10],
+            version: 2,
+            updated_at: None,
+        };
+
+        let template = row.into_template();
+        assert_eq!(
+            template.blocks,
+            vec![
+                TemplateBlock {
+                    study_id: "scales".to_string(),
+                    planned_minutes: 5,
+                },
+                TemplateBlock {
+                    study_id: "scales".to_string(),
+                    planned_minutes: 10,
+                },
+            ]
+        );
+        assert_eq!(template.goal_ids, vec!["goal-1"]);
+        assert_eq!(template.version, 2);
+    }
+
+    #[test]
+    fn test_blocks_need_planned_minutes() {
+        let block = |minutes| TemplateBlock {
+            study_id: "scales".to_string(),
+            planned_minutes: minutes,
+        };
+
+        assert!(TemplateRepository::validate_blocks(&[block(5), block(10)]).is_ok());
+        assert!(TemplateRepository::validate_blocks(&[block(5), block(0)]).is_err());
+    }
+}
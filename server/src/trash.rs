@@ -0,0 +1,132 @@
+use axum::{extract::State, response::Json, routing::get, Router};
+use chrono::{DateTime, Duration, Utc};
+use shared::Trash;
+use std::sync::Arc;
+
+use crate::{
+    auth::AuthUser, goals::GoalRepository, repository::RepositoryResult,
+    sessions::SessionRepository, studies::StudyRepository, ApiError,
+};
+
+// How long deleted entities stay in the trash when TRASH_RETENTION_DAYS isn't set
+const DEFAULT_RETENTION_DAYS: i64 = 30;
+
+// How often the purge job looks for entities past their retention period
+const PURGE_INTERVAL_SECONDS: u64 = 60 * 60;
+
+/// Reads the retention period from TRASH_RETENTION_DAYS, refusing to start
+/// with one that isn't a positive number of days
+pub fn retention_from_env() -> Duration {
+    let days = std::env::var("TRASH_RETENTION_DAYS").ok();
+    retention_from(days.as_deref()).unwrap_or_else(|e| panic!("{e}"))
+}
+
+// The retention period for a TRASH_RETENTION_DAYS value, if it's set
+fn retention_from(days: Option<&str>) -> Result<Duration, String> {
+    let Some(days) = days else {
+        return Ok(Duration::days(DEFAULT_RETENTION_DAYS));
+    };
+    match days.trim().parse::<i64>() {
+        Ok(days) if days > 0 => Ok(Duration::days(days)),
+        _ => Err(format!(
+            "TRASH_RETENTION_DAYS must be a positive number of days, got '{days}'"
+        )),
+    }
+}
+
+// Trash across the other repositories - no traits, just methods
+pub struct TrashRepository {
+    goals: Arc<GoalRepository>,
+    studies: Arc<StudyRepository>,
+    sessions: Arc<SessionRepository>,
+}
+
+impl TrashRepository {
+    pub fn new(
+        goals: Arc<GoalRepository>,
+        studies: Arc<StudyRepository>,
+        sessions: Arc<SessionRepository>,
+    ) -> Self {
+        Self {
+            goals,
+            studies,
+            sessions,
+        }
+    }
+
+    pub async fn find_all(&self, user_id: &str) -> RepositoryResult<Trash> {
+        Ok(Trash {
+            goals: self.goals.find_trashed(user_id).await?,
+            studies: self.studies.find_trashed(user_id).await?,
+            sessions: self.sessions.find_trashed(user_id).await?,
+        })
+    }
+
+    /// Permanently deletes everything that went into the trash before
+    /// `before`, returning how many entities were purged
+    pub async fn purge(&self, before: DateTime<Utc>) -> RepositoryResult<u64> {
+        // Sessions first, so studies don't purge study sessions of sessions
+        // that are going anyway
+        Ok(self.sessions.purge_deleted_before(before).await?
+            + self.studies.purge_deleted_before(before).await?
+            + self.goals.purge_deleted_before(before).await?)
+    }
+}
+
+/// Purges the trash every hour, keeping entities for `retention` after they
+/// were deleted
+pub fn spawn_purge_job(trash_repo: Arc<TrashRepository>, retention: Duration) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL_SECONDS));
+        loop {
+            interval.tick().await;
+            match trash_repo.purge(Utc::now() - retention).await {
+                Ok(0) => {}
+                Ok(purged) => println!("Purged {purged} entities from the trash"),
+                Err(e) => eprintln!("Failed to purge the trash: {e}"),
+            }
+        }
+    });
+}
+
+// HTTP Handlers
+async fn get_trash(
+    State(trash_repo): State<Arc<TrashRepository>>,
+    AuthUser(user_id): AuthUser,
+) -> Result<Json<Trash>, ApiError> {
+    let trash = trash_repo.find_all(&user_id).await?;
+
+    Ok(Json(trash))
+}
+
+pub fn routes() -> Router<Arc<TrashRepository>> {
+    Router::new().route("/trash", get(get_trash))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_defaults_to_thirty_days() {
+        assert_eq!(retention_from(None), Ok(Duration::days(30)));
+    }
+
+    #[test]
+    fn test_retention_is_read_in_days() {
+        assert_eq!(retention_from(Some("7")), Ok(Duration::days(7)));
+    }
+
+    #[test]
+    fn test_retention_must_be_a_positive_number_of_days() {
+        for days in ["0", "-3", "a week", "1.5", ""] {
+            let error = retention_from(Some(days)).unwrap_err();
+            assert!(error.contains("TRASH_RETENTION_DAYS must be a positive number"));
+        }
+    }
+}
@@ -26,18 +26,62 @@ pub mod error;
 pub use error::*;
 
 pub mod goal;
-pub use goal::{add_study_to_goal, GoalEvent, GoalStatus, PracticeGoal};
+pub use goal::{
+    add_study_to_goal, goal_progress, tempo_progress, GoalEvent, GoalProgress, GoalStatus,
+    PracticeGoal, TempoProgress,
+};
 
 pub mod study;
 pub use study::{Study, StudyEvent};
 
+pub mod plan;
+pub use plan::{
+    plan_occurrences, OccurrenceStatus, PlanEvent, PlanOccurrence, PracticePlan, Recurrence,
+    Weekday,
+};
+
+pub mod template;
+pub use template::{CurrentBlock, SessionTemplate, TemplateBlock, TemplateEvent};
+
 pub mod study_session;
-pub use study_session::{StudySession, StudySessionEvent};
+pub use study_session::{StudySession, StudySessionEvent, StudySessionState};
 
 pub mod session;
 pub use session::{
-    complete_reflection, edit_session_fields, edit_session_notes, end_session, start_session,
-    PracticeSession, SessionEvent, SessionState,
+    complete_reflection, edit_session_fields, edit_session_notes, end_session, pause_session,
+    resume_session, start_session, PauseInterval, PracticeSession, SessionEvent, SessionState,
+};
+
+pub mod auth;
+pub use auth::{AuthEvent, AuthResponse, LogInRequest, SignUpRequest, User};
+
+pub mod conflict;
+pub use conflict::{ConflictEvent, EntityKind, SyncConflict};
+
+pub mod trash;
+pub use trash::{Archivable, Trash, TrashEvent};
+
+pub mod history;
+pub use history::History;
+
+pub mod export;
+pub use export::{ExportArchive, ExportEvent, ExportedData, EXPORT_VERSION};
+
+pub mod timer;
+pub use timer::{Clock, TimerRequest, TimerResponse};
+
+pub mod outbox;
+pub use outbox::{OperationMethod, OperationStatus, OutboxEvent, PendingOperation};
+
+pub mod sync;
+pub use sync::{Changes, SyncCursor, SyncEvent, Tombstone};
+
+pub mod stats;
+pub use stats::{DailyMinutes, PracticeStats, TimeSpent};
+
+pub mod target;
+pub use target::{
+    target_progress, PracticeTarget, TargetMeasure, TargetPeriod, TargetProgress, TargetStatus,
 };
 
 pub mod model;
@@ -45,23 +89,28 @@ pub use model::*;
 
 pub mod utils;
 pub use utils::{
-    generate_id, handle_http_error, handle_operation_result, is_valid_id, short_id,
-    validate_and_log,
+    app_error_from_http, generate_id, handle_http_error, handle_operation_result, is_valid_id,
+    short_id, validate_and_log, IdGenerator,
 };
 
 pub mod http_utils;
 pub use http_utils::{
-    api_delete, api_get, api_post, api_put, delete_request, get_request, post_json_request,
-    put_json_request,
+    api_delete, api_get, api_post, api_put, api_send, delete_request, get_request,
+    post_json_request, put_json_request, ApiConfig, DEFAULT_API_BASE_URL,
 };
 
 pub mod repository;
-pub use repository::{GoalRepository, Repository, SessionRepository, StudyRepository};
+pub use repository::{
+    goal_impact, study_impact, DeleteImpact, DeleteMode, DetachedReferences, GoalRepository,
+    Repository, SessionRepository, StudyRepository,
+};
 
 #[cfg(test)]
 pub mod test_utils;
 #[cfg(test)]
 pub use test_utils::*;
+#[cfg(test)]
+mod scenario_tests;
 
 // *************
 // EVENTS
@@ -74,9 +123,39 @@ pub enum Event {
     Study(StudyEvent),
     Session(SessionEvent),
     StudySession(StudySessionEvent),
+    Plan(PlanEvent),
+    Template(TemplateEvent),
+    Trash(TrashEvent),
+    Export(ExportEvent),
+    Outbox(OutboxEvent),
+    Conflict(ConflictEvent),
+    Sync(SyncEvent),
+    Auth(AuthEvent),
+    // Point the core at another server, e.g. localhost or staging
+    Configure {
+        base_url: String,
+    },
     FetchAll,
+    // The time now, RFC 3339. Sent by the shell on launch and when the app
+    // comes back to the foreground; the core asks for more while a session runs
+    Tick(String),
+    // The user's IANA time zone, e.g. "Europe/London", which stats count days
+    // in. Sent by the shell on launch and whenever the zone changes
+    TimeZone(String),
+    // A timer the core asked for went off (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    TimerFired(u32, String),
+    // Takes back the most recent goal, study or session change, or applies
+    // the most recently undone one again
+    Undo,
+    Redo,
     Error(String),
     ClearError,
+    // A failed request or action, decoded into a typed error (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    Failed(AppError),
     // Local store reconciliation events
     ReconcileFromLocal {
         goals: Vec<PracticeGoal>,
@@ -90,6 +169,7 @@ pub enum Event {
 pub enum Effect {
     Render(RenderOperation),
     Http(HttpRequest),
+    Timer(TimerRequest),
 }
 
 // *************
@@ -111,29 +191,74 @@ impl App for Chopin {
         model: &mut Self::Model,
         _caps: &Self::Capabilities,
     ) -> Command<Effect, Event> {
-        match event {
+        let command = match event {
             Event::Goal(goal_event) => goal::handle_event(goal_event, model),
             Event::Study(study_event) => study::handle_event(study_event, model),
             Event::Session(session_event) => session::handle_event(session_event, model),
             Event::StudySession(study_session_event) => {
                 study_session::handle_event(study_session_event, model)
             }
+            Event::Plan(plan_event) => plan::handle_event(plan_event, model),
+            Event::Template(template_event) => template::handle_event(template_event, model),
+            Event::Trash(trash_event) => trash::handle_event(trash_event, model),
+            Event::Export(export_event) => export::handle_event(export_event, model),
+            Event::Outbox(outbox_event) => outbox::handle_event(outbox_event, model),
+            Event::Conflict(conflict_event) => conflict::handle_event(conflict_event, model),
+            Event::Sync(sync_event) => sync::handle_event(sync_event, model),
+            Event::Auth(auth_event) => auth::handle_event(auth_event, model),
+            Event::Configure { base_url } => {
+                match ApiConfig::new(&base_url) {
+                    Ok(api) => model.api = api,
+                    Err(e) => model.last_error = Some(e.to_string()),
+                }
+                crux_core::render::render()
+            }
+            Event::Tick(now) => {
+                if let Err(e) = model.clock.set(&now) {
+                    model.last_error = Some(format!("Failed to read time {now}: {e}"));
+                }
+                crux_core::render::render()
+            }
+            Event::TimeZone(name) => {
+                if model.clock.set_zone(&name).is_err() {
+                    model.last_error = Some(format!("Unknown time zone {name}"));
+                }
+                crux_core::render::render()
+            }
+            Event::TimerFired(id, now) => {
+                model.clock.fired(id);
+                if let Err(e) = model.clock.set(&now) {
+                    model.last_error = Some(format!("Failed to read time {now}: {e}"));
+                }
+                crux_core::render::render()
+            }
             Event::FetchAll => {
-                // Orchestrate all sync operations by dispatching individual sync events
-                Command::all(vec![
-                    Command::event(Event::Goal(GoalEvent::SyncGoals)),
-                    Command::event(Event::Study(StudyEvent::SyncStudies)),
-                    Command::event(Event::Session(SessionEvent::SyncSessions)),
-                ])
+                // Pull only what changed since the last sync
+                sync::fetch_changes(model)
             }
+            Event::Undo => match history::undo(model) {
+                Some(event) => history::replay(model, |model| self.update(event, model, &())),
+                None => crux_core::render::render(),
+            },
+            Event::Redo => match history::redo(model) {
+                Some(event) => history::replay(model, |model| self.update(event, model, &())),
+                None => crux_core::render::render(),
+            },
             Event::Error(error_message) => {
                 model.last_error = Some(error_message);
+                model.error = None;
                 Command::done()
             }
             Event::ClearError => {
                 model.last_error = None;
+                model.error = None;
                 Command::done()
             }
+            Event::Failed(error) => {
+                model.last_error = Some(error.to_string());
+                model.error = Some(error);
+                crux_core::render::render()
+            }
             Event::ReconcileFromLocal {
                 goals,
                 studies,
@@ -146,20 +271,17 @@ impl App for Chopin {
                 crux_core::render::render()
             }
             Event::SyncPendingChanges => {
-                // Trigger sync of all pending changes to server
-                // This will be handled by the iOS layer - just acknowledge here
-                crux_core::render::render()
+                // Send the next queued change whose backoff has elapsed
+                outbox::sync(model)
             }
-        }
+        };
+
+        // Keep elapsed times moving while a session runs
+        command.and(timer::keep_ticking(model))
     }
 
     fn view(&self, model: &Self::Model) -> Self::ViewModel {
-        ViewModel::new(
-            model.goals.clone(),
-            model.studies.clone(),
-            model.sessions.clone(),
-            model.last_error.clone(),
-        )
+        ViewModel::new(model)
     }
 }
 
@@ -0,0 +1,282 @@
+use crate::app::history::History;
+use crate::app::model::Model;
+use crate::app::outbox::Outbox;
+use crate::app::sync::SyncCursor;
+use crate::app::trash::Trash;
+use crate::HttpResult;
+use crux_core::Command;
+use crux_http::HttpError;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct User {
+    pub id: String,
+    pub email: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct SignUpRequest {
+    pub email: String,
+    pub password: String,
+    pub display_name: Option<String>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct LogInRequest {
+    pub email: String,
+    pub password: String,
+}
+
+/// Returned by signup and login: a bearer token for the /api routes
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AuthResponse {
+    pub token: String,
+    pub user: User,
+}
+
+/// Who the core is syncing as
+#[derive(Default)]
+pub struct AuthState {
+    pub token: Option<String>,
+    pub user: Option<User>,
+    // The account local data and queued changes belong to. Kept when the
+    // token expires and restored with it, so logging back in as someone else
+    // can be told apart from a re-login. None only for data made before the
+    // first sign in, which the first account adopts.
+    pub owner_id: Option<String>,
+}
+
+impl AuthState {
+    pub fn is_signed_in(&self) -> bool {
+        self.token.is_some()
+    }
+
+    pub fn bearer_token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum AuthEvent {
+    // Background auth events (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    Authenticated(HttpResult<crux_http::Response<AuthResponse>, HttpError>),
+    #[serde(skip)]
+    #[facet(skip)]
+    UserLoaded(HttpResult<crux_http::Response<User>, HttpError>),
+
+    // User actions
+    SignUp(SignUpRequest),
+    LogIn(LogInRequest),
+    LogOut,
+
+    // Shell persistence, the token with the id of the user it was issued to
+    RestoreToken {
+        token: String,
+        user_id: String,
+    },
+}
+
+pub fn is_unauthorized(error: &HttpError) -> bool {
+    matches!(error, HttpError::Http { code, .. } if u16::from(*code) == 401)
+}
+
+/// Forgets a token the server no longer accepts. Local changes stay queued
+/// until the user logs back in.
+pub fn expire(model: &mut Model) {
+    model.auth.token = None;
+}
+
+// Local data belongs to the account it was synced with
+fn clear_local_data(model: &mut Model) {
+    model.goals.clear();
+    model.studies.clear();
+    model.sessions.clear();
+    model.trash = Trash::default();
+    model.history = History::default();
+    model.export = None;
+    model.outbox = Outbox::default();
+    model.conflicts.clear();
+    model.sync_cursor = SyncCursor::default();
+}
+
+fn signed_in(model: &mut Model) -> Command<super::Effect, super::Event> {
+    Command::all(vec![
+        crate::app::outbox::sync(model),
+        Command::event(super::Event::FetchAll),
+    ])
+}
+
+pub fn handle_event(event: AuthEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        AuthEvent::Authenticated(HttpResult::Ok(mut response)) => {
+            let auth = response.take_body().unwrap();
+            if model
+                .auth
+                .owner_id
+                .as_ref()
+                .is_some_and(|owner_id| *owner_id != auth.user.id)
+            {
+                clear_local_data(model);
+            }
+            model.auth.token = Some(auth.token);
+            model.auth.owner_id = Some(auth.user.id.clone());
+            model.auth.user = Some(auth.user);
+            return signed_in(model);
+        }
+        AuthEvent::Authenticated(HttpResult::Err(e)) => {
+            return crate::app::handle_http_error(e, "sign in");
+        }
+        AuthEvent::UserLoaded(HttpResult::Ok(mut response)) => {
+            model.auth.user = response.take_body();
+        }
+        AuthEvent::UserLoaded(HttpResult::Err(e)) => {
+            if is_unauthorized(&e) {
+                expire(model);
+            }
+        }
+        AuthEvent::SignUp(request) => {
+            return crate::app::api_post(model, "/auth/signup", &request, |response| {
+                super::Event::Auth(AuthEvent::Authenticated(response))
+            });
+        }
+        AuthEvent::LogIn(request) => {
+            return crate::app::api_post(model, "/auth/login", &request, |response| {
+                super::Event::Auth(AuthEvent::Authenticated(response))
+            });
+        }
+        AuthEvent::LogOut => {
+            clear_local_data(model);
+            model.auth = AuthState::default();
+        }
+        AuthEvent::RestoreToken { token, user_id } => {
+            model.auth.token = Some(token);
+            model.auth.owner_id = Some(user_id);
+            return Command::all(vec![
+                crate::app::api_get(model, "/api/me", |response| {
+                    super::Event::Auth(AuthEvent::UserLoaded(response))
+                }),
+                signed_in(model),
+            ]);
+        }
+    }
+
+    crux_core::render::render()
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn auth_response(user_id: &str) -> crux_http::Response<AuthResponse> {
+    crux_http::testing::ResponseBuilder::ok()
+        .body(AuthResponse {
+            token: format!("token-for-{user_id}"),
+            user: User {
+                id: user_id.to_string(),
+                email: format!("{user_id}@example.com"),
+                display_name: None,
+            },
+        })
+        .build()
+}
+
+#[test]
+fn test_login_stores_token_and_user() {
+    let mut model = Model::default();
+
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-1"))),
+        &mut model,
+    );
+
+    assert!(model.auth.is_signed_in());
+    assert_eq!(model.auth.bearer_token(), Some("token-for-user-1"));
+    assert_eq!(model.auth.user.as_ref().unwrap().id, "user-1");
+}
+
+#[test]
+fn test_logging_in_as_someone_else_clears_local_data() {
+    let mut model = Model::default();
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-1"))),
+        &mut model,
+    );
+    model
+        .goals
+        .push(crate::app::create_test_goal("Scales", None));
+    expire(&mut model);
+
+    // The same user logging back in keeps their data...
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-1"))),
+        &mut model,
+    );
+    assert_eq!(model.goals.len(), 1);
+
+    // ...someone else doesn't see it
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-2"))),
+        &mut model,
+    );
+    assert!(model.goals.is_empty());
+}
+
+#[test]
+fn test_logout_forgets_account_and_data() {
+    let mut model = Model::default();
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-1"))),
+        &mut model,
+    );
+    model
+        .goals
+        .push(crate::app::create_test_goal("Scales", None));
+
+    let _ = handle_event(AuthEvent::LogOut, &mut model);
+
+    assert!(!model.auth.is_signed_in());
+    assert!(model.auth.user.is_none());
+    assert!(model.goals.is_empty());
+}
+
+#[test]
+fn test_logging_in_as_someone_else_after_a_restored_token_expired_clears_local_data() {
+    let mut model = Model::default();
+    let _ = handle_event(
+        AuthEvent::RestoreToken {
+            token: "token-for-user-1".to_string(),
+            user_id: "user-1".to_string(),
+        },
+        &mut model,
+    );
+    model
+        .goals
+        .push(crate::app::create_test_goal("Scales", None));
+    crate::app::outbox::enqueue_delete(&mut model, "/api/goals/scales".to_string());
+
+    // The token was rejected before the user was loaded
+    let _ = handle_event(
+        AuthEvent::UserLoaded(HttpResult::Err(HttpError::Http {
+            code: 401.try_into().unwrap(),
+            message: String::new(),
+            body: None,
+        })),
+        &mut model,
+    );
+    assert!(!model.auth.is_signed_in());
+    assert!(model.auth.user.is_none());
+
+    let _ = handle_event(
+        AuthEvent::Authenticated(HttpResult::Ok(auth_response("user-2"))),
+        &mut model,
+    );
+    assert!(model.goals.is_empty());
+    assert!(model.outbox.operations.is_empty());
+    assert_eq!(model.auth.owner_id.as_deref(), Some("user-2"));
+}
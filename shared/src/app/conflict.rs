@@ -0,0 +1,526 @@
+use crate::app::model::Model;
+use crate::app::outbox::{OperationMethod, OperationStatus, PendingOperation};
+use crate::app::{PracticeGoal, PracticePlan, PracticeSession, SessionTemplate, Study};
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub enum EntityKind {
+    Goal,
+    Study,
+    Session,
+    Plan,
+    Template,
+}
+
+impl EntityKind {
+    fn from_collection(collection: &str) -> Option<Self> {
+        match collection {
+            "goals" => Some(EntityKind::Goal),
+            "studies" => Some(EntityKind::Study),
+            "sessions" => Some(EntityKind::Session),
+            "plans" => Some(EntityKind::Plan),
+            "templates" => Some(EntityKind::Template),
+            _ => None,
+        }
+    }
+}
+
+/// The versioned entity an outbox operation writes to
+#[derive(Debug, PartialEq)]
+pub struct OperationTarget {
+    pub kind: EntityKind,
+    pub id: String,
+    // True for /api/{collection}/{id} itself rather than a nested resource
+    pub is_root: bool,
+}
+
+impl OperationTarget {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let path = path.split('?').next().unwrap_or(path);
+        let mut segments = path.trim_start_matches('/').split('/');
+        if segments.next() != Some("api") {
+            return None;
+        }
+        let kind = EntityKind::from_collection(segments.next()?)?;
+        let id = segments.next().filter(|id| !id.is_empty())?;
+
+        Some(Self {
+            kind,
+            id: id.to_string(),
+            is_root: segments.next().is_none(),
+        })
+    }
+
+    /// The entity an operation writes to. A create is posted to the
+    /// collection, so its id is read from the body.
+    pub fn of(operation: &PendingOperation) -> Option<Self> {
+        Self::from_path(&operation.path).or_else(|| {
+            if operation.method != OperationMethod::Post {
+                return None;
+            }
+            let collection = operation.path.strip_prefix("/api/")?;
+            let kind = EntityKind::from_collection(collection)?;
+            let body: serde_json::Value = serde_json::from_str(operation.body.as_deref()?).ok()?;
+            Some(Self {
+                kind,
+                id: body.get("id")?.as_str()?.to_string(),
+                is_root: true,
+            })
+        })
+    }
+
+    pub fn matches(&self, kind: EntityKind, id: &str) -> bool {
+        self.kind == kind && self.id == id
+    }
+}
+
+/// A local edit the server could not accept because the entity changed there
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SyncConflict {
+    pub kind: EntityKind,
+    pub entity_id: String,
+    pub local_version: u32,
+    // Unknown until the next fetch when the conflict came back as a 409
+    pub server_version: Option<u32>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum ConflictEvent {
+    // Resolve a conflict by entity id
+    KeepLocal(String),
+    KeepServer(String),
+}
+
+/// Entities whose local copy is merged with the server by version
+pub trait Versioned: Clone {
+    const KIND: EntityKind;
+
+    fn id(&self) -> &str;
+    fn version(&self) -> u32;
+    fn collection(model: &mut Model) -> &mut Vec<Self>;
+}
+
+impl Versioned for PracticeGoal {
+    const KIND: EntityKind = EntityKind::Goal;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+    fn collection(model: &mut Model) -> &mut Vec<Self> {
+        &mut model.goals
+    }
+}
+
+impl Versioned for Study {
+    const KIND: EntityKind = EntityKind::Study;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+    fn collection(model: &mut Model) -> &mut Vec<Self> {
+        &mut model.studies
+    }
+}
+
+impl Versioned for PracticeSession {
+    const KIND: EntityKind = EntityKind::Session;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+    fn collection(model: &mut Model) -> &mut Vec<Self> {
+        &mut model.sessions
+    }
+}
+
+impl Versioned for PracticePlan {
+    const KIND: EntityKind = EntityKind::Plan;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+    fn collection(model: &mut Model) -> &mut Vec<Self> {
+        &mut model.plans
+    }
+}
+
+impl Versioned for SessionTemplate {
+    const KIND: EntityKind = EntityKind::Template;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn version(&self) -> u32 {
+        self.version
+    }
+    fn collection(model: &mut Model) -> &mut Vec<Self> {
+        &mut model.templates
+    }
+}
+
+fn version_mut<'a>(model: &'a mut Model, kind: EntityKind, id: &str) -> Option<&'a mut u32> {
+    match kind {
+        EntityKind::Goal => model
+            .goals
+            .iter_mut()
+            .find(|g| g.id == id)
+            .map(|g| &mut g.version),
+        EntityKind::Study => model
+            .studies
+            .iter_mut()
+            .find(|s| s.id == id)
+            .map(|s| &mut s.version),
+        EntityKind::Session => model
+            .sessions
+            .iter_mut()
+            .find(|s| s.id == id)
+            .map(|s| &mut s.version),
+        EntityKind::Plan => model
+            .plans
+            .iter_mut()
+            .find(|p| p.id == id)
+            .map(|p| &mut p.version),
+        EntityKind::Template => model
+            .templates
+            .iter_mut()
+            .find(|t| t.id == id)
+            .map(|t| &mut t.version),
+    }
+}
+
+fn set_body_version(operation: &mut PendingOperation, version: u32) {
+    let Some(body) = &operation.body else {
+        return;
+    };
+    if let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_str(body) {
+        fields.insert("version".to_string(), version.into());
+        operation.body = Some(serde_json::Value::Object(fields).to_string());
+    }
+}
+
+/// The server bumps an entity's version on every write, so the local copy is
+/// bumped as each write is queued. PUTs carry the version they were based on,
+/// which the server checks to reject stale edits.
+pub fn stamp_version(model: &mut Model, operation: &mut PendingOperation) {
+    if operation.method == OperationMethod::Delete {
+        return;
+    }
+    let Some(target) = OperationTarget::from_path(&operation.path) else {
+        return;
+    };
+    let Some(version) = version_mut(model, target.kind, &target.id) else {
+        return;
+    };
+
+    let base = *version;
+    *version += 1;
+    if operation.method == OperationMethod::Put && target.is_root {
+        set_body_version(operation, base);
+    }
+}
+
+fn record_conflict(model: &mut Model, conflict: SyncConflict) {
+    match model
+        .conflicts
+        .iter_mut()
+        .find(|c| c.kind == conflict.kind && c.entity_id == conflict.entity_id)
+    {
+        Some(existing) => {
+            existing.local_version = conflict.local_version;
+            existing.server_version = conflict.server_version.or(existing.server_version);
+        }
+        None => model.conflicts.push(conflict),
+    }
+}
+
+/// Records a conflict for an operation the server rejected with 409
+pub fn record_rejected(model: &mut Model, operation: &PendingOperation) {
+    let Some(target) = OperationTarget::from_path(&operation.path) else {
+        return;
+    };
+    let local_version = version_mut(model, target.kind, &target.id).map_or(0, |v| *v);
+    record_conflict(
+        model,
+        SyncConflict {
+            kind: target.kind,
+            entity_id: target.id,
+            local_version,
+            server_version: None,
+        },
+    );
+}
+
+/// Merges fetched entities into the model, which may be the whole collection
+/// or only the ones changed since the last sync. Local entities the server
+/// doesn't know about yet are kept, and local copies with queued operations
+/// win; if the server moved past them in the meantime, the divergence is
+/// reported as a conflict.
+pub fn merge_from_server<T: Versioned>(server_entities: Vec<T>, model: &mut Model) {
+    for server_entity in server_entities {
+        let position = T::collection(model)
+            .iter()
+            .position(|local| local.id() == server_entity.id());
+
+        match position {
+            Some(position) if model.outbox.touches(T::KIND, server_entity.id()) => {
+                let local_version = T::collection(model)[position].version();
+                let already_conflicted = model
+                    .conflicts
+                    .iter()
+                    .any(|c| c.kind == T::KIND && c.entity_id == server_entity.id());
+                if already_conflicted || server_entity.version() >= local_version {
+                    record_conflict(
+                        model,
+                        SyncConflict {
+                            kind: T::KIND,
+                            entity_id: server_entity.id().to_string(),
+                            local_version,
+                            server_version: Some(server_entity.version()),
+                        },
+                    );
+                }
+            }
+            Some(position) => T::collection(model)[position] = server_entity,
+            None => T::collection(model).push(server_entity),
+        }
+    }
+}
+
+/// Drops an entity deleted on the server, along with any local edits to it
+/// that are still waiting to be sent
+pub fn remove_deleted<T: Versioned>(id: &str, model: &mut Model) {
+    T::collection(model).retain(|local| local.id() != id);
+    model.outbox.discard(T::KIND, id);
+    model
+        .conflicts
+        .retain(|c| !(c.kind == T::KIND && c.entity_id == id));
+}
+
+// Re-applies the queued operations on top of the server's version, so the
+// local edits overwrite the server's
+fn rebase_operations(model: &mut Model, kind: EntityKind, id: &str, server_version: u32) {
+    let mut version = server_version;
+    for operation in model.outbox.operations.iter_mut() {
+        let Some(target) = OperationTarget::from_path(&operation.path) else {
+            continue;
+        };
+        if !target.matches(kind, id) || operation.method == OperationMethod::Delete {
+            continue;
+        }
+        if operation.method == OperationMethod::Put && target.is_root {
+            set_body_version(operation, version);
+        }
+        version += 1;
+        operation.status = OperationStatus::Pending;
+        operation.attempts = 0;
+        operation.next_attempt_at = None;
+    }
+
+    if let Some(local_version) = version_mut(model, kind, id) {
+        *local_version = version;
+    }
+}
+
+pub fn handle_event(
+    event: ConflictEvent,
+    model: &mut Model,
+) -> Command<super::Effect, super::Event> {
+    let entity_id = match &event {
+        ConflictEvent::KeepLocal(entity_id) | ConflictEvent::KeepServer(entity_id) => {
+            entity_id.clone()
+        }
+    };
+    let Some(position) = model
+        .conflicts
+        .iter()
+        .position(|c| c.entity_id == entity_id)
+    else {
+        return crux_core::render::render();
+    };
+
+    match event {
+        ConflictEvent::KeepLocal(_) => {
+            let Some(server_version) = model.conflicts[position].server_version else {
+                model.last_error =
+                    Some("Failed to resolve conflict: server version not loaded yet".to_string());
+                return crux_core::render::render();
+            };
+            let conflict = model.conflicts.remove(position);
+            rebase_operations(model, conflict.kind, &entity_id, server_version);
+            crate::app::outbox::sync(model)
+        }
+        ConflictEvent::KeepServer(_) => {
+            let conflict = model.conflicts.remove(position);
+            model.outbox.discard(conflict.kind, &entity_id);
+
+            // The local copy is no longer dirty, so the fetch replaces it
+            Command::all(vec![
+                crux_core::render::render(),
+                Command::event(super::Event::FetchAll),
+            ])
+        }
+    }
+}
+
+// *************
+// TESTS
+// *************
+
+#[test]
+fn test_operation_target_from_path() {
+    assert_eq!(
+        OperationTarget::from_path("/api/goals/goal-1"),
+        Some(OperationTarget {
+            kind: EntityKind::Goal,
+            id: "goal-1".to_string(),
+            is_root: true,
+        })
+    );
+    assert_eq!(
+        OperationTarget::from_path("/api/sessions/session-1/studies"),
+        Some(OperationTarget {
+            kind: EntityKind::Session,
+            id: "session-1".to_string(),
+            is_root: false,
+        })
+    );
+    assert_eq!(
+        OperationTarget::from_path("/api/studies/study-1?mode=block_if_referenced"),
+        Some(OperationTarget {
+            kind: EntityKind::Study,
+            id: "study-1".to_string(),
+            is_root: true,
+        })
+    );
+    assert_eq!(OperationTarget::from_path("/api/goals"), None);
+    assert_eq!(OperationTarget::from_path("/health"), None);
+}
+
+#[test]
+fn test_queued_puts_carry_their_base_version() {
+    let mut model = Model::default();
+    let goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal.clone());
+
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &goal);
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &goal);
+
+    let versions: Vec<u64> = model
+        .outbox
+        .operations
+        .iter()
+        .map(|op| {
+            let body: serde_json::Value = serde_json::from_str(op.body.as_ref().unwrap()).unwrap();
+            body["version"].as_u64().unwrap()
+        })
+        .collect();
+    assert_eq!(versions, vec![1, 2]);
+    assert_eq!(model.goals[0].version, 3);
+}
+
+#[test]
+fn test_merge_keeps_dirty_local_edits() {
+    let mut model = Model::default();
+    let mut goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal.clone());
+
+    // An edit is queued locally...
+    model.goals[0].name = "Scales and arpeggios".to_string();
+    let edited = model.goals[0].clone();
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &edited);
+
+    // ...while the server still has the old copy
+    goal.name = "Old name".to_string();
+    merge_from_server(vec![goal.clone()], &mut model);
+    assert_eq!(model.goals[0].name, "Scales and arpeggios");
+    assert!(model.conflicts.is_empty());
+
+    // Once the queue is empty the server copy wins again
+    model.outbox.operations.clear();
+    merge_from_server(vec![goal], &mut model);
+    assert_eq!(model.goals[0].name, "Old name");
+}
+
+#[test]
+fn test_merge_reports_conflict_when_server_moved_on() {
+    let mut model = Model::default();
+    let mut goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal.clone());
+    let local = model.goals[0].clone();
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &local);
+
+    // Someone else saved twice since our edit was based on version 1
+    goal.version = 3;
+    goal.name = "Changed elsewhere".to_string();
+    merge_from_server(vec![goal], &mut model);
+
+    assert_eq!(model.goals[0].name, "Scales");
+    assert_eq!(
+        model.conflicts,
+        vec![SyncConflict {
+            kind: EntityKind::Goal,
+            entity_id: goal_id,
+            local_version: 2,
+            server_version: Some(3),
+        }]
+    );
+}
+
+#[test]
+fn test_keep_local_rebases_queued_operations() {
+    let mut model = Model::default();
+    let goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal.clone());
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &goal);
+    model.outbox.operations[0].status = OperationStatus::Failed;
+    let rejected = model.outbox.operations[0].clone();
+    record_rejected(&mut model, &rejected);
+    model.conflicts[0].server_version = Some(5);
+
+    let _ = handle_event(ConflictEvent::KeepLocal(goal_id), &mut model);
+
+    let operation = &model.outbox.operations[0];
+    let body: serde_json::Value = serde_json::from_str(operation.body.as_ref().unwrap()).unwrap();
+    assert!(operation.is_pending());
+    assert_eq!(body["version"], 5);
+    assert_eq!(model.goals[0].version, 6);
+    assert!(model.conflicts.is_empty());
+}
+
+#[test]
+fn test_keep_server_discards_queued_operations() {
+    let mut model = Model::default();
+    let goal = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal.clone());
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/goals/{goal_id}"), &goal);
+    crate::app::outbox::enqueue_delete(&mut model, "/api/studies/study-1".to_string());
+    let rejected = model.outbox.operations[0].clone();
+    record_rejected(&mut model, &rejected);
+
+    let _ = handle_event(ConflictEvent::KeepServer(goal_id), &mut model);
+
+    assert_eq!(model.outbox.operations.len(), 1);
+    assert_eq!(model.outbox.operations[0].path, "/api/studies/study-1");
+    assert!(model.conflicts.is_empty());
+}
@@ -6,10 +6,14 @@ use serde::{Deserialize, Serialize};
 pub enum SessionError {
     AlreadyStarted,
     AlreadyEnded,
+    AlreadyPaused,
     NotStarted,
     NotActive,
+    NotPaused,
     NotFound,
     InvalidTransition { from: String, to: String },
+    // Only one session per user can be running at a time
+    AnotherSessionStarted,
 }
 
 impl std::fmt::Display for SessionError {
@@ -17,12 +21,17 @@ impl std::fmt::Display for SessionError {
         match self {
             SessionError::AlreadyStarted => write!(f, "Session is already started"),
             SessionError::AlreadyEnded => write!(f, "Session is already ended"),
+            SessionError::AlreadyPaused => write!(f, "Session is already paused"),
             SessionError::NotStarted => write!(f, "Session has not been started"),
             SessionError::NotActive => write!(f, "Session is not active"),
+            SessionError::NotPaused => write!(f, "Session is not paused"),
             SessionError::NotFound => write!(f, "Session not found"),
             SessionError::InvalidTransition { from, to } => {
                 write!(f, "Invalid transition from '{from}' to '{to}'")
             }
+            SessionError::AnotherSessionStarted => {
+                write!(f, "Another session is already started")
+            }
         }
     }
 }
@@ -53,14 +62,126 @@ impl std::fmt::Display for GoalError {
 
 impl std::error::Error for GoalError {}
 
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum StudySessionError {
+    AlreadyActive,
+    AlreadyCompleted,
+    NotActive,
+    NotPaused,
+    NotFound,
+    SessionNotActive,
+}
+
+impl std::fmt::Display for StudySessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StudySessionError::AlreadyActive => write!(f, "Study session is already active"),
+            StudySessionError::AlreadyCompleted => {
+                write!(f, "Study session is already completed")
+            }
+            StudySessionError::NotActive => write!(f, "Study session is not active"),
+            StudySessionError::NotPaused => write!(f, "Study session is not paused"),
+            StudySessionError::NotFound => write!(f, "Study session not found"),
+            StudySessionError::SessionNotActive => {
+                write!(f, "Practice session is not running")
+            }
+        }
+    }
+}
+
+impl std::error::Error for StudySessionError {}
+
+/// Machine-readable reason sent by the server with every error response
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    BadRequest,
+    Unauthorized,
+    NotFound,
+    Conflict,
+    VersionConflict,
+    // A session or goal status change its current state doesn't allow
+    InvalidTransition,
+    ValidationFailed,
+    Internal,
+    // A code added to the server after this client was built
+    #[serde(other)]
+    Unknown,
+}
+
+/// What was wrong with one field of a request
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Body of the server's error responses
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ApiErrorBody {
+    pub code: ApiErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+    // Why a session transition was refused, with the invalid_transition code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_error: Option<SessionError>,
+    // Why a goal status change was refused, with the invalid_transition code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_error: Option<GoalError>,
+}
+
+impl From<ApiErrorBody> for AppError {
+    fn from(body: ApiErrorBody) -> Self {
+        if let Some(error) = body.session_error {
+            return AppError::Session(error);
+        }
+        if let Some(error) = body.goal_error {
+            return AppError::Goal(error);
+        }
+        match body.code {
+            ApiErrorCode::NotFound => AppError::NotFound(body.message),
+            ApiErrorCode::ValidationFailed => AppError::Validation(ValidationError {
+                message: body.message,
+                fields: body.details,
+            }),
+            ApiErrorCode::VersionConflict => AppError::VersionConflict(body.message),
+            ApiErrorCode::Conflict => AppError::Conflict(body.message),
+            ApiErrorCode::Unauthorized => AppError::Unauthorized(body.message),
+            ApiErrorCode::Internal => AppError::Server(body.message),
+            ApiErrorCode::InvalidTransition | ApiErrorCode::BadRequest | ApiErrorCode::Unknown => {
+                AppError::Http(body.message)
+            }
+        }
+    }
+}
+
+/// A request the server refused because of its content, with the offending fields
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    pub message: String,
+    pub fields: Vec<FieldError>,
+}
+
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[repr(C)]
 pub enum AppError {
     Session(SessionError),
     Goal(GoalError),
+    StudySession(StudySessionError),
     Http(String),
+    InvalidUrl(String),
     Serialization(String),
     Unknown(String),
+    // Reported by the server, whose messages are meant for people
+    NotFound(String),
+    Validation(ValidationError),
+    VersionConflict(String),
+    Conflict(String),
+    Unauthorized(String),
+    Server(String),
+    Network(String),
 }
 
 impl std::fmt::Display for AppError {
@@ -68,9 +189,18 @@ impl std::fmt::Display for AppError {
         match self {
             AppError::Session(e) => write!(f, "Session error: {e}"),
             AppError::Goal(e) => write!(f, "Goal error: {e}"),
+            AppError::StudySession(e) => write!(f, "Study session error: {e}"),
             AppError::Http(msg) => write!(f, "HTTP error: {msg}"),
+            AppError::InvalidUrl(msg) => write!(f, "Invalid URL: {msg}"),
             AppError::Serialization(msg) => write!(f, "Serialization error: {msg}"),
             AppError::Unknown(msg) => write!(f, "Unknown error: {msg}"),
+            AppError::Validation(e) => write!(f, "{}", e.message),
+            AppError::NotFound(msg)
+            | AppError::VersionConflict(msg)
+            | AppError::Conflict(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Server(msg)
+            | AppError::Network(msg) => write!(f, "{msg}"),
         }
     }
 }
@@ -89,8 +219,61 @@ impl From<GoalError> for AppError {
     }
 }
 
+impl From<StudySessionError> for AppError {
+    fn from(error: StudySessionError) -> Self {
+        AppError::StudySession(error)
+    }
+}
+
 impl From<serde_json::Error> for AppError {
     fn from(error: serde_json::Error) -> Self {
         AppError::Serialization(error.to_string())
     }
 }
+
+/// Why an exported archive can't be imported
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum ImportError {
+    // Written by a newer version of the app
+    UnsupportedVersion(u32),
+    DuplicateId(String),
+    // Something refers to an entity the archive doesn't hold
+    MissingReference {
+        from: String, // e.g. "goal 1f0e…"
+        to: String,   // e.g. "study 9c2a…"
+    },
+    // Started or paused, which only the device running it can carry on
+    SessionInProgress(String),
+}
+
+impl ImportError {
+    pub fn missing(from_kind: &str, from_id: &str, to_kind: &str, to_id: &str) -> Self {
+        ImportError::MissingReference {
+            from: format!("{from_kind} {from_id}"),
+            to: format!("{to_kind} {to_id}"),
+        }
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::UnsupportedVersion(version) => {
+                write!(f, "Archive version {version} is not supported")
+            }
+            ImportError::DuplicateId(id) => write!(f, "Archive uses id {id} more than once"),
+            ImportError::MissingReference { from, to } => {
+                write!(f, "{from} refers to {to}, which isn't in the archive")
+            }
+            ImportError::SessionInProgress(id) => {
+                write!(
+                    f,
+                    "Session {id} is still in progress, end it before exporting"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
@@ -0,0 +1,365 @@
+use crate::app::error::ImportError;
+use crate::app::model::Model;
+use crate::app::{PracticeGoal, PracticeSession, Study};
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Bumped whenever the archive's layout changes in a way older importers
+/// can't read
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Everything a user has practiced, as exported by the server or by the core
+/// from its own model. Sessions carry their study sessions; entities in the
+/// trash are left out.
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct ExportArchive {
+    pub version: u32,
+    pub exported_at: String, // RFC 3339
+    pub goals: Vec<PracticeGoal>,
+    pub studies: Vec<Study>,
+    pub sessions: Vec<PracticeSession>,
+}
+
+/// An export made by the core, ready for the shell to save or share
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct ExportedData {
+    pub file_name: String, // without an extension, e.g. "intrada-2025-05-01"
+    pub json: String,
+    pub sessions_csv: String,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum ExportEvent {
+    // Builds an export from what the core holds, which works offline
+    ExportData,
+    // The shell is done with the export
+    ClearExport,
+}
+
+impl ExportArchive {
+    pub fn from_model(model: &Model) -> Self {
+        Self {
+            version: EXPORT_VERSION,
+            exported_at: model.clock.now.to_rfc3339(),
+            goals: model.goals.clone(),
+            studies: model.studies.clone(),
+            sessions: model.sessions.clone(),
+        }
+    }
+
+    /// Checks the archive can be imported as it is: a version this build
+    /// reads, no id used twice, no session still running, and every
+    /// reference pointing inside it
+    pub fn validate(&self) -> Result<(), ImportError> {
+        if self.version == 0 || self.version > EXPORT_VERSION {
+            return Err(ImportError::UnsupportedVersion(self.version));
+        }
+
+        let mut ids = HashSet::new();
+        let all_ids = self
+            .goals
+            .iter()
+            .map(|g| &g.id)
+            .chain(self.studies.iter().map(|s| &s.id))
+            .chain(self.sessions.iter().map(|s| &s.id))
+            .chain(self.study_sessions().map(|s| &s.id));
+        for id in all_ids {
+            if !ids.insert(id.as_str()) {
+                return Err(ImportError::DuplicateId(id.clone()));
+            }
+        }
+
+        let has_study = |id: &str| self.studies.iter().any(|s| s.id == id);
+        let has_goal = |id: &str| self.goals.iter().any(|g| g.id == id);
+        for goal in &self.goals {
+            if let Some(study_id) = goal.study_ids.iter().find(|id| !has_study(id)) {
+                return Err(ImportError::missing("goal", &goal.id, "study", study_id));
+            }
+        }
+        for session in &self.sessions {
+            // Running one would skip the checks that keep one running at a time
+            if session.is_in_progress() {
+                return Err(ImportError::SessionInProgress(session.id.clone()));
+            }
+            if let Some(goal_id) = session.goal_ids.iter().find(|id| !has_goal(id)) {
+                return Err(ImportError::missing(
+                    "session",
+                    &session.id,
+                    "goal",
+                    goal_id,
+                ));
+            }
+            for study_session in &session.study_sessions {
+                if !has_study(&study_session.study_id) {
+                    return Err(ImportError::missing(
+                        "study session",
+                        &study_session.id,
+                        "study",
+                        &study_session.study_id,
+                    ));
+                }
+                if study_session.session_id != session.id {
+                    return Err(ImportError::missing(
+                        "study session",
+                        &study_session.id,
+                        "session",
+                        &study_session.session_id,
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn study_sessions(&self) -> impl Iterator<Item = &crate::app::StudySession> {
+        self.sessions.iter().flat_map(|s| &s.study_sessions)
+    }
+
+    /// Gives each of `ids` a new id from `next_id`, updating everything that
+    /// refers to it. Returns the new id of each one.
+    pub fn remap(
+        &mut self,
+        ids: &HashSet<String>,
+        mut next_id: impl FnMut() -> String,
+    ) -> HashMap<String, String> {
+        let remapped: HashMap<String, String> =
+            ids.iter().map(|id| (id.clone(), next_id())).collect();
+        let remap = |id: &mut String| {
+            if let Some(new_id) = remapped.get(id.as_str()) {
+                *id = new_id.clone();
+            }
+        };
+
+        for goal in &mut self.goals {
+            remap(&mut goal.id);
+            goal.study_ids.iter_mut().for_each(&remap);
+        }
+        for study in &mut self.studies {
+            remap(&mut study.id);
+        }
+        for session in &mut self.sessions {
+            remap(&mut session.id);
+            session.goal_ids.iter_mut().for_each(&remap);
+            session.active_study_session_id.iter_mut().for_each(&remap);
+            for study_session in &mut session.study_sessions {
+                remap(&mut study_session.id);
+                remap(&mut study_session.study_id);
+                remap(&mut study_session.session_id);
+            }
+        }
+
+        remapped
+    }
+
+    /// One row per session, with goals and studies by name, for spreadsheets
+    pub fn sessions_csv(&self) -> String {
+        let goal_names: HashMap<&str, &str> = self
+            .goals
+            .iter()
+            .map(|g| (g.id.as_str(), g.name.as_str()))
+            .collect();
+        let study_names: HashMap<&str, &str> = self
+            .studies
+            .iter()
+            .map(|s| (s.id.as_str(), s.name.as_str()))
+            .collect();
+        let names = |ids: Vec<&str>, names: &HashMap<&str, &str>| {
+            ids.iter()
+                .filter_map(|id| names.get(id).copied())
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+
+        let mut csv = String::from(
+            "id,state,start_time,end_time,duration_in_seconds,intention,goals,studies,notes\n",
+        );
+        for session in &self.sessions {
+            let goal_ids = session.goal_ids.iter().map(String::as_str).collect();
+            let study_ids = session
+                .study_sessions
+                .iter()
+                .map(|s| s.study_id.as_str())
+                .collect();
+            let row = [
+                session.id.clone(),
+                session.state.name().to_string(),
+                session.start_time().unwrap_or_default().to_string(),
+                session.end_time().unwrap_or_default().to_string(),
+                session
+                    .duration_in_seconds()
+                    .map(|s| s.to_string())
+                    .unwrap_or_default(),
+                session.intention.clone(),
+                names(goal_ids, &goal_names),
+                names(study_ids, &study_names),
+                session.notes.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+// Quotes a field holding a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn handle_event(event: ExportEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        ExportEvent::ExportData => {
+            let archive = ExportArchive::from_model(model);
+            match serde_json::to_string_pretty(&archive) {
+                Ok(json) => {
+                    model.export = Some(ExportedData {
+                        file_name: format!("intrada-{}", model.clock.now.format("%Y-%m-%d")),
+                        json,
+                        sessions_csv: archive.sessions_csv(),
+                    });
+                }
+                Err(e) => model.last_error = Some(format!("Failed to export: {e}")),
+            }
+        }
+        ExportEvent::ClearExport => model.export = None,
+    }
+
+    crux_core::render::render()
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn test_archive() -> ExportArchive {
+    let study = crate::app::create_test_study("Scales, major", None);
+    let goal = crate::app::create_custom_test_goal(
+        "Grade 5",
+        None,
+        crate::app::GoalStatus::InProgress,
+        vec![study.id.clone()],
+        None,
+    );
+    let mut session = crate::app::create_ended_test_session(&[goal.id.as_str()], "Warm up");
+    session.complete_reflection().unwrap();
+    session.notes = Some("Felt \"slow\"".to_string());
+    session
+        .study_sessions
+        .push(crate::app::create_test_study_session(
+            &study.id,
+            &session.id,
+        ));
+
+    ExportArchive {
+        version: EXPORT_VERSION,
+        exported_at: "2025-05-01T18:00:00+00:00".to_string(),
+        goals: vec![goal],
+        studies: vec![study],
+        sessions: vec![session],
+    }
+}
+
+#[test]
+fn test_export_from_model_round_trips() {
+    let mut model = crate::app::create_deterministic_test_model("2025-05-01T18:00:00Z");
+    let archive = test_archive();
+    model.goals = archive.goals.clone();
+    model.studies = archive.studies.clone();
+    model.sessions = archive.sessions.clone();
+
+    let _ = handle_event(ExportEvent::ExportData, &mut model);
+    let export = model.export.clone().unwrap();
+    assert_eq!(export.file_name, "intrada-2025-05-01");
+    let parsed: ExportArchive = serde_json::from_str(&export.json).unwrap();
+    assert_eq!(parsed, archive);
+    assert_eq!(parsed.validate(), Ok(()));
+
+    let _ = handle_event(ExportEvent::ClearExport, &mut model);
+    assert_eq!(model.export, None);
+}
+
+#[test]
+fn test_validate_rejects_archives_that_cannot_be_imported() {
+    let archive = ExportArchive {
+        version: EXPORT_VERSION + 1,
+        ..test_archive()
+    };
+    assert_eq!(
+        archive.validate(),
+        Err(ImportError::UnsupportedVersion(EXPORT_VERSION + 1))
+    );
+
+    let mut archive = test_archive();
+    archive.studies.clear();
+    assert_eq!(
+        archive.validate().unwrap_err().to_string(),
+        format!(
+            "goal {} refers to study {}, which isn't in the archive",
+            archive.goals[0].id, archive.goals[0].study_ids[0]
+        )
+    );
+
+    let mut archive = test_archive();
+    let goal = archive.goals[0].clone();
+    archive.goals.push(goal.clone());
+    assert_eq!(archive.validate(), Err(ImportError::DuplicateId(goal.id)));
+
+    let mut archive = test_archive();
+    archive.sessions[0].state = crate::app::SessionState::Started {
+        start_time: "2025-05-01T18:00:00Z".to_string(),
+    };
+    assert_eq!(
+        archive.validate(),
+        Err(ImportError::SessionInProgress(
+            archive.sessions[0].id.clone()
+        ))
+    );
+}
+
+#[test]
+fn test_remap_updates_every_reference() {
+    let mut archive = test_archive();
+    let study_id = archive.studies[0].id.clone();
+    let session_id = archive.sessions[0].id.clone();
+    let ids = HashSet::from([study_id.clone(), session_id.clone()]);
+
+    let mut next = 0;
+    let remapped = archive.remap(&ids, || {
+        next += 1;
+        format!("new-{next}")
+    });
+    let new_study_id = &remapped[&study_id];
+    let new_session_id = &remapped[&session_id];
+    assert_eq!(&archive.studies[0].id, new_study_id);
+    assert_eq!(&archive.goals[0].study_ids[0], new_study_id);
+    assert_eq!(&archive.sessions[0].id, new_session_id);
+    let study_session = &archive.sessions[0].study_sessions[0];
+    assert_eq!(&study_session.study_id, new_study_id);
+    assert_eq!(&study_session.session_id, new_session_id);
+    assert_eq!(archive.validate(), Ok(()));
+}
+
+#[test]
+fn test_sessions_csv_names_goals_and_studies() {
+    let archive = test_archive();
+    let csv = archive.sessions_csv();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,state,start_time,end_time,duration_in_seconds,intention,goals,studies,notes")
+    );
+    let row = lines.next().unwrap();
+    assert!(row.starts_with(&format!("{},Ended,", archive.sessions[0].id)));
+    assert!(row.ends_with(",Warm up,Grade 5,\"Scales, major\",\"Felt \"\"slow\"\"\""));
+    assert_eq!(lines.next(), None);
+}
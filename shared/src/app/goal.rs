@@ -1,6 +1,15 @@
+use crate::app::error::GoalError;
 use crate::app::model::Model;
-use crate::app::repository::Repository;
+use crate::app::outbox;
+use crate::app::repository::{DeleteMode, DetachedReferences, Repository};
+use crate::app::session::PracticeSession;
+use crate::app::stats::PracticeStats;
+use crate::app::study_session::StudySession;
+use crate::app::target::{PracticeTarget, TargetMeasure, TargetPeriod};
+use crate::app::trash;
+use crate::app::{history, Event};
 use crate::HttpResult;
+use chrono::DateTime;
 use crux_core::Command;
 use facet::Facet;
 use serde::{Deserialize, Serialize};
@@ -14,6 +23,29 @@ pub enum GoalStatus {
     Completed,
 }
 
+impl GoalStatus {
+    fn name(&self) -> &'static str {
+        match self {
+            GoalStatus::NotStarted => "NotStarted",
+            GoalStatus::InProgress => "InProgress",
+            GoalStatus::Completed => "Completed",
+        }
+    }
+
+    /// Whether a goal may go from this status to `to`: it is started once,
+    /// completed while in progress and reopened after completion. Keeping the
+    /// same status is always allowed.
+    pub fn can_transition_to(&self, to: &GoalStatus) -> bool {
+        self == to
+            || matches!(
+                (self, to),
+                (GoalStatus::NotStarted, GoalStatus::InProgress)
+                    | (GoalStatus::InProgress, GoalStatus::Completed)
+                    | (GoalStatus::Completed, GoalStatus::InProgress)
+            )
+    }
+}
+
 #[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 pub struct PracticeGoal {
     pub id: String,
@@ -24,6 +56,22 @@ pub struct PracticeGoal {
     pub target_date: Option<String>,
     pub study_ids: Vec<String>,
     pub tempo_target: Option<u32>,
+    // e.g. 20 minutes a day or 3 sessions a week
+    #[serde(default)]
+    pub practice_target: Option<PracticeTarget>,
+    // Clean plays at tempo_target after which the goal completes itself
+    #[serde(default)]
+    pub clean_reps_to_complete: Option<u32>,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    // Hidden from lists but kept until restored
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    // In the trash, from where the server purges it after a retention period
+    #[serde(default)]
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -36,18 +84,49 @@ pub enum GoalEvent {
     #[serde(skip)]
     #[facet(skip)]
     GoalsSynced(HttpResult<crux_http::Response<Vec<PracticeGoal>>, crux_http::HttpError>),
-    #[serde(skip)]
-    #[facet(skip)]
-    GoalSynced(HttpResult<crux_http::Response<PracticeGoal>, crux_http::HttpError>),
 
     // Optimistic user actions (all immediate, sync in background)
     CreateGoal(PracticeGoal),
     UpdateGoal(PracticeGoal),
-    RemoveGoal(String),
+    // Detaches the goal from whatever lists it, or refuses while anything does
+    RemoveGoal(String, DeleteMode),
+    // Hides the goal from lists without removing it
+    ArchiveGoal(String),
+    // Brings the goal back from the archive or the trash
+    RestoreGoal(String),
+    // Undoes a Detach removal, putting the goal back where it was listed (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    ReattachGoal(String, DetachedReferences),
     AddStudyToGoal {
         goal_id: String,
         study_id: String,
     },
+    // Lifecycle, validated against the goal's current status
+    StartGoal(String, String),
+    CompleteGoal(String),
+    ReopenGoal(String),
+}
+
+/// Practice towards a goal's target, from finished sessions
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct GoalProgress {
+    pub goal_id: String,
+    pub practiced_minutes: u32,
+    // The amount of a total minutes practice target
+    pub target_minutes: Option<u32>,
+    // Share of the target practiced, capped at 100. None without a target.
+    pub percent: Option<u32>,
+}
+
+/// Best tempo logged for a goal's studies against its tempo target
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct TempoProgress {
+    pub goal_id: String,
+    pub best_tempo: Option<u32>,
+    pub tempo_target: Option<u32>,
+    // Clean plays at or above the target
+    pub clean_reps_at_target: u32,
 }
 
 impl PracticeGoal {
@@ -67,8 +146,222 @@ impl PracticeGoal {
             target_date,
             study_ids,
             tempo_target,
+            practice_target: None,
+            clean_reps_to_complete: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
+        }
+    }
+
+    /// Minutes the goal should take in all, from a total minutes target
+    pub fn total_minutes_target(&self) -> Option<u32> {
+        self.practice_target
+            .as_ref()
+            .filter(|t| t.measure == TargetMeasure::Minutes && t.period == TargetPeriod::Total)
+            .map(|t| t.amount)
+    }
+
+    /// Moves a goal that hasn't been worked on to InProgress, stamping the
+    /// day of `timestamp` as its start date
+    pub fn start(&mut self, timestamp: &str) -> Result<(), GoalError> {
+        match self.status {
+            GoalStatus::NotStarted => {
+                self.status = GoalStatus::InProgress;
+                self.start_date = Some(local_date(timestamp));
+                Ok(())
+            }
+            GoalStatus::InProgress => Err(self.invalid_transition(GoalStatus::InProgress)),
+            GoalStatus::Completed => Err(GoalError::AlreadyCompleted),
+        }
+    }
+
+    pub fn complete(&mut self) -> Result<(), GoalError> {
+        match self.status {
+            GoalStatus::InProgress => {
+                self.status = GoalStatus::Completed;
+                Ok(())
+            }
+            GoalStatus::NotStarted => Err(GoalError::NotStarted),
+            GoalStatus::Completed => Err(GoalError::AlreadyCompleted),
         }
     }
+
+    /// Puts a completed goal back in progress, keeping its start date
+    pub fn reopen(&mut self) -> Result<(), GoalError> {
+        match self.status {
+            GoalStatus::Completed => {
+                self.status = GoalStatus::InProgress;
+                Ok(())
+            }
+            GoalStatus::NotStarted | GoalStatus::InProgress => {
+                Err(self.invalid_transition(GoalStatus::InProgress))
+            }
+        }
+    }
+
+    fn invalid_transition(&self, to: GoalStatus) -> GoalError {
+        GoalError::InvalidTransition {
+            from: self.status.name().to_string(),
+            to: to.name().to_string(),
+        }
+    }
+}
+
+// The calendar day of a timestamp where it was taken, e.g. "2025-05-01".
+// Plain dates are kept as they are.
+fn local_date(timestamp: &str) -> String {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => time.date_naive().to_string(),
+        Err(_) => timestamp.chars().take(10).collect(),
+    }
+}
+
+/// Starts the not yet started goals a session is for, as the session starts.
+/// Returns the goals that changed so they can be sent to the server.
+pub fn start_goals_for_session(
+    session_id: &str,
+    timestamp: &str,
+    model: &mut Model,
+) -> Vec<PracticeGoal> {
+    let Some(session) = model.sessions.iter().find(|s| s.id == session_id) else {
+        return Vec::new();
+    };
+    let goal_ids = session.goal_ids.clone();
+
+    model
+        .goals
+        .iter_mut()
+        .filter(|g| goal_ids.contains(&g.id) && g.status == GoalStatus::NotStarted)
+        .filter_map(|goal| goal.start(timestamp).ok().map(|()| goal.clone()))
+        .collect()
+}
+
+/// Progress of every goal, most recently listed first like `model.goals`
+pub fn goal_progress(goals: &[PracticeGoal], stats: &PracticeStats) -> Vec<GoalProgress> {
+    goals
+        .iter()
+        .map(|goal| {
+            let practiced_minutes = stats
+                .time_per_goal
+                .iter()
+                .find(|t| t.id == goal.id)
+                .map_or(0, |t| t.minutes);
+            let target_minutes = goal.total_minutes_target();
+            let percent = target_minutes.filter(|target| *target > 0).map(|target| {
+                let percent = u64::from(practiced_minutes) * 100 / u64::from(target);
+                percent.min(100) as u32
+            });
+            GoalProgress {
+                goal_id: goal.id.clone(),
+                practiced_minutes,
+                target_minutes,
+                percent,
+            }
+        })
+        .collect()
+}
+
+// Study sessions with a tempo logged for one of the goal's studies
+fn tempo_log<'a>(
+    goal: &'a PracticeGoal,
+    sessions: &'a [PracticeSession],
+) -> impl Iterator<Item = &'a StudySession> + 'a {
+    sessions
+        .iter()
+        .flat_map(|session| session.study_sessions.iter())
+        .filter(|s| s.tempo.is_some() && goal.study_ids.contains(&s.study_id))
+}
+
+fn clean_reps_at_target(goal: &PracticeGoal, sessions: &[PracticeSession]) -> u32 {
+    goal.tempo_target.map_or(0, |target| {
+        tempo_log(goal, sessions)
+            .filter(|s| s.is_clean_at(target))
+            .count() as u32
+    })
+}
+
+/// Tempo progress of every goal, in the same order as `goals`
+pub fn tempo_progress(goals: &[PracticeGoal], sessions: &[PracticeSession]) -> Vec<TempoProgress> {
+    goals
+        .iter()
+        .map(|goal| TempoProgress {
+            goal_id: goal.id.clone(),
+            best_tempo: tempo_log(goal, sessions).filter_map(|s| s.tempo).max(),
+            tempo_target: goal.tempo_target,
+            clean_reps_at_target: clean_reps_at_target(goal, sessions),
+        })
+        .collect()
+}
+
+/// Completes the in progress goals for `study_id` that have now been played
+/// cleanly at their tempo target as often as they ask for.
+/// Returns the goals that changed so they can be sent to the server.
+pub fn complete_goals_at_tempo(study_id: &str, model: &mut Model) -> Vec<PracticeGoal> {
+    let ready: Vec<String> = model
+        .goals
+        .iter()
+        .filter(|g| {
+            g.status == GoalStatus::InProgress && g.study_ids.iter().any(|id| id == study_id)
+        })
+        .filter(|g| {
+            g.clean_reps_to_complete
+                .is_some_and(|reps| clean_reps_at_target(g, &model.sessions) >= reps.max(1))
+        })
+        .map(|g| g.id.clone())
+        .collect();
+
+    model
+        .goals
+        .iter_mut()
+        .filter(|g| ready.contains(&g.id))
+        .filter_map(|goal| goal.complete().ok().map(|()| goal.clone()))
+        .collect()
+}
+
+// Applies a lifecycle change locally and queues it for the server
+fn transition_goal(
+    goal_id: &str,
+    action: &str,
+    model: &mut Model,
+    change: impl FnOnce(&mut PracticeGoal) -> Result<(), GoalError>,
+) -> Command<super::Effect, super::Event> {
+    let result = match model.goals().find_mut_by_id(goal_id) {
+        Some(goal) => change(goal).map(|()| goal.clone()),
+        None => Err(GoalError::NotFound),
+    };
+
+    match result {
+        Ok(goal) => {
+            outbox::enqueue_put(model, format!("/api/goals/{goal_id}"), &goal);
+            outbox::sync(model)
+        }
+        Err(e) => {
+            model.last_error = Some(format!("Failed to {action} goal: {e}"));
+            crux_core::render::render()
+        }
+    }
+}
+
+fn status_of(goal_id: &str, model: &Model) -> Option<GoalStatus> {
+    model
+        .goals
+        .iter()
+        .find(|g| g.id == goal_id)
+        .map(|g| g.status.clone())
+}
+
+// Records a change to a goal as going back to how it is now
+fn record_edit(goal_id: &str, label: &str, redo: GoalEvent, model: &mut Model) {
+    if let Some(previous) = model.goals.iter().find(|g| g.id == goal_id).cloned() {
+        history::record(
+            model,
+            label,
+            Event::Goal(GoalEvent::UpdateGoal(previous)),
+            Event::Goal(redo),
+        );
+    }
 }
 
 // Note: add_goal and edit_goal removed - use model.goals().add() and model.goals().update() directly
@@ -86,7 +379,7 @@ pub fn handle_event(event: GoalEvent, model: &mut Model) -> Command<super::Effec
     match event {
         // Background sync events (internal only)
         GoalEvent::SyncGoals => {
-            return crate::app::api_get("/api/goals", |response| {
+            return crate::app::api_get(model, "/api/goals", |response| {
                 super::Event::Goal(GoalEvent::GoalsSynced(response))
             });
         }
@@ -98,83 +391,137 @@ pub fn handle_event(event: GoalEvent, model: &mut Model) -> Command<super::Effec
         GoalEvent::GoalsSynced(HttpResult::Err(_e)) => {
             // Silently fail background sync - user doesn't need to know
         }
-        GoalEvent::GoalSynced(HttpResult::Ok(_response)) => {
-            // Individual goal synced successfully - nothing to do
-        }
-        GoalEvent::GoalSynced(HttpResult::Err(_e)) => {
-            // Individual goal sync failed - could retry or show status
-        }
 
         // Optimistic user actions (all immediate, sync in background)
         GoalEvent::CreateGoal(goal) => {
             // Apply immediately to local model
             model.goals().add(goal.clone());
+            history::record(
+                model,
+                "Add goal",
+                Event::Goal(GoalEvent::RemoveGoal(goal.id.clone(), DeleteMode::Detach)),
+                Event::Goal(GoalEvent::RestoreGoal(goal.id.clone())),
+            );
 
-            // Trigger background sync
+            // Queue background sync
             let create_request = serde_json::json!({
+                "id": goal.id,
                 "name": goal.name,
                 "description": goal.description,
                 "target_date": goal.target_date,
                 "study_ids": goal.study_ids,
-                "tempo_target": goal.tempo_target
-            });
-            return crate::app::api_post("/api/goals", &create_request, |response| {
-                super::Event::Goal(GoalEvent::GoalSynced(response))
+                "tempo_target": goal.tempo_target,
+                "practice_target": goal.practice_target,
+                "clean_reps_to_complete": goal.clean_reps_to_complete
             });
+            outbox::enqueue_post(model, "/api/goals".to_string(), &create_request);
+            return outbox::sync(model);
         }
         GoalEvent::UpdateGoal(goal) => {
             // Apply immediately to local model
+            record_edit(
+                &goal.id,
+                "Edit goal",
+                GoalEvent::UpdateGoal(goal.clone()),
+                model,
+            );
             model.goals().update(goal.clone());
 
-            // Trigger background sync
-            return crate::app::api_put(&format!("/api/goals/{}", goal.id), &goal, |response| {
-                super::Event::Goal(GoalEvent::GoalSynced(response))
-            });
+            // Queue background sync
+            outbox::enqueue_put(model, format!("/api/goals/{}", goal.id), &goal);
+            return outbox::sync(model);
         }
-        GoalEvent::RemoveGoal(goal_id) => {
-            // Apply immediately to local model
-            model.goals.retain(|g| g.id != goal_id);
+        GoalEvent::RemoveGoal(goal_id, mode) => {
+            // Apply immediately to local model, along with the references to it
+            match model.goals().remove_with_references(&goal_id, mode) {
+                Ok(Some((goal, references))) => {
+                    trash::move_to_trash(goal, model);
+                    history::record(
+                        model,
+                        "Remove goal",
+                        Event::Goal(GoalEvent::ReattachGoal(goal_id.clone(), references)),
+                        Event::Goal(GoalEvent::RemoveGoal(goal_id.clone(), mode)),
+                    );
+                }
+                Ok(None) => {}
+                Err(impact) => {
+                    let summary = impact.summary.unwrap_or_default();
+                    model.last_error = Some(format!("Failed to remove goal: {summary}"));
+                    return crux_core::render::render();
+                }
+            }
 
-            // Trigger background sync
-            return crate::app::api_delete(&format!("/api/goals/{goal_id}"), |response| {
-                super::Event::Goal(GoalEvent::GoalSynced(response))
-            });
+            // Queue background sync, the server cleans up its own references
+            outbox::enqueue_delete(model, mode.delete_path(format!("/api/goals/{goal_id}")));
+            return outbox::sync(model);
+        }
+        GoalEvent::ArchiveGoal(goal_id) => {
+            return trash::archive::<PracticeGoal>(&goal_id, model);
+        }
+        GoalEvent::RestoreGoal(goal_id) => {
+            return trash::restore::<PracticeGoal>(&goal_id, model);
+        }
+        GoalEvent::ReattachGoal(goal_id, references) => {
+            // The server's restore brings back its own references
+            let command = trash::restore::<PracticeGoal>(&goal_id, model);
+            if model.goals.iter().any(|g| g.id == goal_id) {
+                model.goals().reattach(&goal_id, &references);
+            }
+            return command;
         }
         GoalEvent::AddStudyToGoal { goal_id, study_id } => {
             // Apply immediately to local model
+            let redo = GoalEvent::AddStudyToGoal {
+                goal_id: goal_id.clone(),
+                study_id: study_id.clone(),
+            };
+            record_edit(&goal_id, "Add study to goal", redo, model);
             add_study_to_goal(&goal_id, &study_id, model);
 
-            // Trigger background sync
-            if let Some(goal) = model.goals.iter().find(|g| g.id == goal_id) {
-                return crate::app::api_put(&format!("/api/goals/{}", goal.id), goal, |response| {
-                    super::Event::Goal(GoalEvent::GoalSynced(response))
-                });
+            // Queue background sync
+            if let Some(goal) = model.goals.iter().find(|g| g.id == goal_id).cloned() {
+                outbox::enqueue_put(model, format!("/api/goals/{}", goal.id), &goal);
+                return outbox::sync(model);
             }
         }
+        GoalEvent::StartGoal(goal_id, timestamp) => {
+            return transition_goal(&goal_id, "start", model, |goal| goal.start(&timestamp));
+        }
+        // Starting can't be undone, the server never moves a goal back to NotStarted
+        GoalEvent::CompleteGoal(goal_id) => {
+            let before = status_of(&goal_id, model);
+            let command = transition_goal(&goal_id, "complete", model, PracticeGoal::complete);
+            if status_of(&goal_id, model) != before {
+                history::record(
+                    model,
+                    "Complete goal",
+                    Event::Goal(GoalEvent::ReopenGoal(goal_id.clone())),
+                    Event::Goal(GoalEvent::CompleteGoal(goal_id)),
+                );
+            }
+            return command;
+        }
+        GoalEvent::ReopenGoal(goal_id) => {
+            let before = status_of(&goal_id, model);
+            let command = transition_goal(&goal_id, "reopen", model, PracticeGoal::reopen);
+            if status_of(&goal_id, model) != before {
+                history::record(
+                    model,
+                    "Reopen goal",
+                    Event::Goal(GoalEvent::CompleteGoal(goal_id.clone())),
+                    Event::Goal(GoalEvent::ReopenGoal(goal_id)),
+                );
+            }
+            return command;
+        }
     }
 
     crux_core::render::render()
 }
 
-// Helper function to merge server goals with local goals
+// Helper function to merge server goals with local ones, keeping unsynced local edits
 fn merge_goals_from_server(server_goals: Vec<PracticeGoal>, model: &mut Model) {
-    // Simple merge strategy: server goals override local ones with same ID
-    let server_goal_ids: std::collections::HashSet<String> =
-        server_goals.iter().map(|g| g.id.clone()).collect();
-
-    // Keep local goals that don't exist on server (likely new/pending sync)
-    model
-        .goals
-        .retain(|local_goal| !server_goal_ids.contains(&local_goal.id));
-
-    // Add/update with server goals
-    for server_goal in server_goals {
-        if let Some(existing_pos) = model.goals.iter().position(|g| g.id == server_goal.id) {
-            model.goals[existing_pos] = server_goal;
-        } else {
-            model.goals.push(server_goal);
-        }
-    }
+    crate::app::conflict::merge_from_server(server_goals, model);
 }
 
 // *************
@@ -217,6 +564,12 @@ fn test_edit_goal() {
         target_date: Some("2024-04-20".to_string()),
         study_ids: vec!["Study 2".to_string()],
         tempo_target: Some(120),
+        practice_target: None,
+        clean_reps_to_complete: None,
+        version: 1,
+        updated_at: None,
+        archived_at: None,
+        deleted_at: None,
     };
 
     model.goals().update(updated_goal);
@@ -245,3 +598,161 @@ fn test_add_study_to_goal() {
     model.goals().add(goal);
     add_study_to_goal(&goal_id, "Study 1", &mut model);
 }
+
+#[test]
+fn test_goal_lifecycle() {
+    let mut goal = crate::app::create_test_goal("Scales", None);
+
+    assert_eq!(goal.complete(), Err(GoalError::NotStarted));
+    assert_eq!(
+        goal.reopen().unwrap_err().to_string(),
+        "Invalid transition from 'NotStarted' to 'InProgress'"
+    );
+
+    goal.start("2025-05-01T23:30:00-04:00").unwrap();
+    assert_eq!(goal.status, GoalStatus::InProgress);
+    assert_eq!(goal.start_date.as_deref(), Some("2025-05-01"));
+    assert!(matches!(
+        goal.start("2025-05-02T09:00:00Z"),
+        Err(GoalError::InvalidTransition { .. })
+    ));
+
+    goal.complete().unwrap();
+    assert_eq!(goal.status, GoalStatus::Completed);
+    assert_eq!(
+        goal.start("2025-05-02T09:00:00Z"),
+        Err(GoalError::AlreadyCompleted)
+    );
+
+    goal.reopen().unwrap();
+    assert_eq!(goal.status, GoalStatus::InProgress);
+    assert_eq!(goal.start_date.as_deref(), Some("2025-05-01"));
+}
+
+#[test]
+fn test_status_transitions() {
+    use GoalStatus::*;
+    assert!(NotStarted.can_transition_to(&InProgress));
+    assert!(Completed.can_transition_to(&InProgress));
+    assert!(Completed.can_transition_to(&Completed));
+    assert!(!NotStarted.can_transition_to(&Completed));
+    assert!(!InProgress.can_transition_to(&NotStarted));
+}
+
+#[test]
+fn test_invalid_goal_transition_is_reported_and_not_sent() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let goal = crate::app::create_test_goal("Scales", None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+
+    let _ = handle_event(GoalEvent::CompleteGoal(goal_id.clone()), &mut model);
+    assert_eq!(
+        model.last_error.as_deref(),
+        Some("Failed to complete goal: Goal has not been started")
+    );
+    assert!(model.outbox.operations.is_empty());
+
+    let _ = handle_event(
+        GoalEvent::StartGoal(goal_id.clone(), "2025-05-01T09:00:00Z".to_string()),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].status, GoalStatus::InProgress);
+    assert_eq!(model.outbox.operations.len(), 1);
+    assert_eq!(
+        model.outbox.operations[0].path,
+        format!("/api/goals/{goal_id}")
+    );
+}
+
+#[test]
+fn test_goal_progress() {
+    let mut with_target = crate::app::create_test_goal("Scales", None);
+    with_target.practice_target = Some(PracticeTarget {
+        amount: 120,
+        measure: TargetMeasure::Minutes,
+        period: TargetPeriod::Total,
+    });
+    let without_target = crate::app::create_test_goal("Etudes", None);
+    let stats = PracticeStats {
+        time_per_goal: vec![crate::app::TimeSpent {
+            id: with_target.id.clone(),
+            name: "Scales".to_string(),
+            minutes: 90,
+        }],
+        ..PracticeStats::default()
+    };
+
+    let progress = goal_progress(&[with_target, without_target], &stats);
+    assert_eq!(progress[0].practiced_minutes, 90);
+    assert_eq!(progress[0].percent, Some(75));
+    assert_eq!(progress[1].practiced_minutes, 0);
+    assert_eq!(progress[1].percent, None);
+}
+
+#[test]
+fn test_tempo_progress() {
+    let goal = crate::app::create_custom_test_goal(
+        "Etude at 120",
+        None,
+        GoalStatus::InProgress,
+        vec!["etude".to_string()],
+        Some(120),
+    );
+    let mut session = crate::app::create_test_session(&[&goal.id], "Tempo");
+    for (study_id, bpm, clean) in [
+        ("etude", 104, true),
+        ("etude", 124, false),
+        ("etude", 120, true),
+        ("scales", 160, true),
+    ] {
+        let mut study_session = crate::app::create_test_study_session(study_id, &session.id);
+        study_session.record_tempo(bpm, clean);
+        session.push_study_session(study_session);
+    }
+
+    let progress = tempo_progress(&[goal], &[session]);
+    assert_eq!(progress[0].best_tempo, Some(124));
+    assert_eq!(progress[0].tempo_target, Some(120));
+    assert_eq!(progress[0].clean_reps_at_target, 1);
+}
+
+#[test]
+fn test_remove_goal_in_use_is_blocked_until_detached() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let goal = crate::app::create_test_goal("Scales", None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+    for _ in 0..14 {
+        model
+            .sessions
+            .push(crate::app::create_test_session(&[&goal_id], "Scales"));
+    }
+    let view = crate::app::ViewModel::new(&model);
+    assert_eq!(
+        view.goal_impacts[0].summary.as_deref(),
+        Some("used by 14 sessions")
+    );
+
+    let _ = handle_event(
+        GoalEvent::RemoveGoal(goal_id.clone(), DeleteMode::BlockIfReferenced),
+        &mut model,
+    );
+    assert_eq!(model.goals.len(), 1);
+    assert!(model.outbox.operations.is_empty());
+    assert_eq!(
+        model.last_error.as_deref(),
+        Some("Failed to remove goal: used by 14 sessions")
+    );
+
+    let _ = handle_event(
+        GoalEvent::RemoveGoal(goal_id.clone(), DeleteMode::Detach),
+        &mut model,
+    );
+    assert!(model.goals.is_empty());
+    assert!(model.sessions.iter().all(|s| s.goal_ids.is_empty()));
+    assert_eq!(
+        model.outbox.operations[0].path,
+        format!("/api/goals/{goal_id}")
+    );
+}
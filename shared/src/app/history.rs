@@ -0,0 +1,310 @@
+use crate::app::model::Model;
+use crate::app::{Event, GoalEvent, StudyEvent};
+use crux_core::Command;
+use std::collections::VecDeque;
+
+// How many actions can be undone, the oldest are forgotten first
+const HISTORY_LIMIT: usize = 50;
+
+/// A user action as the events that take it back and apply it again. Both
+/// go through the same handlers as the action itself, so they are sent to
+/// the server like any other change.
+#[derive(Clone, Debug, PartialEq)]
+struct HistoryEntry {
+    label: String, // e.g. "Remove session"
+    undo: Event,
+    redo: Event,
+}
+
+/// Actions that can be undone, most recent last, and those undone that can
+/// be redone until something new is done
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    // Set while an undo or redo is applied, so it isn't recorded as a new action
+    replaying: bool,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo_label(&self) -> Option<String> {
+        self.undo.back().map(|entry| entry.label.clone())
+    }
+
+    pub fn redo_label(&self) -> Option<String> {
+        self.redo.last().map(|entry| entry.label.clone())
+    }
+
+    fn push_undo(&mut self, entry: HistoryEntry) {
+        if self.undo.len() == HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(entry);
+    }
+}
+
+/// Remembers an action the user just took, with the event that undoes it and
+/// the one that redoes it
+pub fn record(model: &mut Model, label: &str, undo: Event, redo: Event) {
+    let history = &mut model.history;
+    if history.replaying {
+        return;
+    }
+
+    history.redo.clear();
+    history.push_undo(HistoryEntry {
+        label: label.to_string(),
+        undo,
+        redo,
+    });
+}
+
+/// The event taking back the most recent action, which moves to the redo stack
+pub fn undo(model: &mut Model) -> Option<Event> {
+    let entry = model.history.undo.pop_back()?;
+    let event = rebase(entry.undo.clone(), model);
+    model.history.redo.push(entry);
+    Some(event)
+}
+
+/// The event applying the most recently undone action again
+pub fn redo(model: &mut Model) -> Option<Event> {
+    let entry = model.history.redo.pop()?;
+    let event = rebase(entry.redo.clone(), model);
+    model.history.push_undo(entry);
+    Some(event)
+}
+
+/// Applies an undo or redo step without recording it as a new action
+pub fn replay(
+    model: &mut Model,
+    apply: impl FnOnce(&mut Model) -> Command<super::Effect, super::Event>,
+) -> Command<super::Effect, super::Event> {
+    model.history.replaying = true;
+    let command = apply(model);
+    model.history.replaying = false;
+    command
+}
+
+// Whole-entity updates were captured with the version they had then, so they
+// are moved onto the current one. Whether it is archived, and a status the
+// server won't go back to, are left as they are now.
+fn rebase(event: Event, model: &Model) -> Event {
+    match event {
+        Event::Goal(GoalEvent::UpdateGoal(mut goal)) => {
+            if let Some(current) = model.goals.iter().find(|g| g.id == goal.id) {
+                goal.version = current.version;
+                goal.archived_at = current.archived_at.clone();
+                if !current.status.can_transition_to(&goal.status) {
+                    goal.status = current.status.clone();
+                }
+            }
+            Event::Goal(GoalEvent::UpdateGoal(goal))
+        }
+        Event::Study(StudyEvent::UpdateStudy(mut study)) => {
+            if let Some(current) = model.studies.iter().find(|s| s.id == study.id) {
+                study.version = current.version;
+                study.archived_at = current.archived_at.clone();
+            }
+            Event::Study(StudyEvent::UpdateStudy(study))
+        }
+        event => event,
+    }
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn update(event: Event, model: &mut Model) {
+    use crux_core::App as _;
+
+    let _ = crate::Chopin.update(event, model, &());
+}
+
+#[test]
+fn test_undoing_a_removed_session_restores_it_on_the_server() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let session = crate::app::create_ended_test_session(&[], "Scales");
+    let session_id = session.id.clone();
+    model.sessions.push(session);
+    assert!(!model.history.can_undo());
+
+    update(
+        Event::Session(crate::app::SessionEvent::RemoveSession(session_id.clone())),
+        &mut model,
+    );
+    let view = crate::app::ViewModel::new(&model);
+    assert!(view.can_undo);
+    assert_eq!(view.undo_label.as_deref(), Some("Remove session"));
+
+    update(Event::Undo, &mut model);
+    assert_eq!(model.sessions[0].id, session_id);
+    assert!(model.trash.sessions.is_empty());
+    let paths: Vec<&str> = model
+        .outbox
+        .operations
+        .iter()
+        .map(|op| op.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            format!("/api/sessions/{session_id}"),
+            format!("/api/sessions/{session_id}/restore")
+        ]
+    );
+    // The undo itself isn't something to undo
+    assert!(!model.history.can_undo());
+    assert_eq!(
+        model.history.redo_label().as_deref(),
+        Some("Remove session")
+    );
+
+    update(Event::Redo, &mut model);
+    assert!(model.sessions.is_empty());
+    assert_eq!(
+        model.history.undo_label().as_deref(),
+        Some("Remove session")
+    );
+    assert!(!model.history.can_redo());
+}
+
+#[test]
+fn test_undoing_an_edit_sends_the_previous_goal_at_the_current_version() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let goal = crate::app::create_test_goal("Scales", None);
+    model.goals.push(goal.clone());
+
+    update(
+        Event::Goal(GoalEvent::UpdateGoal(crate::app::PracticeGoal {
+            name: "Arpeggios".to_string(),
+            ..goal.clone()
+        })),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].version, goal.version + 1);
+
+    update(Event::Undo, &mut model);
+    assert_eq!(model.goals[0].name, "Scales");
+    let body: serde_json::Value =
+        serde_json::from_str(model.outbox.operations[1].body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["name"], "Scales");
+    assert_eq!(body["version"], goal.version + 1);
+}
+
+#[test]
+fn test_history_is_bounded_and_new_actions_clear_redo() {
+    let mut model = crate::app::create_signed_in_test_model();
+    for i in 0..HISTORY_LIMIT + 5 {
+        let study = crate::app::Study::new(format!("Study {i}"), None);
+        update(Event::Study(StudyEvent::CreateStudy(study)), &mut model);
+    }
+    assert_eq!(model.history.undo.len(), HISTORY_LIMIT);
+
+    update(Event::Undo, &mut model);
+    assert!(model.history.can_redo());
+    let study = crate::app::Study::new("Etudes".to_string(), None);
+    update(Event::Study(StudyEvent::CreateStudy(study)), &mut model);
+    assert!(!model.history.can_redo());
+    assert_eq!(model.history.undo_label().as_deref(), Some("Add study"));
+
+    // Nothing left to redo is a no-op
+    update(Event::Redo, &mut model);
+    assert_eq!(model.history.undo.len(), HISTORY_LIMIT);
+}
+
+#[test]
+fn test_undoing_a_detached_goal_removal_puts_it_back_in_its_sessions() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let goal = crate::app::create_test_goal("Scales", None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+    model.sessions.push(crate::app::create_ended_test_session(
+        &["first", &goal_id, "last"],
+        "One",
+    ));
+    model.templates.push(crate::app::SessionTemplate {
+        id: "template".to_string(),
+        goal_ids: vec![goal_id.clone()],
+        ..crate::app::SessionTemplate::default()
+    });
+
+    update(
+        Event::Goal(GoalEvent::RemoveGoal(
+            goal_id.clone(),
+            crate::app::DeleteMode::Detach,
+        )),
+        &mut model,
+    );
+    assert_eq!(model.sessions[0].goal_ids, vec!["first", "last"]);
+    assert!(model.templates[0].goal_ids.is_empty());
+
+    update(Event::Undo, &mut model);
+    assert_eq!(model.goals[0].id, goal_id);
+    assert_eq!(
+        model.sessions[0].goal_ids,
+        vec!["first".to_string(), goal_id.clone(), "last".to_string()]
+    );
+    assert_eq!(model.templates[0].goal_ids, vec![goal_id.clone()]);
+
+    // Redoing detaches it again
+    update(Event::Redo, &mut model);
+    assert_eq!(model.sessions[0].goal_ids, vec!["first", "last"]);
+}
+
+#[test]
+fn test_undoing_a_detached_study_removal_brings_back_its_study_sessions() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let study = crate::app::create_test_study("Scales", None);
+    let study_id = study.id.clone();
+    model.studies.push(study);
+    let mut goal = crate::app::create_test_goal("Technique", None);
+    goal.study_ids = vec!["other".to_string(), study_id.clone()];
+    model.goals.push(goal);
+    let mut session = crate::app::create_ended_test_session(&[], "Warm up");
+    let kept = crate::app::create_test_study_session("other", &session.id);
+    let removed = crate::app::create_test_study_session(&study_id, &session.id);
+    session.study_sessions = vec![removed.clone(), kept.clone()];
+    model.sessions.push(session);
+    let block = |study_id: &str| crate::app::TemplateBlock {
+        study_id: study_id.to_string(),
+        planned_minutes: 10,
+    };
+    model.templates.push(crate::app::SessionTemplate {
+        id: "template".to_string(),
+        blocks: vec![block(&study_id), block("other"), block(&study_id)],
+        ..crate::app::SessionTemplate::default()
+    });
+
+    update(
+        Event::Study(StudyEvent::RemoveStudy(
+            study_id.clone(),
+            crate::app::DeleteMode::Detach,
+        )),
+        &mut model,
+    );
+    assert_eq!(model.sessions[0].study_sessions, vec![kept.clone()]);
+    assert_eq!(model.templates[0].blocks, vec![block("other")]);
+
+    update(Event::Undo, &mut model);
+    assert_eq!(model.studies[0].id, study_id);
+    assert_eq!(model.sessions[0].study_sessions, vec![removed, kept]);
+    assert_eq!(
+        model.goals[0].study_ids,
+        vec!["other".to_string(), study_id.clone()]
+    );
+    assert_eq!(
+        model.templates[0].blocks,
+        vec![block(&study_id), block("other"), block(&study_id)]
+    );
+}
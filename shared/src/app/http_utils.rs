@@ -1,25 +1,150 @@
-use crate::app::{Effect, Event, HttpResult};
+use crate::app::{AppError, Effect, Event, HttpResult, Model, OperationMethod, PendingOperation};
 /// HTTP utilities for common request patterns
 use crux_core::Command;
-use crux_http::command::Http;
+use crux_http::command::{Http, RequestBuilder};
+use crux_http::http::Method;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-// Base API URL - can be overridden by platforms if needed
-const API_BASE_URL: &str = "https://intrada-server.fly.dev";
+// Production API, used until the shell configures another environment
+pub const DEFAULT_API_BASE_URL: &str = "https://intrada-server.fly.dev";
+
+/// Where the api_* helpers send requests (production, staging, localhost or a
+/// test stand-in)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiConfig {
+    base_url: Url,
+}
+
+impl ApiConfig {
+    pub fn new(base_url: &str) -> Result<Self, AppError> {
+        let mut url =
+            Url::parse(base_url).map_err(|e| AppError::InvalidUrl(format!("{base_url}: {e}")))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::InvalidUrl(format!(
+                "{base_url}: expected an http or https URL"
+            )));
+        }
+
+        // Keep a path prefix such as https://example.com/intrada when joining
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        Ok(Self { base_url: url })
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    /// Resolves an API path such as "/api/goals" against the base URL
+    pub fn url(&self, path: &str) -> Result<Url, AppError> {
+        self.base_url
+            .join(path.trim_start_matches('/'))
+            .map_err(|e| AppError::InvalidUrl(format!("{path}: {e}")))
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_API_BASE_URL).expect("default API base URL is valid")
+    }
+}
+
+fn parse_url(url: &str) -> Result<Url, AppError> {
+    Url::parse(url).map_err(|e| AppError::InvalidUrl(format!("{url}: {e}")))
+}
+
+// Requests to the API carry the signed in user's token
+fn request(method: Method, url: Url, token: Option<&str>) -> RequestBuilder<Effect, Event> {
+    let request = Http::request(method, url);
+    match token {
+        Some(token) => request.header("Authorization", format!("Bearer {token}")),
+        None => request,
+    }
+}
+
+// A request that can't be built is reported instead of sent
+fn request_failed(error: AppError) -> Command<Effect, Event> {
+    log::error!("Failed to build request: {error}");
+    Command::event(Event::Failed(error))
+}
+
+fn send_get<T: for<'de> Deserialize<'de> + 'static>(
+    url: Result<Url, AppError>,
+    token: Option<&str>,
+    callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
+) -> Command<Effect, Event> {
+    match url {
+        Ok(url) => request(Method::Get, url, token)
+            .expect_json()
+            .build()
+            .map(Into::into)
+            .then_send(callback),
+        Err(error) => request_failed(error),
+    }
+}
+
+fn send_post<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
+    url: Result<Url, AppError>,
+    token: Option<&str>,
+    body: &B,
+    callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
+) -> Command<Effect, Event> {
+    let prepared = url.and_then(|url| Ok((url, serde_json::to_string(body)?)));
+    match prepared {
+        Ok((url, json_string)) => request(Method::Post, url, token)
+            .header("Content-Type", "application/json")
+            .body(json_string)
+            .expect_json::<T>()
+            .build()
+            .map(Into::into)
+            .then_send(callback),
+        Err(error) => request_failed(error),
+    }
+}
+
+fn send_put<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
+    url: Result<Url, AppError>,
+    token: Option<&str>,
+    body: &B,
+    callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
+) -> Command<Effect, Event> {
+    let prepared = url.and_then(|url| Ok((url, serde_json::to_string(body)?)));
+    match prepared {
+        Ok((url, json_string)) => request(Method::Put, url, token)
+            .header("Content-Type", "application/json")
+            .body(json_string)
+            .expect_json::<T>()
+            .build()
+            .map(Into::into)
+            .then_send(callback),
+        Err(error) => request_failed(error),
+    }
+}
+
+fn send_delete<T: for<'de> Deserialize<'de> + 'static>(
+    url: Result<Url, AppError>,
+    token: Option<&str>,
+    callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
+) -> Command<Effect, Event> {
+    match url {
+        Ok(url) => request(Method::Delete, url, token)
+            .expect_json()
+            .build()
+            .map(Into::into)
+            .then_send(callback),
+        Err(error) => request_failed(error),
+    }
+}
 
 /// Creates a GET request command using API base URL + path
 pub fn api_get<T: for<'de> Deserialize<'de> + 'static>(
+    model: &Model,
     path: &str,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let base = Url::parse(API_BASE_URL).unwrap();
-    let url = base.join(path).unwrap();
-    Http::get(url)
-        .expect_json()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_get(model.api.url(path), model.auth.bearer_token(), callback)
 }
 
 /// Creates a GET request command for custom URLs (backwards compatibility)
@@ -27,30 +152,22 @@ pub fn get_request<T: for<'de> Deserialize<'de> + 'static>(
     url: &str,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    Http::get(url)
-        .expect_json()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_get(parse_url(url), None, callback)
 }
 
 /// Creates a POST request command with JSON body using API base URL + path
 pub fn api_post<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
+    model: &Model,
     path: &str,
     body: &B,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let base = Url::parse(API_BASE_URL).unwrap();
-    let url = base.join(path).unwrap();
-    let json_string = serde_json::to_string(body).expect("Failed to serialize JSON");
-
-    Http::post(url)
-        .header("Content-Type", "application/json")
-        .body(json_string)
-        .expect_json::<T>()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_post(
+        model.api.url(path),
+        model.auth.bearer_token(),
+        body,
+        callback,
+    )
 }
 
 /// Creates a POST request command with JSON body for custom URLs (backwards compatibility)
@@ -59,34 +176,22 @@ pub fn post_json_request<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
     body: &B,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let json_string = serde_json::to_string(body).expect("Failed to serialize JSON");
-
-    Http::post(url)
-        .header("Content-Type", "application/json")
-        .body(json_string)
-        .expect_json::<T>()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_post(parse_url(url), None, body, callback)
 }
 
 /// Creates a PUT request command with JSON body using API base URL + path
 pub fn api_put<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
+    model: &Model,
     path: &str,
     body: &B,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let base = Url::parse(API_BASE_URL).unwrap();
-    let url = base.join(path).unwrap();
-    let json_string = serde_json::to_string(body).expect("Failed to serialize JSON");
-
-    Http::put(url)
-        .header("Content-Type", "application/json")
-        .body(json_string)
-        .expect_json::<T>()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_put(
+        model.api.url(path),
+        model.auth.bearer_token(),
+        body,
+        callback,
+    )
 }
 
 /// Creates a PUT request command with JSON body for custom URLs (backwards compatibility)
@@ -95,29 +200,16 @@ pub fn put_json_request<T: for<'de> Deserialize<'de> + 'static, B: Serialize>(
     body: &B,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let json_string = serde_json::to_string(body).expect("Failed to serialize JSON");
-
-    Http::put(url)
-        .header("Content-Type", "application/json")
-        .body(json_string)
-        .expect_json::<T>()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_put(parse_url(url), None, body, callback)
 }
 
 /// Creates a DELETE request command using API base URL + path
 pub fn api_delete<T: for<'de> Deserialize<'de> + 'static>(
+    model: &Model,
     path: &str,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    let base = Url::parse(API_BASE_URL).unwrap();
-    let url = base.join(path).unwrap();
-    Http::delete(url)
-        .expect_json()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_delete(model.api.url(path), model.auth.bearer_token(), callback)
 }
 
 /// Creates a DELETE request command for custom URLs (backwards compatibility)
@@ -125,15 +217,79 @@ pub fn delete_request<T: for<'de> Deserialize<'de> + 'static>(
     url: &str,
     callback: fn(HttpResult<crux_http::Response<T>, crux_http::HttpError>) -> Event,
 ) -> Command<Effect, Event> {
-    Http::delete(url)
-        .expect_json()
-        .build()
-        .map(Into::into)
-        .then_send(callback)
+    send_delete(parse_url(url), None, callback)
+}
+
+/// Sends a queued outbox operation, tagged with its idempotency key. The
+/// outbox waits for the callback, so a URL that can't be built is passed to
+/// it as a failed request rather than reported separately.
+pub fn api_send(
+    model: &Model,
+    operation: &PendingOperation,
+    callback: impl FnOnce(HttpResult<crux_http::Response<Vec<u8>>, crux_http::HttpError>) -> Event
+        + Send
+        + 'static,
+) -> Command<Effect, Event> {
+    let url = match model.api.url(&operation.path) {
+        Ok(url) => url,
+        Err(error) => {
+            return Command::event(callback(HttpResult::Err(crux_http::HttpError::Url(
+                error.to_string(),
+            ))))
+        }
+    };
+    let method = match operation.method {
+        OperationMethod::Post => Method::Post,
+        OperationMethod::Put => Method::Put,
+        OperationMethod::Delete => Method::Delete,
+    };
+    let request = request(method, url, model.auth.bearer_token())
+        .header("Idempotency-Key", operation.id.as_str());
+
+    let request = match &operation.body {
+        Some(body) => request
+            .header("Content-Type", "application/json")
+            .body(body.clone()),
+        None => request,
+    };
+
+    request.build().map(Into::into).then_send(callback)
 }
 
 #[cfg(test)]
 mod tests {
-    // HTTP utility functions are tested through integration tests
-    // since they require mocking the HTTP layer
+    // Sending requests is tested through integration tests
+    // since it requires mocking the HTTP layer
+    use super::*;
+
+    #[test]
+    fn test_default_config_points_at_production() {
+        let config = ApiConfig::default();
+        assert_eq!(
+            config.url("/api/goals").unwrap().as_str(),
+            "https://intrada-server.fly.dev/api/goals"
+        );
+    }
+
+    #[test]
+    fn test_config_keeps_base_path_and_query() {
+        let config = ApiConfig::new("http://localhost:3000/intrada").unwrap();
+        assert_eq!(config.base_url(), "http://localhost:3000/intrada/");
+        assert_eq!(
+            config.url("/api/changes?since=x").unwrap().as_str(),
+            "http://localhost:3000/intrada/api/changes?since=x"
+        );
+    }
+
+    #[test]
+    fn test_config_rejects_invalid_urls() {
+        assert!(matches!(
+            ApiConfig::new("not a url"),
+            Err(AppError::InvalidUrl(_))
+        ));
+        assert!(matches!(
+            ApiConfig::new("ftp://example.com"),
+            Err(AppError::InvalidUrl(_))
+        ));
+    }
 }
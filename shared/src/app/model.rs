@@ -1,4 +1,16 @@
-use crate::app::{PracticeGoal, PracticeSession, SessionState, Study};
+use crate::app::auth::AuthState;
+use crate::app::history::History;
+use crate::app::http_utils::ApiConfig;
+use crate::app::outbox::Outbox;
+use crate::app::template::{current_block, CurrentBlock};
+use crate::app::timer::Clock;
+use crate::app::utils::IdGenerator;
+use crate::app::{
+    goal_impact, goal_progress, plan_occurrences, study_impact, target_progress, tempo_progress,
+    AppError, DeleteImpact, ExportedData, GoalProgress, PendingOperation, PlanOccurrence,
+    PracticeGoal, PracticePlan, PracticeSession, PracticeStats, SessionState, SessionTemplate,
+    Study, StudySession, SyncConflict, SyncCursor, TargetProgress, TempoProgress, Trash, User,
+};
 use chrono::{DateTime, Utc};
 use facet::Facet;
 use serde::{Deserialize, Serialize};
@@ -8,7 +20,24 @@ pub struct Model {
     pub goals: Vec<PracticeGoal>,
     pub studies: Vec<Study>,
     pub sessions: Vec<PracticeSession>,
+    pub plans: Vec<PracticePlan>,
+    pub templates: Vec<SessionTemplate>,
+    // What was removed here, or fetched from the server's trash
+    pub trash: Trash,
+    // Goal, study and session changes that can be undone
+    pub history: History,
+    // The last export made by the core, until the shell has saved it
+    pub export: Option<ExportedData>,
+    pub outbox: Outbox,
+    pub clock: Clock,
+    pub ids: IdGenerator,
+    pub conflicts: Vec<SyncConflict>,
+    pub sync_cursor: SyncCursor,
+    pub api: ApiConfig,
+    pub auth: AuthState,
     pub last_error: Option<String>,
+    // Typed form of last_error, e.g. to point at the fields a request got wrong
+    pub error: Option<AppError>,
 }
 
 impl Model {}
@@ -16,32 +45,92 @@ impl Model {}
 #[derive(Facet, Serialize, Deserialize, Clone, Default)]
 #[allow(clippy::struct_excessive_bools)]
 pub struct ViewModel {
+    // Archived goals, studies and sessions are left out, see `archived_*`
     pub goals: Vec<PracticeGoal>,
     pub studies: Vec<Study>,
     pub sessions: Vec<PracticeSession>,
+    pub archived_goals: Vec<PracticeGoal>,
+    pub archived_studies: Vec<Study>,
+    pub archived_sessions: Vec<PracticeSession>,
+    pub trash: Trash,
+    pub plans: Vec<PracticePlan>,
+    // Every plan's occurrences from a week ago to a week ahead, oldest first
+    pub plan_occurrences: Vec<PlanOccurrence>,
+    pub templates: Vec<SessionTemplate>,
     // Session state computed properties (replaces SessionManager)
     pub current_session: Option<PracticeSession>,
     pub has_active_session: bool, // Note: "active" means currently started session
     pub can_start_session: bool,
     pub can_end_session: bool,
     pub is_session_running: bool,
+    pub is_session_paused: bool,
     pub is_session_ended: bool,
     pub current_session_elapsed_time: Option<String>, // e.g. "01:23:45"
+    pub active_study_session: Option<StudySession>,
+    pub active_study_elapsed_time: Option<String>,
+    // The template block being worked on while the current session runs
+    pub current_block: Option<CurrentBlock>,
+    // Local changes not yet on the server, for the shell to persist across launches
+    pub pending_operations: Vec<PendingOperation>,
+    pub pending_operation_count: u32,
+    pub failed_operation_count: u32,
+    // Local edits the server rejected, waiting for the user to pick a side
+    pub conflicts: Vec<SyncConflict>,
+    // Where incremental sync left off, for the shell to persist across launches
+    pub sync_cursor: Option<String>,
+    pub user: Option<User>,
+    pub is_signed_in: bool,
+    // Kept by the shell in secure storage and restored on launch
+    pub auth_token: Option<String>,
+    // Stored with the token and passed back with it in RestoreToken
+    pub auth_user_id: Option<String>,
+    // Totals, streaks and time per goal/study from finished sessions
+    pub stats: PracticeStats,
+    // One per goal, in the order of `goals`
+    pub goal_progress: Vec<GoalProgress>,
+    // Best logged tempo against the tempo target, one per goal
+    pub tempo_progress: Vec<TempoProgress>,
+    // One per goal with a practice target, for its current period
+    pub target_progress: Vec<TargetProgress>,
+    // What removing each goal or study would detach, in the order of `goals`
+    // and `studies`
+    pub goal_impacts: Vec<DeleteImpact>,
+    pub study_impacts: Vec<DeleteImpact>,
+    // For an "Undo" toast, e.g. "Remove session"
+    pub can_undo: bool,
+    pub undo_label: Option<String>,
+    pub can_redo: bool,
+    pub redo_label: Option<String>,
+    // JSON archive and sessions CSV for the shell to save or share
+    pub export: Option<ExportedData>,
     pub last_error: Option<String>,
+    pub error: Option<AppError>,
 }
 
 impl ViewModel {
-    pub fn new(
-        goals: Vec<PracticeGoal>,
-        studies: Vec<Study>,
-        sessions: Vec<PracticeSession>,
-        last_error: Option<String>,
-    ) -> Self {
-        // Find current session (the one that is started)
-        let current_session = sessions
+    pub fn new(model: &Model) -> Self {
+        // Computed for the clock's time, never the system's, so views are
+        // reproducible
+        let now = model.clock.now;
+        let (goals, archived_goals): (Vec<_>, Vec<_>) = model
+            .goals
             .iter()
-            .find(|s| matches!(s.state, SessionState::Started { .. }))
-            .cloned();
+            .cloned()
+            .partition(|g| g.archived_at.is_none());
+        let (studies, archived_studies): (Vec<_>, Vec<_>) = model
+            .studies
+            .iter()
+            .cloned()
+            .partition(|s| s.archived_at.is_none());
+        let (sessions, archived_sessions): (Vec<_>, Vec<_>) = model
+            .sessions
+            .iter()
+            .cloned()
+            .partition(|s| s.archived_at.is_none());
+        let pending_operations = model.outbox.operations.clone();
+
+        // Find current session (the one that is started or paused)
+        let current_session = sessions.iter().find(|s| s.is_in_progress()).cloned();
 
         let has_active_session = current_session.is_some();
 
@@ -49,7 +138,7 @@ impl ViewModel {
         let (can_start_session, can_end_session, is_session_running, is_session_ended) =
             if let Some(ref session) = current_session {
                 let can_start = matches!(session.state, SessionState::NotStarted);
-                let can_end = matches!(session.state, SessionState::Started { .. });
+                let can_end = session.is_in_progress();
                 let is_running = matches!(session.state, SessionState::Started { .. });
                 let is_ended = matches!(session.state, SessionState::Ended { .. });
 
@@ -57,69 +146,123 @@ impl ViewModel {
             } else {
                 (false, false, false, false)
             };
+        let is_session_paused = current_session.as_ref().is_some_and(|s| s.is_paused());
 
         // Calculate elapsed time for running sessions
         let current_session_elapsed_time = if let Some(ref session) = current_session {
             match &session.state {
-                SessionState::Started { start_time } => {
-                    Some(calculate_elapsed_time_from_start(start_time))
-                }
-                SessionState::PendingReflection {
-                    start_time,
-                    end_time,
-                } => Some(calculate_elapsed_time_between(start_time, end_time)),
-                SessionState::Ended {
+                SessionState::Started { start_time } => Some(calculate_elapsed_time_from_start(
                     start_time,
-                    end_time,
-                    duration_in_seconds: _,
-                } => Some(calculate_elapsed_time_between(start_time, end_time)),
+                    session.paused_seconds(),
+                    now,
+                )),
+                SessionState::Paused {
+                    accumulated_seconds,
+                    ..
+                } => Some(format_duration_hms(i64::from(*accumulated_seconds))),
+                SessionState::PendingReflection { .. } | SessionState::Ended { .. } => session
+                    .duration_in_seconds()
+                    .map(|seconds| format_duration_hms(i64::from(seconds))),
                 SessionState::NotStarted => None,
             }
         } else {
             None
         };
 
+        // The study currently being worked on within the current session
+        let active_study_session = current_session
+            .as_ref()
+            .and_then(|s| s.active_study_session())
+            .cloned();
+        let timestamp = now.to_rfc3339();
+        let active_study_elapsed_time = active_study_session
+            .as_ref()
+            .map(|study| format_duration_hms(i64::from(study.elapsed_seconds_at(&timestamp))));
+        let current_block = current_session
+            .as_ref()
+            .and_then(|s| current_block(s, &timestamp));
+
+        let count = |failed: bool| {
+            let count = pending_operations
+                .iter()
+                .filter(|op| op.is_failed() == failed)
+                .count();
+            u32::try_from(count).unwrap_or(u32::MAX)
+        };
+        let pending_operation_count = count(false);
+        let failed_operation_count = count(true);
+
+        let stats = PracticeStats::new(model, now, model.clock.zone);
+        let goal_progress = goal_progress(&goals, &stats);
+        let tempo_progress = tempo_progress(&goals, &model.sessions);
+        let target_progress = target_progress(&goals, &model.sessions, now, model.clock.zone);
+        let goal_impacts = goals.iter().map(|g| goal_impact(model, &g.id)).collect();
+        let study_impacts = studies.iter().map(|s| study_impact(model, &s.id)).collect();
+
         Self {
             goals,
             studies,
+            archived_goals,
+            archived_studies,
+            archived_sessions,
+            trash: model.trash.clone(),
+            plans: model.plans.clone(),
+            plan_occurrences: plan_occurrences(&model.plans, &model.sessions, now),
+            templates: model.templates.clone(),
             sessions,
             current_session,
             has_active_session,
             can_start_session,
             can_end_session,
             is_session_running,
+            is_session_paused,
             is_session_ended,
             current_session_elapsed_time,
-            last_error,
+            active_study_session,
+            active_study_elapsed_time,
+            current_block,
+            pending_operations,
+            pending_operation_count,
+            failed_operation_count,
+            conflicts: model.conflicts.clone(),
+            sync_cursor: model.sync_cursor.since.clone(),
+            user: model.auth.user.clone(),
+            is_signed_in: model.auth.is_signed_in(),
+            auth_token: model.auth.token.clone(),
+            auth_user_id: model.auth.owner_id.clone(),
+            stats,
+            goal_progress,
+            tempo_progress,
+            target_progress,
+            goal_impacts,
+            study_impacts,
+            can_undo: model.history.can_undo(),
+            undo_label: model.history.undo_label(),
+            can_redo: model.history.can_redo(),
+            redo_label: model.history.redo_label(),
+            export: model.export.clone(),
+            last_error: model.last_error.clone(),
+            error: model.error.clone(),
         }
     }
 }
 
 // Helper functions for time calculations
-fn calculate_elapsed_time_from_start(start_time: &str) -> String {
+fn calculate_elapsed_time_from_start(
+    start_time: &str,
+    paused_seconds: u32,
+    now: DateTime<Utc>,
+) -> String {
     let start = match DateTime::parse_from_rfc3339(start_time) {
         Ok(datetime) => datetime,
         Err(_) => return "Invalid start time".to_string(),
     };
-    let now = Utc::now();
     let duration = now.signed_duration_since(start);
-    format_duration_hms(duration.num_seconds())
-}
-
-fn calculate_elapsed_time_between(start_time: &str, end_time: &str) -> String {
-    let start = match DateTime::parse_from_rfc3339(start_time) {
-        Ok(datetime) => datetime,
-        Err(_) => return "Invalid start time".to_string(),
-    };
-    let end = match DateTime::parse_from_rfc3339(end_time) {
-        Ok(datetime) => datetime,
-        Err(_) => return "Invalid end time".to_string(),
-    };
-    let duration = end.signed_duration_since(start);
-    format_duration_hms(duration.num_seconds())
+    // The clock can lag a session started since the last tick
+    format_duration_hms((duration.num_seconds() - i64::from(paused_seconds)).max(0))
 }
 
-fn format_duration_hms(total_seconds: i64) -> String {
+pub(crate) fn format_duration_hms(total_seconds: i64) -> String {
     let hours = total_seconds / 3600;
     let minutes = (total_seconds % 3600) / 60;
     let seconds = total_seconds % 60;
@@ -0,0 +1,629 @@
+use crate::app::conflict::{self, EntityKind, OperationTarget};
+use crate::app::model::Model;
+use crate::app::AppError;
+use crate::HttpResult;
+use chrono::{DateTime, Duration, Utc};
+use crux_core::Command;
+use crux_http::HttpError;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// Attempts made before an operation is marked as failed
+pub const MAX_ATTEMPTS: u32 = 8;
+const BASE_BACKOFF_SECONDS: i64 = 2;
+const MAX_BACKOFF_SECONDS: i64 = 300;
+
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub enum OperationMethod {
+    Post,
+    Put,
+    Delete,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[repr(C)]
+pub enum OperationStatus {
+    #[default]
+    Pending,
+    Failed,
+}
+
+/// A local change waiting to be sent to the server
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PendingOperation {
+    /// Also sent as the Idempotency-Key header, so a retry is never applied twice
+    pub id: String,
+    pub method: OperationMethod,
+    pub path: String,
+    pub body: Option<String>,
+    pub attempts: u32,
+    pub next_attempt_at: Option<String>,
+    pub status: OperationStatus,
+    pub last_error: Option<String>,
+}
+
+impl PendingOperation {
+    pub fn new(method: OperationMethod, path: String, body: Option<String>) -> Self {
+        Self {
+            id: crate::app::generate_id(),
+            method,
+            path,
+            body,
+            attempts: 0,
+            next_attempt_at: None,
+            status: OperationStatus::Pending,
+            last_error: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == OperationStatus::Pending
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.status == OperationStatus::Failed
+    }
+
+    fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_attempt_at
+            .as_deref()
+            .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+            .map_or(true, |at| at <= now)
+    }
+
+    fn wait_on_failed(&mut self) {
+        self.status = OperationStatus::Failed;
+        self.next_attempt_at = None;
+        self.last_error = Some("An earlier change to the same item failed".to_string());
+    }
+
+    // Transient failures are retried with exponential backoff, anything the
+    // server rejected outright would fail the same way again
+    fn record_failure(&mut self, error: &HttpError, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.last_error = Some(format!("{error:?}"));
+
+        if !is_retryable(error) || self.attempts >= MAX_ATTEMPTS {
+            self.status = OperationStatus::Failed;
+            self.next_attempt_at = None;
+        } else {
+            self.next_attempt_at = Some((now + backoff(self.attempts)).to_rfc3339());
+        }
+    }
+}
+
+/// Ordered queue of local changes not yet acknowledged by the server
+#[derive(Default)]
+pub struct Outbox {
+    pub operations: Vec<PendingOperation>,
+    pub in_flight: Option<String>,
+}
+
+impl Outbox {
+    pub fn pending_count(&self) -> usize {
+        self.operations.iter().filter(|op| op.is_pending()).count()
+    }
+
+    pub fn failed_count(&self) -> usize {
+        self.operations.iter().filter(|op| op.is_failed()).count()
+    }
+
+    /// Whether the entity has local changes the server hasn't accepted yet
+    pub fn touches(&self, kind: EntityKind, id: &str) -> bool {
+        self.operations.iter().any(|op| {
+            OperationTarget::from_path(&op.path).is_some_and(|target| target.matches(kind, id))
+        })
+    }
+
+    /// Drops the queued changes to an entity. The one in flight is kept so
+    /// its response can still be matched up.
+    pub fn discard(&mut self, kind: EntityKind, id: &str) {
+        let in_flight = self.in_flight.clone();
+        self.operations.retain(|op| {
+            in_flight.as_deref() == Some(op.id.as_str())
+                || !OperationTarget::from_path(&op.path)
+                    .is_some_and(|target| target.matches(kind, id))
+        });
+    }
+
+    // Later changes to an entity build on the earlier ones, so once one has
+    // failed for good the rest wait with it rather than going out without it,
+    // e.g. updates to something whose create was rejected
+    fn fail_later_changes(&mut self, index: usize) {
+        let Some(target) = OperationTarget::of(&self.operations[index]) else {
+            return;
+        };
+        for operation in self.operations[index + 1..].iter_mut().filter(|op| {
+            op.is_pending()
+                && OperationTarget::of(op).is_some_and(|t| t.matches(target.kind, &target.id))
+        }) {
+            operation.wait_on_failed();
+        }
+    }
+
+    // Whether a change to the entity `operation` writes to has failed for good
+    fn has_failed_change_to(&self, operation: &PendingOperation) -> bool {
+        let Some(target) = OperationTarget::of(operation) else {
+            return false;
+        };
+        self.operations.iter().any(|op| {
+            op.is_failed()
+                && OperationTarget::of(op).is_some_and(|t| t.matches(target.kind, &target.id))
+        })
+    }
+
+    // Operations are sent one at a time and in order, so a create always
+    // reaches the server before the updates that depend on it
+    fn next_due(&self, now: DateTime<Utc>) -> Option<&PendingOperation> {
+        if self.in_flight.is_some() {
+            return None;
+        }
+        self.operations
+            .iter()
+            .find(|op| op.is_pending())
+            .filter(|op| op.is_due(now))
+    }
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum OutboxEvent {
+    // Background sync events (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    OperationSynced(String, HttpResult<crux_http::Response<Vec<u8>>, HttpError>),
+
+    // Shell persistence
+    RestorePendingOperations(Vec<PendingOperation>),
+    RetryFailedOperations,
+    DiscardFailedOperations,
+}
+
+fn backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    Duration::seconds((BASE_BACKOFF_SECONDS << exponent).min(MAX_BACKOFF_SECONDS))
+}
+
+fn is_retryable(error: &HttpError) -> bool {
+    match error {
+        HttpError::Http { code, .. } => {
+            let status: u16 = (*code).into();
+            status >= 500 || status == 408 || status == 429
+        }
+        HttpError::Json(_) | HttpError::Url(_) => false,
+        _ => true,
+    }
+}
+
+// Only stale versions become conflicts, other 409s are ordinary failures
+fn is_conflict(error: &HttpError) -> bool {
+    matches!(
+        crate::app::app_error_from_http(error, "sync"),
+        AppError::VersionConflict(_)
+    )
+}
+
+// A delete refused because something still refers to the entity, which the
+// app removed without knowing everything the server does
+fn is_refused_delete(operation: &PendingOperation, error: &HttpError) -> bool {
+    operation.method == OperationMethod::Delete
+        && matches!(
+            crate::app::app_error_from_http(error, "sync"),
+            AppError::Conflict(_)
+        )
+}
+
+// Deleting something the server no longer has is as good as deleting it
+fn is_already_applied(operation: &PendingOperation, error: &HttpError) -> bool {
+    match error {
+        HttpError::Http { code, .. } => {
+            operation.method == OperationMethod::Delete && u16::from(*code) == 404
+        }
+        _ => false,
+    }
+}
+
+/// Queues a change for the server without sending anything yet
+pub fn enqueue(model: &mut Model, method: OperationMethod, path: String, body: Option<String>) {
+    let mut operation = PendingOperation {
+        id: model.ids.next_id(),
+        ..PendingOperation::new(method, path, body)
+    };
+    conflict::stamp_version(model, &mut operation);
+    if model.outbox.has_failed_change_to(&operation) {
+        operation.wait_on_failed();
+    }
+    model.outbox.operations.push(operation);
+}
+
+fn enqueue_json<B: Serialize>(model: &mut Model, method: OperationMethod, path: String, body: &B) {
+    match serde_json::to_string(body) {
+        Ok(json) => enqueue(model, method, path, Some(json)),
+        Err(e) => model.last_error = Some(format!("Failed to queue change: {e:?}")),
+    }
+}
+
+pub fn enqueue_post<B: Serialize>(model: &mut Model, path: String, body: &B) {
+    enqueue_json(model, OperationMethod::Post, path, body);
+}
+
+pub fn enqueue_put<B: Serialize>(model: &mut Model, path: String, body: &B) {
+    enqueue_json(model, OperationMethod::Put, path, body);
+}
+
+pub fn enqueue_delete(model: &mut Model, path: String) {
+    enqueue(model, OperationMethod::Delete, path, None);
+}
+
+/// Renders and sends the next due operation, if nothing is already in flight
+pub fn sync(model: &mut Model) -> Command<super::Effect, super::Event> {
+    sync_at(model, model.clock.now)
+}
+
+fn sync_at(model: &mut Model, now: DateTime<Utc>) -> Command<super::Effect, super::Event> {
+    // Changes wait in the queue while signed out
+    if !model.auth.is_signed_in() {
+        return crux_core::render::render();
+    }
+    let Some(operation) = model.outbox.next_due(now).cloned() else {
+        return crux_core::render::render();
+    };
+
+    let operation_id = operation.id.clone();
+    model.outbox.in_flight = Some(operation_id.clone());
+    Command::all(vec![
+        crux_core::render::render(),
+        crate::app::api_send(model, &operation, move |response| {
+            super::Event::Outbox(OutboxEvent::OperationSynced(operation_id, response))
+        }),
+    ])
+}
+
+// Returns true when the server's copy is needed: after a version conflict, or
+// to bring back what a refused delete removed
+fn record_result(
+    model: &mut Model,
+    operation_id: &str,
+    result: HttpResult<crux_http::Response<Vec<u8>>, HttpError>,
+    now: DateTime<Utc>,
+) -> bool {
+    if model.outbox.in_flight.as_deref() == Some(operation_id) {
+        model.outbox.in_flight = None;
+    }
+    let Some(index) = model
+        .outbox
+        .operations
+        .iter()
+        .position(|op| op.id == operation_id)
+    else {
+        return false;
+    };
+
+    match result {
+        HttpResult::Ok(_) => {
+            model.outbox.operations.remove(index);
+            false
+        }
+        HttpResult::Err(e) if is_already_applied(&model.outbox.operations[index], &e) => {
+            model.outbox.operations.remove(index);
+            false
+        }
+        HttpResult::Err(e) if is_refused_delete(&model.outbox.operations[index], &e) => {
+            // Retrying won't help, so the error is shown and the entity fetched back
+            model.outbox.operations.remove(index);
+            let error = crate::app::app_error_from_http(&e, "delete");
+            model.last_error = Some(error.to_string());
+            model.error = Some(error);
+            true
+        }
+        HttpResult::Err(e) if crate::app::auth::is_unauthorized(&e) => {
+            // Not the operation's fault, it is sent again after logging in
+            crate::app::auth::expire(model);
+            false
+        }
+        HttpResult::Err(e) => {
+            log::warn!("Pending operation {operation_id} failed: {e:?}");
+            model.outbox.operations[index].record_failure(&e, now);
+            if model.outbox.operations[index].is_failed() {
+                model.outbox.fail_later_changes(index);
+            }
+
+            let conflicted = is_conflict(&e);
+            if conflicted {
+                let operation = model.outbox.operations[index].clone();
+                conflict::record_rejected(model, &operation);
+            }
+            conflicted
+        }
+    }
+}
+
+pub fn handle_event(event: OutboxEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        OutboxEvent::OperationSynced(operation_id, result) => {
+            let now = model.clock.now;
+            if record_result(model, &operation_id, result, now) {
+                // Fetch the server's copy so the conflict can be resolved, or
+                // what the server refused to delete comes back
+                return Command::all(vec![sync(model), Command::event(super::Event::FetchAll)]);
+            }
+        }
+        OutboxEvent::RestorePendingOperations(operations) => {
+            // Restored operations were queued before anything added since launch
+            let queued = std::mem::take(&mut model.outbox.operations);
+            model.outbox.operations = operations;
+            for operation in queued {
+                if !model
+                    .outbox
+                    .operations
+                    .iter()
+                    .any(|op| op.id == operation.id)
+                {
+                    model.outbox.operations.push(operation);
+                }
+            }
+        }
+        OutboxEvent::RetryFailedOperations => {
+            for operation in model
+                .outbox
+                .operations
+                .iter_mut()
+                .filter(|op| op.is_failed())
+            {
+                operation.status = OperationStatus::Pending;
+                operation.attempts = 0;
+                operation.next_attempt_at = None;
+            }
+        }
+        OutboxEvent::DiscardFailedOperations => {
+            model.outbox.operations.retain(|op| !op.is_failed());
+        }
+    }
+
+    sync(model)
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn now() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2025-05-01T12:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[cfg(test)]
+fn http_error(status: u16) -> HttpError {
+    HttpError::Http {
+        code: status.try_into().unwrap(),
+        message: String::new(),
+        body: None,
+    }
+}
+
+#[test]
+fn test_operations_are_sent_one_at_a_time_in_order() {
+    let mut model = crate::app::create_signed_in_test_model();
+    enqueue_post(&mut model, "/api/goals".to_string(), &serde_json::json!({}));
+    enqueue_put(
+        &mut model,
+        "/api/goals/1".to_string(),
+        &serde_json::json!({}),
+    );
+    let first_id = model.outbox.operations[0].id.clone();
+
+    let _ = sync_at(&mut model, now());
+    assert_eq!(model.outbox.in_flight.as_deref(), Some(first_id.as_str()));
+
+    // Nothing else goes out while the first operation is in flight
+    let _ = sync_at(&mut model, now());
+    assert_eq!(model.outbox.in_flight.as_deref(), Some(first_id.as_str()));
+
+    record_result(
+        &mut model,
+        &first_id,
+        HttpResult::Err(http_error(503)),
+        now(),
+    );
+    assert!(model.outbox.in_flight.is_none());
+    assert_eq!(model.outbox.operations[0].id, first_id);
+    assert_eq!(model.outbox.pending_count(), 2);
+}
+
+#[test]
+fn test_failed_operation_backs_off_exponentially() {
+    let mut operation =
+        PendingOperation::new(OperationMethod::Put, "/api/goals/1".to_string(), None);
+
+    operation.record_failure(&http_error(503), now());
+    assert_eq!(
+        operation.next_attempt_at.as_deref(),
+        Some("2025-05-01T12:00:02+00:00")
+    );
+    operation.record_failure(&http_error(503), now());
+    assert_eq!(
+        operation.next_attempt_at.as_deref(),
+        Some("2025-05-01T12:00:04+00:00")
+    );
+    assert!(!operation.is_due(now()));
+    assert!(operation.is_due(now() + Duration::seconds(4)));
+
+    for _ in 2..MAX_ATTEMPTS {
+        operation.record_failure(&http_error(503), now());
+    }
+    assert!(operation.is_failed());
+    assert_eq!(backoff(20), Duration::seconds(MAX_BACKOFF_SECONDS));
+}
+
+#[test]
+fn test_rejected_operation_fails_without_retry() {
+    let mut model = crate::app::create_signed_in_test_model();
+    enqueue_post(&mut model, "/api/goals".to_string(), &serde_json::json!({}));
+    enqueue_delete(&mut model, "/api/goals/1".to_string());
+    let post_id = model.outbox.operations[0].id.clone();
+    let delete_id = model.outbox.operations[1].id.clone();
+
+    let _ = sync_at(&mut model, now());
+    record_result(
+        &mut model,
+        &post_id,
+        HttpResult::Err(http_error(400)),
+        now(),
+    );
+    assert_eq!(model.outbox.failed_count(), 1);
+
+    // The queue moves past failed operations
+    let _ = sync_at(&mut model, now());
+    assert_eq!(model.outbox.in_flight.as_deref(), Some(delete_id.as_str()));
+
+    // A delete of something already gone counts as done
+    record_result(
+        &mut model,
+        &delete_id,
+        HttpResult::Err(http_error(404)),
+        now(),
+    );
+    assert_eq!(model.outbox.pending_count(), 0);
+    assert_eq!(model.outbox.operations.len(), 1);
+}
+
+#[test]
+fn test_later_changes_fail_with_a_rejected_create() {
+    let mut model = crate::app::create_signed_in_test_model();
+    enqueue_post(
+        &mut model,
+        "/api/sessions".to_string(),
+        &serde_json::json!({ "id": "session-1" }),
+    );
+    enqueue_post(
+        &mut model,
+        "/api/sessions/session-1/studies".to_string(),
+        &serde_json::json!({}),
+    );
+    enqueue_put(
+        &mut model,
+        "/api/goals/goal-1".to_string(),
+        &serde_json::json!({}),
+    );
+    enqueue_post(
+        &mut model,
+        "/api/sessions/session-1/end".to_string(),
+        &serde_json::json!({}),
+    );
+    let create_id = model.outbox.operations[0].id.clone();
+    let goal_id = model.outbox.operations[2].id.clone();
+
+    let _ = sync_at(&mut model, now());
+    record_result(
+        &mut model,
+        &create_id,
+        HttpResult::Err(http_error(400)),
+        now(),
+    );
+    let failed: Vec<bool> = model
+        .outbox
+        .operations
+        .iter()
+        .map(PendingOperation::is_failed)
+        .collect();
+    assert_eq!(failed, vec![true, true, false, true]);
+
+    // So do changes queued after it failed
+    enqueue_delete(&mut model, "/api/sessions/session-1".to_string());
+    assert!(model.outbox.operations[4].is_failed());
+
+    // Only the goal's change still goes out
+    let _ = sync_at(&mut model, now());
+    assert_eq!(model.outbox.in_flight.as_deref(), Some(goal_id.as_str()));
+}
+
+#[test]
+fn test_only_version_conflicts_are_sync_conflicts() {
+    let with_code = |code: &str| HttpError::Http {
+        code: 409.try_into().unwrap(),
+        message: String::new(),
+        body: Some(format!(r#"{{"code":"{code}","message":"Rejected"}}"#).into_bytes()),
+    };
+
+    assert!(is_conflict(&with_code("version_conflict")));
+    assert!(!is_conflict(&with_code("conflict")));
+    // Servers that predate error codes only send the status
+    assert!(is_conflict(&http_error(409)));
+}
+
+#[test]
+fn test_refused_delete_is_dropped_and_reported() {
+    let mut model = crate::app::create_signed_in_test_model();
+    enqueue_delete(
+        &mut model,
+        "/api/goals/1?mode=block_if_referenced".to_string(),
+    );
+    let operation_id = model.outbox.operations[0].id.clone();
+
+    let _ = sync_at(&mut model, now());
+    let refetch = record_result(
+        &mut model,
+        &operation_id,
+        HttpResult::Err(HttpError::Http {
+            code: 409.try_into().unwrap(),
+            message: String::new(),
+            body: Some(br#"{"code":"conflict","message":"Goal is used by 2 sessions"}"#.to_vec()),
+        }),
+        now(),
+    );
+
+    assert!(refetch);
+    assert!(model.outbox.operations.is_empty());
+    assert_eq!(
+        model.error,
+        Some(AppError::Conflict("Goal is used by 2 sessions".to_string()))
+    );
+}
+
+#[test]
+fn test_operations_wait_while_signed_out() {
+    let mut model = crate::app::create_signed_in_test_model();
+    enqueue_delete(&mut model, "/api/goals/1".to_string());
+    let operation_id = model.outbox.operations[0].id.clone();
+
+    let _ = sync_at(&mut model, now());
+    record_result(
+        &mut model,
+        &operation_id,
+        HttpResult::Err(http_error(401)),
+        now(),
+    );
+
+    // The expired token is dropped and the operation kept as it was
+    assert!(!model.auth.is_signed_in());
+    assert_eq!(model.outbox.operations[0].attempts, 0);
+    let _ = sync_at(&mut model, now());
+    assert!(model.outbox.in_flight.is_none());
+}
+
+#[test]
+fn test_restore_and_retry_failed_operations() {
+    let mut model = Model::default();
+    enqueue_delete(&mut model, "/api/goals/2".to_string());
+
+    let mut restored =
+        PendingOperation::new(OperationMethod::Delete, "/api/goals/1".to_string(), None);
+    restored.status = OperationStatus::Failed;
+    restored.attempts = MAX_ATTEMPTS;
+    let _ = handle_event(
+        OutboxEvent::RestorePendingOperations(vec![restored.clone()]),
+        &mut model,
+    );
+
+    // Restored operations go ahead of the ones queued since launch
+    assert_eq!(model.outbox.operations.len(), 2);
+    assert_eq!(model.outbox.operations[0].id, restored.id);
+    assert_eq!(model.outbox.failed_count(), 1);
+
+    let _ = handle_event(OutboxEvent::RetryFailedOperations, &mut model);
+    assert_eq!(model.outbox.failed_count(), 0);
+    assert_eq!(model.outbox.operations[0].attempts, 0);
+}
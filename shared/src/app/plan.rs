@@ -0,0 +1,508 @@
+use crate::app::model::Model;
+use crate::app::outbox;
+use crate::app::session::{enqueue_create_session, PracticeSession, SessionState};
+use crate::app::study_session::StudySession;
+use crate::app::utils::derived_id;
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+// Sessions are scheduled this far ahead, and occurrences are reported this
+// far either side of now
+const SCHEDULE_DAYS: i64 = 7;
+
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    fn of(day: NaiveDate) -> Self {
+        match day.weekday() {
+            chrono::Weekday::Mon => Weekday::Monday,
+            chrono::Weekday::Tue => Weekday::Tuesday,
+            chrono::Weekday::Wed => Weekday::Wednesday,
+            chrono::Weekday::Thu => Weekday::Thursday,
+            chrono::Weekday::Fri => Weekday::Friday,
+            chrono::Weekday::Sat => Weekday::Saturday,
+            chrono::Weekday::Sun => Weekday::Sunday,
+        }
+    }
+}
+
+/// When a plan's sessions happen: the same time on some days of every week.
+/// The time and days are local to `time_zone`, so a plan stays at 18:30 when
+/// the clocks change.
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct Recurrence {
+    pub weekdays: Vec<Weekday>,
+    pub time_of_day: String, // "18:30"
+    // IANA name, e.g. "Europe/London"
+    #[serde(default = "default_time_zone")]
+    pub time_zone: String,
+}
+
+fn default_time_zone() -> String {
+    "UTC".to_string()
+}
+
+impl Recurrence {
+    /// The time zone, None when `time_zone` isn't a known IANA name
+    pub fn zone(&self) -> Option<Tz> {
+        self.time_zone.parse().ok()
+    }
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct PracticePlan {
+    pub id: String,
+    pub name: String,
+    pub recurrence: Recurrence,
+    // Given to every session the plan schedules
+    pub intention: String,
+    pub goal_ids: Vec<String>,
+    pub study_ids: Vec<String>,
+    pub planned_minutes: u32,
+    // Occurrences fall from start_date up to and including end_date
+    pub start_date: String,
+    #[serde(default)]
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum PlanEvent {
+    // Optimistic user actions (all immediate, sync in background)
+    CreatePlan(PracticePlan),
+    UpdatePlan(PracticePlan),
+    RemovePlan(String),
+    // Creates NotStarted sessions for the coming week's occurrences (timestamp)
+    ScheduleSessions(String),
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub enum OccurrenceStatus {
+    #[default]
+    Upcoming,
+    InProgress,
+    Completed,
+    // The planned time went by without the session being started
+    Missed,
+}
+
+/// One scheduled time of a plan and how it went
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct PlanOccurrence {
+    pub plan_id: String,
+    pub scheduled_for: String,
+    pub session_id: Option<String>,
+    pub status: OccurrenceStatus,
+}
+
+impl PracticePlan {
+    pub fn new(
+        name: String,
+        recurrence: Recurrence,
+        intention: String,
+        planned_minutes: u32,
+        start_date: String,
+    ) -> Self {
+        Self {
+            id: crate::app::generate_id(),
+            name,
+            recurrence,
+            intention,
+            goal_ids: Vec::new(),
+            study_ids: Vec::new(),
+            planned_minutes,
+            start_date,
+            end_date: None,
+            version: 1,
+            updated_at: None,
+        }
+    }
+
+    /// Start times of the plan's occurrences in [from, to)
+    pub fn occurrences(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+        let date = |d: &str| NaiveDate::parse_from_str(d.get(..10)?, "%Y-%m-%d").ok();
+        let (Some(start_date), Ok(time), Some(zone)) = (
+            date(&self.start_date),
+            NaiveTime::parse_from_str(&self.recurrence.time_of_day, "%H:%M"),
+            self.recurrence.zone(),
+        ) else {
+            return Vec::new();
+        };
+        let end_date = self.end_date.as_deref().and_then(date);
+
+        let mut occurrences = Vec::new();
+        let mut day = from.with_timezone(&zone).date_naive().max(start_date);
+        while day <= to.with_timezone(&zone).date_naive() && end_date.map_or(true, |end| day <= end)
+        {
+            if self.recurrence.weekdays.contains(&Weekday::of(day)) {
+                if let Some(at) = local_instant(zone, day.and_time(time)) {
+                    if at >= from && at < to {
+                        occurrences.push(at);
+                    }
+                }
+            }
+            day += Duration::days(1);
+        }
+        occurrences
+    }
+
+    /// A NotStarted session for the occurrence at `scheduled_for`. Its ids
+    /// come from the plan and the occurrence, so every device schedules the
+    /// same session.
+    pub fn session_for(&self, scheduled_for: DateTime<Utc>) -> PracticeSession {
+        let scheduled_for = format_occurrence(scheduled_for);
+        let mut session = PracticeSession {
+            id: derived_id(&format!("plan/{}/{scheduled_for}", self.id)),
+            ..PracticeSession::new(self.goal_ids.clone(), self.intention.clone())
+        };
+        session.plan_id = Some(self.id.clone());
+        session.scheduled_for = Some(scheduled_for);
+        for study_id in &self.study_ids {
+            let study_session = StudySession {
+                id: derived_id(&format!("session/{}/{study_id}", session.id)),
+                ..StudySession::new(study_id.clone(), session.id.clone())
+            };
+            session.push_study_session(study_session);
+        }
+        session
+    }
+}
+
+// When a local time happens: the first time when the clocks go back over it,
+// and an hour later when they go forward over it
+pub(crate) fn local_instant(zone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    zone.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            zone.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+}
+
+fn format_occurrence(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn parse_instant(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+// The session scheduled for a plan's occurrence, if there is one
+fn scheduled_session<'a>(
+    sessions: &'a [PracticeSession],
+    plan_id: &str,
+    at: DateTime<Utc>,
+) -> Option<&'a PracticeSession> {
+    sessions.iter().find(|s| {
+        s.plan_id.as_deref() == Some(plan_id)
+            && s.scheduled_for.as_deref().and_then(parse_instant) == Some(at)
+    })
+}
+
+/// Every plan's occurrences from a week ago to a week ahead, oldest first
+pub fn plan_occurrences(
+    plans: &[PracticePlan],
+    sessions: &[PracticeSession],
+    now: DateTime<Utc>,
+) -> Vec<PlanOccurrence> {
+    let mut occurrences: Vec<(DateTime<Utc>, PlanOccurrence)> = plans
+        .iter()
+        .flat_map(|plan| {
+            plan.occurrences(
+                now - Duration::days(SCHEDULE_DAYS),
+                now + Duration::days(SCHEDULE_DAYS),
+            )
+            .into_iter()
+            .map(move |at| {
+                let session = scheduled_session(sessions, &plan.id, at);
+                let planned_end = at + Duration::minutes(i64::from(plan.planned_minutes));
+                let status = match session.map(|s| &s.state) {
+                    Some(SessionState::Ended { .. } | SessionState::PendingReflection { .. }) => {
+                        OccurrenceStatus::Completed
+                    }
+                    Some(SessionState::Started { .. } | SessionState::Paused { .. }) => {
+                        OccurrenceStatus::InProgress
+                    }
+                    Some(SessionState::NotStarted) | None if planned_end <= now => {
+                        OccurrenceStatus::Missed
+                    }
+                    Some(SessionState::NotStarted) | None => OccurrenceStatus::Upcoming,
+                };
+                (
+                    at,
+                    PlanOccurrence {
+                        plan_id: plan.id.clone(),
+                        scheduled_for: format_occurrence(at),
+                        session_id: session.map(|s| s.id.clone()),
+                        status,
+                    },
+                )
+            })
+        })
+        .collect();
+
+    occurrences.sort_by_key(|occurrence| occurrence.0);
+    occurrences
+        .into_iter()
+        .map(|(_, occurrence)| occurrence)
+        .collect()
+}
+
+/// Creates sessions for the occurrences in the week from `now` that don't
+/// have one yet. Returns the new sessions so they can be sent to the server.
+pub fn schedule_sessions(model: &mut Model, now: DateTime<Utc>) -> Vec<PracticeSession> {
+    let scheduled: Vec<PracticeSession> = model
+        .plans
+        .iter()
+        .flat_map(|plan| {
+            plan.occurrences(now, now + Duration::days(SCHEDULE_DAYS))
+                .into_iter()
+                .filter(|at| scheduled_session(&model.sessions, &plan.id, *at).is_none())
+                .map(|at| plan.session_for(at))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    model.sessions.extend(scheduled.iter().cloned());
+    scheduled
+}
+
+fn plan_request(plan: &PracticePlan) -> serde_json::Value {
+    serde_json::json!({
+        "id": plan.id,
+        "name": plan.name,
+        "recurrence": plan.recurrence,
+        "intention": plan.intention,
+        "goal_ids": plan.goal_ids,
+        "study_ids": plan.study_ids,
+        "planned_minutes": plan.planned_minutes,
+        "start_date": plan.start_date,
+        "end_date": plan.end_date,
+        "version": plan.version
+    })
+}
+
+pub fn handle_event(event: PlanEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        // Optimistic user actions (all immediate, sync in background)
+        PlanEvent::CreatePlan(plan) => {
+            // Apply immediately to local model
+            model.plans.push(plan.clone());
+
+            // Queue background sync
+            outbox::enqueue_post(model, "/api/plans".to_string(), &plan_request(&plan));
+        }
+        PlanEvent::UpdatePlan(plan) => {
+            // Apply immediately to local model
+            if let Some(existing) = model.plans.iter_mut().find(|p| p.id == plan.id) {
+                *existing = plan.clone();
+            }
+
+            // Queue background sync
+            outbox::enqueue_put(
+                model,
+                format!("/api/plans/{}", plan.id),
+                &plan_request(&plan),
+            );
+        }
+        PlanEvent::RemovePlan(plan_id) => {
+            // Apply immediately to local model, sessions it scheduled are kept
+            model.plans.retain(|p| p.id != plan_id);
+            for session in &mut model.sessions {
+                if session.plan_id.as_deref() == Some(plan_id.as_str()) {
+                    session.plan_id = None;
+                }
+            }
+
+            // Queue background sync
+            outbox::enqueue_delete(model, format!("/api/plans/{plan_id}"));
+        }
+        PlanEvent::ScheduleSessions(timestamp) => {
+            let Some(now) = parse_instant(&timestamp) else {
+                model.last_error = Some(format!(
+                    "Failed to schedule sessions: invalid time {timestamp}"
+                ));
+                return crux_core::render::render();
+            };
+            for session in schedule_sessions(model, now) {
+                enqueue_create_session(model, &session);
+            }
+        }
+    }
+
+    outbox::sync(model)
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn evening_plan() -> PracticePlan {
+    let mut plan = PracticePlan::new(
+        "Evenings".to_string(),
+        Recurrence {
+            weekdays: vec![Weekday::Monday, Weekday::Wednesday],
+            time_of_day: "18:30".to_string(),
+            time_zone: "UTC".to_string(),
+        },
+        "Scales then repertoire".to_string(),
+        30,
+        "2025-04-28".to_string(),
+    );
+    plan.study_ids = vec!["hanon".to_string()];
+    plan
+}
+
+#[test]
+fn test_occurrences_follow_recurrence() {
+    let mut plan = evening_plan();
+    plan.end_date = Some("2025-05-07".to_string());
+
+    let occurrences = plan.occurrences(
+        crate::app::test_time("2025-04-20T00:00:00Z"),
+        crate::app::test_time("2025-05-15T00:00:00Z"),
+    );
+    let times: Vec<String> = occurrences.into_iter().map(format_occurrence).collect();
+    assert_eq!(
+        times,
+        vec![
+            "2025-04-28T18:30:00Z",
+            "2025-04-30T18:30:00Z",
+            "2025-05-05T18:30:00Z",
+            "2025-05-07T18:30:00Z",
+        ]
+    );
+}
+
+#[test]
+fn test_occurrences_keep_local_time_across_clock_changes() {
+    let mut plan = evening_plan();
+    plan.recurrence.time_zone = "Europe/London".to_string();
+    plan.recurrence.weekdays = vec![Weekday::Sunday];
+    plan.start_date = "2025-03-01".to_string();
+
+    // The clocks went forward on 30 March
+    let occurrences = plan.occurrences(
+        crate::app::test_time("2025-03-22T00:00:00Z"),
+        crate::app::test_time("2025-04-01T00:00:00Z"),
+    );
+    let times: Vec<String> = occurrences.into_iter().map(format_occurrence).collect();
+    assert_eq!(times, vec!["2025-03-23T18:30:00Z", "2025-03-30T17:30:00Z"]);
+
+    // A time the clocks skip happens an hour later
+    plan.recurrence.time_of_day = "01:30".to_string();
+    let occurrences = plan.occurrences(
+        crate::app::test_time("2025-03-30T00:00:00Z"),
+        crate::app::test_time("2025-03-31T00:00:00Z"),
+    );
+    assert_eq!(
+        occurrences,
+        vec![crate::app::test_time("2025-03-30T01:30:00Z")]
+    );
+}
+
+#[test]
+fn test_scheduling_creates_sessions_once() {
+    let mut model = crate::app::create_signed_in_test_model();
+    model.sessions.clear();
+    model.plans.push(evening_plan());
+
+    let _ = handle_event(
+        PlanEvent::ScheduleSessions("2025-05-01T09:00:00Z".to_string()),
+        &mut model,
+    );
+    let scheduled: Vec<&str> = model
+        .sessions
+        .iter()
+        .filter_map(|s| s.scheduled_for.as_deref())
+        .collect();
+    assert_eq!(
+        scheduled,
+        vec!["2025-05-05T18:30:00Z", "2025-05-07T18:30:00Z"]
+    );
+    assert_eq!(model.sessions[0].intention, "Scales then repertoire");
+    assert_eq!(model.sessions[0].study_sessions.len(), 1);
+    // Each session and its study session are sent
+    assert_eq!(model.outbox.operations.len(), 4);
+
+    let _ = handle_event(
+        PlanEvent::ScheduleSessions("2025-05-02T09:00:00Z".to_string()),
+        &mut model,
+    );
+    assert_eq!(model.sessions.len(), 2);
+}
+
+#[test]
+fn test_plan_occurrences_report_missed_and_completed() {
+    let plan = evening_plan();
+    let mut completed = plan.session_for(crate::app::test_time("2025-04-28T18:30:00Z"));
+    completed.state = SessionState::Ended {
+        start_time: "2025-04-28T18:35:00Z".to_string(),
+        end_time: "2025-04-28T19:05:00Z".to_string(),
+        duration_in_seconds: 30 * 60,
+    };
+    let upcoming = plan.session_for(crate::app::test_time("2025-05-05T18:30:00Z"));
+
+    let occurrences = plan_occurrences(
+        &[plan],
+        &[completed.clone(), upcoming.clone()],
+        crate::app::test_time("2025-05-01T09:00:00Z"),
+    );
+    let statuses: Vec<(&str, OccurrenceStatus)> = occurrences
+        .iter()
+        .map(|o| (o.scheduled_for.as_str(), o.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("2025-04-28T18:30:00Z", OccurrenceStatus::Completed),
+            ("2025-04-30T18:30:00Z", OccurrenceStatus::Missed),
+            ("2025-05-05T18:30:00Z", OccurrenceStatus::Upcoming),
+            ("2025-05-07T18:30:00Z", OccurrenceStatus::Upcoming),
+        ]
+    );
+    assert_eq!(occurrences[0].session_id, Some(completed.id));
+    assert_eq!(occurrences[2].session_id, Some(upcoming.id));
+    assert_eq!(occurrences[3].session_id, None);
+}
+
+#[test]
+fn test_devices_schedule_the_same_sessions() {
+    let plan = evening_plan();
+    let mut phone = crate::app::create_signed_in_test_model();
+    let mut laptop = crate::app::create_signed_in_test_model();
+    for model in [&mut phone, &mut laptop] {
+        model.sessions.clear();
+        model.plans.push(plan.clone());
+    }
+
+    let now = crate::app::test_time("2025-05-01T09:00:00Z");
+    let on_phone = schedule_sessions(&mut phone, now);
+    let on_laptop = schedule_sessions(&mut laptop, now);
+    assert_eq!(on_phone, on_laptop);
+    assert_ne!(on_phone[0].id, on_phone[1].id);
+}
@@ -1,6 +1,172 @@
 /// Repository pattern for data access operations
 use crate::app::model::Model;
-use crate::app::{PracticeGoal, PracticeSession, Study};
+use crate::app::{PracticeGoal, PracticeSession, Study, StudySession, TemplateBlock};
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// What removing a goal or study does to the entities that refer to it
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[repr(C)]
+#[serde(rename_all = "snake_case")]
+pub enum DeleteMode {
+    // Drop it from every session, goal, plan and template that lists it
+    #[default]
+    Detach,
+    // Keep it while anything still refers to it
+    BlockIfReferenced,
+}
+
+impl DeleteMode {
+    /// The DELETE path for `path`, asking the server to apply the same mode
+    pub fn delete_path(self, path: String) -> String {
+        match self {
+            DeleteMode::Detach => path,
+            DeleteMode::BlockIfReferenced => format!("{path}?mode=block_if_referenced"),
+        }
+    }
+}
+
+/// What refers to a goal or study, shown before it is removed
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct DeleteImpact {
+    pub id: String,
+    pub session_count: u32,
+    pub goal_count: u32,
+    pub plan_count: u32,
+    pub template_count: u32,
+    // e.g. "used by 14 sessions and 1 plan", None when nothing refers to it
+    pub summary: Option<String>,
+}
+
+impl DeleteImpact {
+    pub fn new(
+        id: String,
+        session_count: u32,
+        goal_count: u32,
+        plan_count: u32,
+        template_count: u32,
+    ) -> Self {
+        let parts: Vec<String> = [
+            (session_count, "session"),
+            (goal_count, "goal"),
+            (plan_count, "plan"),
+            (template_count, "template"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, noun)| match count {
+            1 => format!("1 {noun}"),
+            _ => format!("{count} {noun}s"),
+        })
+        .collect();
+
+        let summary = match parts.split_last() {
+            None => None,
+            Some((last, [])) => Some(format!("used by {last}")),
+            Some((last, rest)) => Some(format!("used by {} and {last}", rest.join(", "))),
+        };
+        Self {
+            id,
+            session_count,
+            goal_count,
+            plan_count,
+            template_count,
+            summary,
+        }
+    }
+
+    pub fn is_referenced(&self) -> bool {
+        self.summary.is_some()
+    }
+}
+
+/// Where a Detach removal took a goal or study out of what listed it, so
+/// undoing the removal can put it back in the same places
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct DetachedReferences {
+    // (id of what listed it, position in that list)
+    pub sessions: Vec<(String, usize)>,
+    pub goals: Vec<(String, usize)>,
+    pub plans: Vec<(String, usize)>,
+    pub templates: Vec<(String, usize)>,
+    // A study's blocks and study sessions can't be left without it, so they
+    // went with it and are kept whole
+    pub template_blocks: Vec<(String, usize, TemplateBlock)>,
+    pub study_sessions: Vec<(String, usize, StudySession)>,
+}
+
+// Takes `id` out of `ids`, returning the position it was at
+fn take_id(ids: &mut Vec<String>, id: &str) -> Option<usize> {
+    let position = ids.iter().position(|i| i == id)?;
+    ids.retain(|i| i != id);
+    Some(position)
+}
+
+fn put_back_id(ids: &mut Vec<String>, id: &str, position: usize) {
+    if !ids.iter().any(|i| i == id) {
+        ids.insert(position.min(ids.len()), id.to_string());
+    }
+}
+
+// Takes the items `is_removed` picks out of `items`, along with their positions
+fn take_items<T>(items: &mut Vec<T>, is_removed: impl Fn(&T) -> bool) -> Vec<(usize, T)> {
+    let mut taken = Vec::new();
+    for (position, item) in std::mem::take(items).into_iter().enumerate() {
+        if is_removed(&item) {
+            taken.push((position, item));
+        } else {
+            items.push(item);
+        }
+    }
+    taken
+}
+
+fn count(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+/// The sessions, plans and templates that list a goal
+pub fn goal_impact(model: &Model, goal_id: &str) -> DeleteImpact {
+    let lists = |goal_ids: &Vec<String>| goal_ids.iter().any(|id| id == goal_id);
+    DeleteImpact::new(
+        goal_id.to_string(),
+        count(model.sessions.iter().filter(|s| lists(&s.goal_ids)).count()),
+        0,
+        count(model.plans.iter().filter(|p| lists(&p.goal_ids)).count()),
+        count(
+            model
+                .templates
+                .iter()
+                .filter(|t| lists(&t.goal_ids))
+                .count(),
+        ),
+    )
+}
+
+/// The sessions that practiced a study, and the goals, plans and templates
+/// that list it
+pub fn study_impact(model: &Model, study_id: &str) -> DeleteImpact {
+    let lists = |study_ids: &Vec<String>| study_ids.iter().any(|id| id == study_id);
+    DeleteImpact::new(
+        study_id.to_string(),
+        count(
+            model
+                .sessions
+                .iter()
+                .filter(|s| s.study_sessions.iter().any(|ss| ss.study_id == study_id))
+                .count(),
+        ),
+        count(model.goals.iter().filter(|g| lists(&g.study_ids)).count()),
+        count(model.plans.iter().filter(|p| lists(&p.study_ids)).count()),
+        count(
+            model
+                .templates
+                .iter()
+                .filter(|t| t.blocks.iter().any(|b| b.study_id == study_id))
+                .count(),
+        ),
+    )
+}
 
 /// Generic repository trait for common data operations
 pub trait Repository<T> {
@@ -58,6 +224,60 @@ impl<'a> GoalRepository<'a> {
             .filter(|goal| goal.study_ids.contains(&study_id.to_string()))
             .collect()
     }
+
+    /// Removes a goal along with every reference to it, returning where they
+    /// were, or leaves everything as it is and returns what refers to it when
+    /// `mode` blocks the removal
+    pub fn remove_with_references(
+        &mut self,
+        goal_id: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<(PracticeGoal, DetachedReferences)>, DeleteImpact> {
+        let impact = goal_impact(self.model, goal_id);
+        if mode == DeleteMode::BlockIfReferenced && impact.is_referenced() {
+            return Err(impact);
+        }
+
+        let model = &mut *self.model;
+        let mut references = DetachedReferences::default();
+        for session in &mut model.sessions {
+            if let Some(position) = take_id(&mut session.goal_ids, goal_id) {
+                references.sessions.push((session.id.clone(), position));
+            }
+        }
+        for plan in &mut model.plans {
+            if let Some(position) = take_id(&mut plan.goal_ids, goal_id) {
+                references.plans.push((plan.id.clone(), position));
+            }
+        }
+        for template in &mut model.templates {
+            if let Some(position) = take_id(&mut template.goal_ids, goal_id) {
+                references.templates.push((template.id.clone(), position));
+            }
+        }
+        Ok(self.remove(goal_id).map(|goal| (goal, references)))
+    }
+
+    /// Puts a goal back where a Detach removal took it from, in whatever of
+    /// that is still here
+    pub fn reattach(&mut self, goal_id: &str, references: &DetachedReferences) {
+        let model = &mut *self.model;
+        for (session_id, position) in &references.sessions {
+            if let Some(session) = model.sessions.iter_mut().find(|s| &s.id == session_id) {
+                put_back_id(&mut session.goal_ids, goal_id, *position);
+            }
+        }
+        for (plan_id, position) in &references.plans {
+            if let Some(plan) = model.plans.iter_mut().find(|p| &p.id == plan_id) {
+                put_back_id(&mut plan.goal_ids, goal_id, *position);
+            }
+        }
+        for (template_id, position) in &references.templates {
+            if let Some(template) = model.templates.iter_mut().find(|t| &t.id == template_id) {
+                put_back_id(&mut template.goal_ids, goal_id, *position);
+            }
+        }
+    }
 }
 
 impl<'a> Repository<PracticeGoal> for GoalRepository<'a> {
@@ -117,6 +337,102 @@ impl<'a> StudyRepository<'a> {
             .filter(|study| study.name.to_lowercase().contains(&pattern.to_lowercase()))
             .collect()
     }
+
+    /// Removes a study along with every reference to it, returning where they
+    /// were, or leaves everything as it is and returns what refers to it when
+    /// `mode` blocks the removal. A study session can't exist without its
+    /// study, so the time logged against it goes too.
+    pub fn remove_with_references(
+        &mut self,
+        study_id: &str,
+        mode: DeleteMode,
+    ) -> Result<Option<(Study, DetachedReferences)>, DeleteImpact> {
+        let impact = study_impact(self.model, study_id);
+        if mode == DeleteMode::BlockIfReferenced && impact.is_referenced() {
+            return Err(impact);
+        }
+
+        let model = &mut *self.model;
+        let mut references = DetachedReferences::default();
+        for session in &mut model.sessions {
+            let taken = take_items(&mut session.study_sessions, |s| s.study_id == study_id);
+            for (position, study_session) in taken {
+                references
+                    .study_sessions
+                    .push((session.id.clone(), position, study_session));
+            }
+            if let Some(active_id) = &session.active_study_session_id {
+                if !session.study_sessions.iter().any(|s| &s.id == active_id) {
+                    session.active_study_session_id = None;
+                }
+            }
+        }
+        for goal in &mut model.goals {
+            if let Some(position) = take_id(&mut goal.study_ids, study_id) {
+                references.goals.push((goal.id.clone(), position));
+            }
+        }
+        for plan in &mut model.plans {
+            if let Some(position) = take_id(&mut plan.study_ids, study_id) {
+                references.plans.push((plan.id.clone(), position));
+            }
+        }
+        for template in &mut model.templates {
+            for (position, block) in take_items(&mut template.blocks, |b| b.study_id == study_id) {
+                references
+                    .template_blocks
+                    .push((template.id.clone(), position, block));
+            }
+        }
+        Ok(self.remove(study_id).map(|study| (study, references)))
+    }
+
+    /// Puts a study back where a Detach removal took it from, with its study
+    /// sessions and template blocks, in whatever of that is still here
+    pub fn reattach(&mut self, study_id: &str, references: &DetachedReferences) {
+        let model = &mut *self.model;
+        for (session_id, position, study_session) in &references.study_sessions {
+            if let Some(session) = model.sessions.iter_mut().find(|s| &s.id == session_id) {
+                if !session
+                    .study_sessions
+                    .iter()
+                    .any(|s| s.id == study_session.id)
+                {
+                    let position = (*position).min(session.study_sessions.len());
+                    session
+                        .study_sessions
+                        .insert(position, study_session.clone());
+                }
+            }
+        }
+        for (goal_id, position) in &references.goals {
+            if let Some(goal) = model.goals.iter_mut().find(|g| &g.id == goal_id) {
+                put_back_id(&mut goal.study_ids, study_id, *position);
+            }
+        }
+        for (plan_id, position) in &references.plans {
+            if let Some(plan) = model.plans.iter_mut().find(|p| &p.id == plan_id) {
+                put_back_id(&mut plan.study_ids, study_id, *position);
+            }
+        }
+        // Taken in order, so putting them back in order restores the same
+        // positions. Templates that list the study again are left as they are.
+        let listing: Vec<String> = model
+            .templates
+            .iter()
+            .filter(|t| t.blocks.iter().any(|b| b.study_id == study_id))
+            .map(|t| t.id.clone())
+            .collect();
+        for (template_id, position, block) in &references.template_blocks {
+            if listing.contains(template_id) {
+                continue;
+            }
+            if let Some(template) = model.templates.iter_mut().find(|t| &t.id == template_id) {
+                let position = (*position).min(template.blocks.len());
+                template.blocks.insert(position, block.clone());
+            }
+        }
+    }
 }
 
 impl<'a> Repository<Study> for StudyRepository<'a> {
@@ -177,14 +493,14 @@ impl<'a> SessionRepository<'a> {
             .collect()
     }
 
-    /// Find the currently active session (should only be one)
+    /// Find the currently active (started or paused) session (should only be one)
     pub fn find_current_active(&self) -> Option<&PracticeSession> {
-        self.model.sessions.iter().find(|s| s.is_active())
+        self.model.sessions.iter().find(|s| s.is_in_progress())
     }
 
-    /// Find the currently active session mutably (should only be one)
+    /// Find the currently active (started or paused) session mutably (should only be one)
     pub fn find_current_active_mut(&mut self) -> Option<&mut PracticeSession> {
-        self.model.sessions.iter_mut().find(|s| s.is_active())
+        self.model.sessions.iter_mut().find(|s| s.is_in_progress())
     }
 
     /// Find not started sessions
@@ -364,4 +680,84 @@ mod tests {
         let no_sessions = repo.find_by_goal_id("nonexistent");
         assert_eq!(no_sessions.len(), 0);
     }
+
+    #[test]
+    fn test_delete_impact_summary() {
+        let unused = DeleteImpact::new("goal1".to_string(), 0, 0, 0, 0);
+        assert!(!unused.is_referenced());
+        assert_eq!(unused.summary, None);
+
+        let sessions = DeleteImpact::new("goal1".to_string(), 14, 0, 0, 0);
+        assert_eq!(sessions.summary.as_deref(), Some("used by 14 sessions"));
+
+        let mixed = DeleteImpact::new("study1".to_string(), 2, 1, 0, 3);
+        assert_eq!(
+            mixed.summary.as_deref(),
+            Some("used by 2 sessions, 1 goal and 3 templates")
+        );
+    }
+
+    #[test]
+    fn test_remove_goal_detaches_or_blocks() {
+        let mut model = create_test_model();
+        let goal = create_test_goal("Test Goal", None);
+        let goal_id = goal.id.clone();
+        model.goals.push(goal);
+        model.sessions.push(create_test_session(&[&goal_id], "One"));
+        model
+            .sessions
+            .push(create_test_session(&[&goal_id, "other"], "Two"));
+
+        let blocked = model
+            .goals()
+            .remove_with_references(&goal_id, DeleteMode::BlockIfReferenced)
+            .unwrap_err();
+        assert_eq!(blocked.session_count, 2);
+        assert_eq!(model.goals.len(), 1);
+        assert_eq!(model.sessions[0].goal_ids, vec![goal_id.clone()]);
+
+        let removed = model
+            .goals()
+            .remove_with_references(&goal_id, DeleteMode::Detach)
+            .unwrap();
+        assert!(removed.is_some());
+        assert!(model.goals.is_empty());
+        assert!(model.sessions[0].goal_ids.is_empty());
+        assert_eq!(model.sessions[1].goal_ids, vec!["other".to_string()]);
+    }
+
+    #[test]
+    fn test_remove_study_detaches_goals_and_study_sessions() {
+        let mut model = create_test_model();
+        let study = create_test_study("Scales", None);
+        let study_id = study.id.clone();
+        model.studies.push(study);
+        model.goals.push(create_custom_test_goal(
+            "Test Goal",
+            None,
+            crate::app::GoalStatus::NotStarted,
+            vec![study_id.clone(), "other".to_string()],
+            None,
+        ));
+        let mut session = create_started_test_session(&[], "Warm up");
+        let session_id = session.id.clone();
+        let study_session = create_test_study_session(&study_id, &session_id);
+        let study_session_id = study_session.id.clone();
+        session.study_sessions.push(study_session);
+        session.active_study_session_id = Some(study_session_id);
+        model.sessions.push(session);
+
+        let impact = study_impact(&model, &study_id);
+        assert_eq!((impact.session_count, impact.goal_count), (1, 1));
+
+        model
+            .studies()
+            .remove_with_references(&study_id, DeleteMode::Detach)
+            .unwrap();
+        assert!(model.studies.is_empty());
+        assert_eq!(model.goals[0].study_ids, vec!["other".to_string()]);
+        assert!(model.sessions[0].study_sessions.is_empty());
+        assert_eq!(model.sessions[0].active_study_session_id, None);
+        assert!(!study_impact(&model, &study_id).is_referenced());
+    }
 }
@@ -0,0 +1,99 @@
+use crate::app::{
+    create_deterministic_test_model, create_test_goal, create_test_session, GoalEvent,
+    PracticeGoal, PracticeSession, SessionEvent,
+};
+use crate::{Chopin, Effect, Event, Model};
+use crux_core::{App as _, Command};
+use crux_http::protocol::{HttpResponse, HttpResult};
+
+// Full scenarios run through `update`, with every request the core makes
+// answered by a server that accepts everything. Ids and times come from the
+// model's seeded generator and manual clock, so the snapshots are stable.
+
+/// Runs an event and everything it leads to. Returns the requests sent.
+fn run(app: &Chopin, model: &mut Model, event: Event) -> Vec<String> {
+    let mut sent = Vec::new();
+    let mut events = vec![event];
+    while !events.is_empty() {
+        let event = events.remove(0);
+        let mut command = app.update(event, model, &());
+        settle(&mut command, &mut sent);
+        events.extend(command.events());
+    }
+    sent
+}
+
+fn settle(command: &mut Command<Effect, Event>, sent: &mut Vec<String>) {
+    loop {
+        let effects: Vec<Effect> = command.effects().collect();
+        if effects.is_empty() {
+            return;
+        }
+        for effect in effects {
+            // Renders need no answer, and timers are left waiting like a
+            // shell that hasn't fired yet
+            if let Effect::Http(mut request) = effect {
+                let operation = &request.operation;
+                sent.push(format!("{} {}", operation.method, operation.url));
+                request
+                    .resolve(HttpResult::Ok(HttpResponse::ok().build()))
+                    .expect("request is still waiting");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_practice_scenario_snapshot() {
+    let app = Chopin;
+    let mut model = create_deterministic_test_model("2025-05-01T17:55:00Z");
+    let goal = PracticeGoal {
+        id: model.ids.next_id(),
+        ..create_test_goal("Even scales", Some("Two octaves at 90"))
+    };
+    let session = PracticeSession {
+        id: model.ids.next_id(),
+        ..create_test_session(&[&goal.id], "Even tone through the turn")
+    };
+    let session_id = session.id.clone();
+
+    let mut sent = run(&app, &mut model, Event::Goal(GoalEvent::CreateGoal(goal)));
+    sent.extend(run(
+        &app,
+        &mut model,
+        Event::Session(SessionEvent::CreateSession(session)),
+    ));
+    sent.extend(run(
+        &app,
+        &mut model,
+        Event::Session(SessionEvent::StartSession(
+            session_id.clone(),
+            "2025-05-01T18:00:00Z".to_string(),
+        )),
+    ));
+    sent.extend(run(
+        &app,
+        &mut model,
+        Event::Tick("2025-05-01T18:12:30Z".to_string()),
+    ));
+    insta::assert_yaml_snapshot!("practice_scenario_running", app.view(&model));
+
+    sent.extend(run(
+        &app,
+        &mut model,
+        Event::Session(SessionEvent::EndSession(
+            session_id.clone(),
+            "2025-05-01T18:30:00Z".to_string(),
+        )),
+    ));
+    sent.extend(run(
+        &app,
+        &mut model,
+        Event::Session(SessionEvent::CompleteWithNotes(
+            session_id,
+            "Turn is even at 90".to_string(),
+        )),
+    ));
+    insta::assert_yaml_snapshot!("practice_scenario_ended", app.view(&model));
+    insta::assert_yaml_snapshot!("practice_scenario_requests", sent);
+}
@@ -1,7 +1,10 @@
-use crate::app::error::SessionError;
+use crate::app::error::{SessionError, StudySessionError};
 use crate::app::model::Model;
+use crate::app::outbox;
 use crate::app::repository::Repository;
 use crate::app::study_session::StudySession;
+use crate::app::trash;
+use crate::app::{history, Event};
 use chrono::DateTime;
 use crux_core::Command;
 use facet::Facet;
@@ -17,6 +20,30 @@ pub struct PracticeSession {
     pub study_sessions: Vec<StudySession>,
     pub active_study_session_id: Option<String>,
     pub state: SessionState,
+    #[serde(default)]
+    pub pauses: Vec<PauseInterval>,
+    // The practice plan occurrence this session was scheduled for
+    #[serde(default)]
+    pub plan_id: Option<String>,
+    #[serde(default)]
+    pub scheduled_for: Option<String>,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    // Hidden from lists but kept until restored
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    // In the trash, from where the server purges it after a retention period
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+}
+
+/// A completed break within a session; excluded from the session duration
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PauseInterval {
+    pub paused_at: String,
+    pub resumed_at: String,
 }
 
 // Note: PracticeSessionView eliminated - using computed methods on PracticeSession directly
@@ -33,14 +60,15 @@ pub enum SessionEvent {
     SessionsSynced(
         crate::HttpResult<crux_http::Response<Vec<PracticeSession>>, crux_http::HttpError>,
     ),
-    #[serde(skip)]
-    #[facet(skip)]
-    SessionSynced(crate::HttpResult<crux_http::Response<PracticeSession>, crux_http::HttpError>),
 
     // Optimistic user actions (all immediate, sync in background)
     CreateSession(PracticeSession),
+    // Creates a NotStarted session from a template's intention, goals and blocks
+    CreateFromTemplate(String),
     UpdateSession(PracticeSession),
     StartSession(String, String),
+    PauseSession(String, String),
+    ResumeSession(String, String),
     EndSession(String, String),
     CompleteReflection(String),
     EditSessionFields {
@@ -52,6 +80,10 @@ pub enum SessionEvent {
     EditSessionNotes(String, String),
     CompleteWithNotes(String, String),
     RemoveSession(String),
+    // Hides the session from lists without removing it
+    ArchiveSession(String),
+    // Brings the session back from the archive or the trash
+    RestoreSession(String),
 }
 
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
@@ -62,6 +94,11 @@ pub enum SessionState {
     Started {
         start_time: String,
     },
+    Paused {
+        start_time: String,
+        paused_at: String,
+        accumulated_seconds: u32,
+    },
     PendingReflection {
         start_time: String,
         end_time: String,
@@ -73,6 +110,18 @@ pub enum SessionState {
     },
 }
 
+impl SessionState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionState::NotStarted => "NotStarted",
+            SessionState::Started { .. } => "Started",
+            SessionState::Paused { .. } => "Paused",
+            SessionState::PendingReflection { .. } => "PendingReflection",
+            SessionState::Ended { .. } => "Ended",
+        }
+    }
+}
+
 impl PracticeSession {
     pub fn new(goal_ids: Vec<String>, intention: String) -> Self {
         Self {
@@ -83,6 +132,13 @@ impl PracticeSession {
             study_sessions: Vec::new(),
             active_study_session_id: None,
             state: SessionState::NotStarted,
+            pauses: Vec::new(),
+            plan_id: None,
+            scheduled_for: None,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         }
     }
 
@@ -95,7 +151,60 @@ impl PracticeSession {
                 };
                 Ok(())
             }
-            SessionState::Started { .. } => Err(SessionError::AlreadyStarted),
+            SessionState::Started { .. } | SessionState::Paused { .. } => {
+                Err(SessionError::AlreadyStarted)
+            }
+            SessionState::PendingReflection { .. } | SessionState::Ended { .. } => {
+                Err(SessionError::AlreadyEnded)
+            }
+        }
+    }
+
+    pub fn pause(&mut self, timestamp: String) -> Result<(), SessionError> {
+        match &self.state {
+            SessionState::Started { start_time } => {
+                let accumulated_seconds =
+                    calculate_active_seconds(start_time, &timestamp, &self.pauses).unwrap_or(0);
+                self.state = SessionState::Paused {
+                    start_time: start_time.clone(),
+                    paused_at: timestamp.clone(),
+                    accumulated_seconds,
+                };
+                // The active study stays selected so it picks up again on resume
+                if let Some(study) = self.active_study_session_mut() {
+                    let _ = study.pause(timestamp);
+                }
+                Ok(())
+            }
+            SessionState::Paused { .. } => Err(SessionError::AlreadyPaused),
+            SessionState::NotStarted => Err(SessionError::NotActive),
+            SessionState::PendingReflection { .. } | SessionState::Ended { .. } => {
+                Err(SessionError::AlreadyEnded)
+            }
+        }
+    }
+
+    pub fn resume(&mut self, timestamp: String) -> Result<(), SessionError> {
+        match &self.state {
+            SessionState::Paused {
+                start_time,
+                paused_at,
+                ..
+            } => {
+                self.pauses.push(PauseInterval {
+                    paused_at: paused_at.clone(),
+                    resumed_at: timestamp.clone(),
+                });
+                self.state = SessionState::Started {
+                    start_time: start_time.clone(),
+                };
+                if let Some(study) = self.active_study_session_mut() {
+                    let _ = study.resume(timestamp);
+                }
+                Ok(())
+            }
+            SessionState::Started { .. } => Err(SessionError::NotPaused),
+            SessionState::NotStarted => Err(SessionError::NotActive),
             SessionState::PendingReflection { .. } | SessionState::Ended { .. } => {
                 Err(SessionError::AlreadyEnded)
             }
@@ -107,8 +216,27 @@ impl PracticeSession {
             SessionState::Started { start_time } => {
                 self.state = SessionState::PendingReflection {
                     start_time: start_time.clone(),
-                    end_time: timestamp,
+                    end_time: timestamp.clone(),
+                };
+                self.close_active_study(timestamp);
+                Ok(())
+            }
+            SessionState::Paused {
+                start_time,
+                paused_at,
+                ..
+            } => {
+                // Ending while paused closes the open break at the end time
+                let start_time = start_time.clone();
+                self.pauses.push(PauseInterval {
+                    paused_at: paused_at.clone(),
+                    resumed_at: timestamp.clone(),
+                });
+                self.state = SessionState::PendingReflection {
+                    start_time,
+                    end_time: timestamp.clone(),
                 };
+                self.close_active_study(timestamp);
                 Ok(())
             }
             SessionState::NotStarted => Err(SessionError::NotActive),
@@ -125,7 +253,7 @@ impl PracticeSession {
                 end_time,
             } => {
                 let duration_in_seconds =
-                    calculate_duration_in_seconds(start_time, end_time).unwrap_or(0);
+                    calculate_active_seconds(start_time, end_time, &self.pauses).unwrap_or(0);
                 self.state = SessionState::Ended {
                     start_time: start_time.clone(),
                     end_time: end_time.clone(),
@@ -133,7 +261,9 @@ impl PracticeSession {
                 };
                 Ok(())
             }
-            SessionState::NotStarted | SessionState::Started { .. } => Err(SessionError::NotActive),
+            SessionState::NotStarted
+            | SessionState::Started { .. }
+            | SessionState::Paused { .. } => Err(SessionError::NotActive),
             SessionState::Ended { .. } => Err(SessionError::AlreadyEnded),
         }
     }
@@ -143,6 +273,15 @@ impl PracticeSession {
         matches!(self.state, SessionState::Started { .. })
     }
 
+    pub fn is_paused(&self) -> bool {
+        matches!(self.state, SessionState::Paused { .. })
+    }
+
+    /// Started or paused - the session is still in play
+    pub fn is_in_progress(&self) -> bool {
+        self.is_active() || self.is_paused()
+    }
+
     pub fn is_ended(&self) -> bool {
         matches!(self.state, SessionState::Ended { .. })
     }
@@ -150,6 +289,7 @@ impl PracticeSession {
     pub fn start_time(&self) -> Option<&str> {
         match &self.state {
             SessionState::Started { start_time }
+            | SessionState::Paused { start_time, .. }
             | SessionState::PendingReflection { start_time, .. }
             | SessionState::Ended { start_time, .. } => Some(start_time),
             SessionState::NotStarted => None,
@@ -160,41 +300,41 @@ impl PracticeSession {
         match &self.state {
             SessionState::PendingReflection { end_time, .. }
             | SessionState::Ended { end_time, .. } => Some(end_time),
-            SessionState::NotStarted | SessionState::Started { .. } => None,
+            SessionState::NotStarted
+            | SessionState::Started { .. }
+            | SessionState::Paused { .. } => None,
         }
     }
 
     pub fn duration(&self) -> Option<String> {
-        match &self.state {
-            SessionState::PendingReflection {
-                start_time,
-                end_time,
-            } => {
-                let seconds = calculate_duration_in_seconds(start_time, end_time)?;
-                Some(format_duration_from_seconds(seconds))
-            }
-            SessionState::Ended {
-                duration_in_seconds,
-                ..
-            } => Some(format_duration_from_seconds(*duration_in_seconds)),
-            SessionState::NotStarted | SessionState::Started { .. } => None,
-        }
+        self.duration_in_seconds().map(format_duration_from_seconds)
     }
 
+    /// Practiced time in seconds, excluding any paused intervals
     pub fn duration_in_seconds(&self) -> Option<u32> {
         match &self.state {
             SessionState::PendingReflection {
                 start_time,
                 end_time,
-            } => calculate_duration_in_seconds(start_time, end_time),
+            } => calculate_active_seconds(start_time, end_time, &self.pauses),
             SessionState::Ended {
                 duration_in_seconds,
                 ..
             } => Some(*duration_in_seconds),
-            SessionState::NotStarted | SessionState::Started { .. } => None,
+            SessionState::NotStarted
+            | SessionState::Started { .. }
+            | SessionState::Paused { .. } => None,
         }
     }
 
+    /// Total seconds spent in completed pauses
+    pub fn paused_seconds(&self) -> u32 {
+        self.pauses
+            .iter()
+            .filter_map(|p| calculate_duration_in_seconds(&p.paused_at, &p.resumed_at))
+            .sum()
+    }
+
     // Mutator: push a StudySession
     pub fn push_study_session(&mut self, session: StudySession) {
         self.study_sessions.push(session);
@@ -206,6 +346,102 @@ impl PracticeSession {
             *existing = session;
         }
     }
+
+    pub fn active_study_session(&self) -> Option<&StudySession> {
+        let active_id = self.active_study_session_id.as_deref()?;
+        self.study_sessions.iter().find(|s| s.id == active_id)
+    }
+
+    fn active_study_session_mut(&mut self) -> Option<&mut StudySession> {
+        let active_id = self.active_study_session_id.clone()?;
+        self.study_sessions.iter_mut().find(|s| s.id == active_id)
+    }
+
+    /// Starts (or resumes) a study, auto-pausing whichever study was active before
+    pub fn start_study(
+        &mut self,
+        study_session_id: &str,
+        timestamp: String,
+    ) -> Result<(), StudySessionError> {
+        if !self.is_active() {
+            return Err(StudySessionError::SessionNotActive);
+        }
+        if !self.study_sessions.iter().any(|s| s.id == study_session_id) {
+            return Err(StudySessionError::NotFound);
+        }
+
+        if self.active_study_session_id.as_deref() != Some(study_session_id) {
+            if let Some(previous) = self.active_study_session_mut() {
+                if previous.is_active() {
+                    previous.pause(timestamp.clone())?;
+                }
+            }
+        }
+
+        let study = self
+            .study_sessions
+            .iter_mut()
+            .find(|s| s.id == study_session_id)
+            .ok_or(StudySessionError::NotFound)?;
+        study.start(timestamp)?;
+        self.active_study_session_id = Some(study_session_id.to_string());
+        Ok(())
+    }
+
+    pub fn pause_study(
+        &mut self,
+        study_session_id: &str,
+        timestamp: String,
+    ) -> Result<(), StudySessionError> {
+        let study = self
+            .study_sessions
+            .iter_mut()
+            .find(|s| s.id == study_session_id)
+            .ok_or(StudySessionError::NotFound)?;
+        study.pause(timestamp)?;
+        if self.active_study_session_id.as_deref() == Some(study_session_id) {
+            self.active_study_session_id = None;
+        }
+        Ok(())
+    }
+
+    pub fn complete_study(
+        &mut self,
+        study_session_id: &str,
+        timestamp: String,
+    ) -> Result<(), StudySessionError> {
+        let study = self
+            .study_sessions
+            .iter_mut()
+            .find(|s| s.id == study_session_id)
+            .ok_or(StudySessionError::NotFound)?;
+        study.complete(timestamp)?;
+        if self.active_study_session_id.as_deref() == Some(study_session_id) {
+            self.active_study_session_id = None;
+        }
+        Ok(())
+    }
+
+    // Ending the session completes whichever study was in progress
+    fn close_active_study(&mut self, timestamp: String) {
+        if let Some(study) = self.active_study_session_mut() {
+            let _ = study.complete(timestamp);
+        }
+        self.active_study_session_id = None;
+    }
+
+    /// Seconds spent per study as of `timestamp` (study_id, seconds), in first-worked order
+    pub fn time_per_study(&self, timestamp: &str) -> Vec<(String, u32)> {
+        let mut totals: Vec<(String, u32)> = Vec::new();
+        for study in &self.study_sessions {
+            let seconds = study.elapsed_seconds_at(timestamp);
+            match totals.iter_mut().find(|(id, _)| *id == study.study_id) {
+                Some((_, total)) => *total += seconds,
+                None => totals.push((study.study_id.clone(), seconds)),
+            }
+        }
+        totals
+    }
 }
 
 // Public duration calculation function for use by viewmodel
@@ -222,6 +458,20 @@ pub fn calculate_duration_in_seconds(start_time: &str, end_time: &str) -> Option
     }
 }
 
+/// Wall-clock time between start and end minus the time spent in pauses
+pub fn calculate_active_seconds(
+    start_time: &str,
+    end_time: &str,
+    pauses: &[PauseInterval],
+) -> Option<u32> {
+    let total = calculate_duration_in_seconds(start_time, end_time)?;
+    let paused: u32 = pauses
+        .iter()
+        .filter_map(|p| calculate_duration_in_seconds(&p.paused_at, &p.resumed_at))
+        .sum();
+    Some(total.saturating_sub(paused))
+}
+
 fn format_duration_from_seconds(seconds: u32) -> String {
     let minutes = (seconds as f64 / 60.0).round() as u32;
     format!("{minutes}m")
@@ -238,7 +488,7 @@ pub fn start_session(
     let mut repo = model.sessions();
 
     // Ensure only one session can be started at a time
-    // If there's already a started session, end it first
+    // If there's already a started (or paused) session, end it first
     if let Some(current_started) = repo.find_current_active_mut() {
         let current_id = current_started.id.clone();
         if current_id != session_id {
@@ -257,6 +507,34 @@ pub fn start_session(
     }
 }
 
+pub fn pause_session(
+    session_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), SessionError> {
+    let mut repo = model.sessions();
+    if let Some(session) = repo.find_mut_by_id(session_id) {
+        session.pause(timestamp)?;
+        Ok(())
+    } else {
+        Err(SessionError::NotFound)
+    }
+}
+
+pub fn resume_session(
+    session_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), SessionError> {
+    let mut repo = model.sessions();
+    if let Some(session) = repo.find_mut_by_id(session_id) {
+        session.resume(timestamp)?;
+        Ok(())
+    } else {
+        Err(SessionError::NotFound)
+    }
+}
+
 pub fn end_session(
     session_id: &str,
     timestamp: String,
@@ -303,6 +581,26 @@ pub fn edit_session_fields(
     }
 }
 
+/// Queues creating a session on the server, along with its study sessions
+pub fn enqueue_create_session(model: &mut Model, session: &PracticeSession) {
+    let create_request = serde_json::json!({
+        "id": session.id,
+        "goal_ids": session.goal_ids,
+        "intention": session.intention,
+        "notes": session.notes,
+        "plan_id": session.plan_id,
+        "scheduled_for": session.scheduled_for
+    });
+    outbox::enqueue_post(model, "/api/sessions".to_string(), &create_request);
+    for study_session in &session.study_sessions {
+        outbox::enqueue_post(
+            model,
+            format!("/api/sessions/{}/studies", session.id),
+            study_session,
+        );
+    }
+}
+
 pub fn handle_event(
     event: SessionEvent,
     model: &mut Model,
@@ -310,7 +608,7 @@ pub fn handle_event(
     match event {
         // Background sync events (internal only)
         SessionEvent::SyncSessions => {
-            return crate::app::api_get("/api/sessions", |response| {
+            return crate::app::api_get(model, "/api/sessions", |response| {
                 super::Event::Session(SessionEvent::SessionsSynced(response))
             });
         }
@@ -323,55 +621,118 @@ pub fn handle_event(
             // Silently fail background sync - user doesn't need to know
             // Could add sync status to model if we want to show sync state later
         }
-        SessionEvent::SessionSynced(crate::HttpResult::Ok(_response)) => {
-            // Individual session synced successfully - nothing to do
-        }
-        SessionEvent::SessionSynced(crate::HttpResult::Err(_e)) => {
-            // Individual session sync failed - could retry or show status
-        }
 
         // Optimistic user actions (all immediate, sync in background)
         SessionEvent::CreateSession(session) => {
             // Apply immediately to local model
             model.sessions().add(session.clone());
+            record_create(&session.id, model);
 
-            // Trigger background sync
-            let create_request = serde_json::json!({
-                "goal_ids": session.goal_ids,
-                "intention": session.intention,
-                "notes": session.notes
-            });
-            return crate::app::api_post("/api/sessions", &create_request, |response| {
-                super::Event::Session(SessionEvent::SessionSynced(response))
-            });
+            // Queue background sync
+            enqueue_create_session(model, &session);
+            return outbox::sync(model);
+        }
+        SessionEvent::CreateFromTemplate(template_id) => {
+            let Some(template) = model.templates.iter().find(|t| t.id == template_id) else {
+                model.last_error = Some(format!(
+                    "Failed to create session: template {template_id} not found"
+                ));
+                return crux_core::render::render();
+            };
+            let session = template.session(&mut model.ids);
+
+            // Apply immediately to local model
+            model.sessions().add(session.clone());
+            record_create(&session.id, model);
+
+            // Queue background sync
+            enqueue_create_session(model, &session);
+            return outbox::sync(model);
         }
         SessionEvent::UpdateSession(session) => {
             // Apply immediately to local model
+            record_edit(&session.id, edit_fields_event(&session), model);
             if let Some(existing) = model.sessions.iter_mut().find(|s| s.id == session.id) {
                 *existing = session.clone();
             }
 
-            // Trigger background sync
-            return crate::app::api_put(
-                &format!("/api/sessions/{}", session.id),
-                &session,
-                |response| super::Event::Session(SessionEvent::SessionSynced(response)),
-            );
+            // Queue background sync
+            outbox::enqueue_put(model, format!("/api/sessions/{}", session.id), &session);
+            return outbox::sync(model);
         }
         SessionEvent::StartSession(session_id, timestamp) => {
+            // Starting ends whichever session was running, which the server
+            // has to hear about first as it only allows one at a time
+            let interrupted_id = model
+                .sessions
+                .iter()
+                .find(|s| s.is_in_progress() && s.id != session_id)
+                .map(|s| s.id.clone());
+
             // Apply optimistically to local model
             if let Err(e) = start_session(&session_id, timestamp.clone(), model) {
                 model.last_error = Some(format!("Failed to start session: {e:?}"));
                 return crux_core::render::render();
             }
 
-            // Trigger background sync
+            // Queue background sync
+            if let Some(interrupted_id) = interrupted_id {
+                let end_request = serde_json::json!({ "end_time": timestamp });
+                outbox::enqueue_post(
+                    model,
+                    format!("/api/sessions/{interrupted_id}/end"),
+                    &end_request,
+                );
+                outbox::enqueue_post(
+                    model,
+                    format!("/api/sessions/{interrupted_id}/complete"),
+                    &serde_json::json!({ "notes": null }),
+                );
+            }
             let start_request = serde_json::json!({ "start_time": timestamp });
-            return crate::app::api_post(
-                &format!("/api/sessions/{session_id}/start"),
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/start"),
                 &start_request,
-                |response| super::Event::Session(SessionEvent::SessionSynced(response)),
             );
+
+            // The first session for a goal puts it in progress
+            for goal in crate::app::goal::start_goals_for_session(&session_id, &timestamp, model) {
+                outbox::enqueue_put(model, format!("/api/goals/{}", goal.id), &goal);
+            }
+            return outbox::sync(model);
+        }
+        SessionEvent::PauseSession(session_id, timestamp) => {
+            // Apply optimistically to local model
+            if let Err(e) = pause_session(&session_id, timestamp.clone(), model) {
+                model.last_error = Some(format!("Failed to pause session: {e:?}"));
+                return crux_core::render::render();
+            }
+
+            // Queue background sync
+            let pause_request = serde_json::json!({ "paused_at": timestamp });
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/pause"),
+                &pause_request,
+            );
+            return outbox::sync(model);
+        }
+        SessionEvent::ResumeSession(session_id, timestamp) => {
+            // Apply optimistically to local model
+            if let Err(e) = resume_session(&session_id, timestamp.clone(), model) {
+                model.last_error = Some(format!("Failed to resume session: {e:?}"));
+                return crux_core::render::render();
+            }
+
+            // Queue background sync
+            let resume_request = serde_json::json!({ "resumed_at": timestamp });
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/resume"),
+                &resume_request,
+            );
+            return outbox::sync(model);
         }
         SessionEvent::EndSession(session_id, timestamp) => {
             // Apply optimistically to local model - transitions to PendingReflection
@@ -380,13 +741,14 @@ pub fn handle_event(
                 return crux_core::render::render();
             }
 
-            // Trigger background sync
+            // Queue background sync
             let end_request = serde_json::json!({ "end_time": timestamp });
-            return crate::app::api_post(
-                &format!("/api/sessions/{session_id}/end"),
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/end"),
                 &end_request,
-                |response| super::Event::Session(SessionEvent::SessionSynced(response)),
             );
+            return outbox::sync(model);
         }
         SessionEvent::CompleteReflection(session_id) => {
             // Apply optimistically to local model - transitions PendingReflection to Ended
@@ -395,12 +757,13 @@ pub fn handle_event(
                 return crux_core::render::render();
             }
 
-            // Trigger background sync using the new complete endpoint
-            return crate::app::api_post(
-                &format!("/api/sessions/{session_id}/complete"),
+            // Queue background sync using the new complete endpoint
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/complete"),
                 &serde_json::json!({}),
-                |response| super::Event::Session(SessionEvent::SessionSynced(response)),
             );
+            return outbox::sync(model);
         }
         SessionEvent::EditSessionFields {
             session_id,
@@ -409,6 +772,13 @@ pub fn handle_event(
             notes,
         } => {
             // Apply immediately to local model
+            let redo = SessionEvent::EditSessionFields {
+                session_id: session_id.clone(),
+                goal_ids: goal_ids.clone(),
+                intention: intention.clone(),
+                notes: notes.clone(),
+            };
+            record_edit(&session_id, redo, model);
             edit_session_fields(
                 &session_id,
                 goal_ids.clone(),
@@ -417,26 +787,22 @@ pub fn handle_event(
                 model,
             );
 
-            // Trigger background sync
-            if let Some(session) = model.sessions.iter().find(|s| s.id == session_id) {
-                return crate::app::api_put(
-                    &format!("/api/sessions/{}", session.id),
-                    session,
-                    |response| super::Event::Session(SessionEvent::SessionSynced(response)),
-                );
+            // Queue background sync
+            if let Some(session) = model.sessions.iter().find(|s| s.id == session_id).cloned() {
+                outbox::enqueue_put(model, format!("/api/sessions/{}", session.id), &session);
+                return outbox::sync(model);
             }
         }
         SessionEvent::EditSessionNotes(session_id, notes) => {
             // Apply immediately to local model
+            let redo = SessionEvent::EditSessionNotes(session_id.clone(), notes.clone());
+            record_edit(&session_id, redo, model);
             edit_session_notes(&session_id, notes.clone(), model);
 
-            // Trigger background sync
-            if let Some(session) = model.sessions.iter().find(|s| s.id == session_id) {
-                return crate::app::api_put(
-                    &format!("/api/sessions/{}", session.id),
-                    session,
-                    |response| super::Event::Session(SessionEvent::SessionSynced(response)),
-                );
+            // Queue background sync
+            if let Some(session) = model.sessions.iter().find(|s| s.id == session_id).cloned() {
+                outbox::enqueue_put(model, format!("/api/sessions/{}", session.id), &session);
+                return outbox::sync(model);
             }
         }
         SessionEvent::CompleteWithNotes(session_id, notes) => {
@@ -447,50 +813,80 @@ pub fn handle_event(
                 return crux_core::render::render();
             }
 
-            // Trigger single background sync to complete endpoint (which will save notes too)
-            return crate::app::api_post(
-                &format!("/api/sessions/{session_id}/complete"),
+            // Queue single background sync to complete endpoint (which will save notes too)
+            outbox::enqueue_post(
+                model,
+                format!("/api/sessions/{session_id}/complete"),
                 &serde_json::json!({ "notes": notes }),
-                |response| super::Event::Session(SessionEvent::SessionSynced(response)),
             );
+            return outbox::sync(model);
         }
         SessionEvent::RemoveSession(session_id) => {
-            // Apply immediately to local model
-            model.sessions().remove(&session_id);
+            // Apply immediately to local model, keeping it in the trash
+            if let Some(session) = model.sessions().remove(&session_id) {
+                trash::move_to_trash(session, model);
+                history::record(
+                    model,
+                    "Remove session",
+                    Event::Session(SessionEvent::RestoreSession(session_id.clone())),
+                    Event::Session(SessionEvent::RemoveSession(session_id.clone())),
+                );
+            }
 
-            // Trigger background sync
-            return crate::app::api_delete(&format!("/api/sessions/{session_id}"), |response| {
-                super::Event::Session(SessionEvent::SessionSynced(response))
-            });
+            // Queue background sync
+            outbox::enqueue_delete(model, format!("/api/sessions/{session_id}"));
+            return outbox::sync(model);
+        }
+        SessionEvent::ArchiveSession(session_id) => {
+            return trash::archive::<PracticeSession>(&session_id, model);
+        }
+        SessionEvent::RestoreSession(session_id) => {
+            return trash::restore::<PracticeSession>(&session_id, model);
         }
     }
 
     crux_core::render::render()
 }
 
-// Helper function to merge server sessions with local sessions
-fn merge_sessions_from_server(server_sessions: Vec<PracticeSession>, model: &mut Model) {
-    // Simple merge strategy: server sessions override local ones with same ID
-    // More sophisticated conflict resolution could be added here
-    let server_session_ids: std::collections::HashSet<String> =
-        server_sessions.iter().map(|s| s.id.clone()).collect();
+// Undoing a new session trashes it, and redoing brings it back from there
+fn record_create(session_id: &str, model: &mut Model) {
+    history::record(
+        model,
+        "Add session",
+        Event::Session(SessionEvent::RemoveSession(session_id.to_string())),
+        Event::Session(SessionEvent::RestoreSession(session_id.to_string())),
+    );
+}
 
-    // Keep local sessions that don't exist on server (likely new/pending sync)
-    model
-        .sessions
-        .retain(|local_session| !server_session_ids.contains(&local_session.id));
+// Edits are undone by putting back the fields the server lets a session
+// change. Starting, pausing and ending can't be undone, as the server only
+// moves sessions forward.
+fn record_edit(session_id: &str, redo: SessionEvent, model: &mut Model) {
+    if let Some(previous) = model.sessions.iter().find(|s| s.id == session_id) {
+        let undo = edit_fields_event(previous);
+        history::record(
+            model,
+            "Edit session",
+            Event::Session(undo),
+            Event::Session(redo),
+        );
+    }
+}
 
-    // Add/update with server sessions
-    for server_session in server_sessions {
-        let session = server_session;
-        if let Some(existing_pos) = model.sessions.iter().position(|s| s.id == session.id) {
-            model.sessions[existing_pos] = session;
-        } else {
-            model.sessions.push(session);
-        }
+fn edit_fields_event(session: &PracticeSession) -> SessionEvent {
+    SessionEvent::EditSessionFields {
+        session_id: session.id.clone(),
+        goal_ids: session.goal_ids.clone(),
+        intention: session.intention.clone(),
+        notes: session.notes.clone(),
     }
 }
 
+// Helper function to merge server sessions with local ones, keeping unsynced local edits
+fn merge_sessions_from_server(server_sessions: Vec<PracticeSession>, model: &mut Model) {
+    crate::app::conflict::merge_from_server(server_sessions, model);
+}
+
 // *************
 // TESTS
 // *************
@@ -581,6 +977,178 @@ fn test_session_state_transitions() {
     assert!(session.end("2025-05-01T13:00:00Z".to_string()).is_err());
 }
 
+#[test]
+fn test_pause_and_resume_excludes_paused_time() {
+    let mut session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    session.start("2025-05-01T12:00:00Z".to_string()).unwrap();
+
+    // Pause after 10 minutes of practice
+    session.pause("2025-05-01T12:10:00Z".to_string()).unwrap();
+    assert!(session.is_paused());
+    assert!(!session.is_active());
+    assert!(session.is_in_progress());
+    assert_eq!(
+        session.state,
+        SessionState::Paused {
+            start_time: "2025-05-01T12:00:00Z".to_string(),
+            paused_at: "2025-05-01T12:10:00Z".to_string(),
+            accumulated_seconds: 600,
+        }
+    );
+
+    // 15 minute phone call, then another 5 minutes of practice
+    session.resume("2025-05-01T12:25:00Z".to_string()).unwrap();
+    assert!(session.is_active());
+    assert_eq!(session.paused_seconds(), 900);
+
+    session.pause("2025-05-01T12:30:00Z".to_string()).unwrap();
+    assert!(matches!(
+        session.state,
+        SessionState::Paused {
+            accumulated_seconds: 900,
+            ..
+        }
+    ));
+    session.resume("2025-05-01T12:35:00Z".to_string()).unwrap();
+
+    session.end("2025-05-01T12:45:00Z".to_string()).unwrap();
+    assert_eq!(session.duration_in_seconds(), Some(1500));
+
+    session.complete_reflection().unwrap();
+    assert_eq!(session.duration_in_seconds(), Some(1500));
+    assert_eq!(session.duration(), Some("25m".to_string()));
+}
+
+#[test]
+fn test_end_while_paused_closes_pause() {
+    let mut session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    session.start("2025-05-01T12:00:00Z".to_string()).unwrap();
+    session.pause("2025-05-01T12:20:00Z".to_string()).unwrap();
+    session.end("2025-05-01T12:50:00Z".to_string()).unwrap();
+
+    assert_eq!(session.end_time(), Some("2025-05-01T12:50:00Z"));
+    assert_eq!(session.pauses.len(), 1);
+    assert_eq!(session.duration_in_seconds(), Some(1200));
+}
+
+#[test]
+fn test_invalid_pause_transitions() {
+    let mut session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    assert_eq!(
+        session.pause("2025-05-01T12:00:00Z".to_string()),
+        Err(SessionError::NotActive)
+    );
+    assert_eq!(
+        session.resume("2025-05-01T12:00:00Z".to_string()),
+        Err(SessionError::NotActive)
+    );
+
+    session.start("2025-05-01T12:00:00Z".to_string()).unwrap();
+    assert_eq!(
+        session.resume("2025-05-01T12:05:00Z".to_string()),
+        Err(SessionError::NotPaused)
+    );
+
+    session.pause("2025-05-01T12:05:00Z".to_string()).unwrap();
+    assert_eq!(
+        session.pause("2025-05-01T12:06:00Z".to_string()),
+        Err(SessionError::AlreadyPaused)
+    );
+    assert_eq!(
+        session.start("2025-05-01T12:06:00Z".to_string()),
+        Err(SessionError::AlreadyStarted)
+    );
+    assert_eq!(session.complete_reflection(), Err(SessionError::NotActive));
+}
+
+#[test]
+fn test_starting_new_session_ends_paused_session() {
+    let mut model = Model::default();
+    let session1 = PracticeSession::new(vec!["Goal 1".to_string()], "Session 1".to_string());
+    let session1_id = session1.id.to_string();
+    let session2 = PracticeSession::new(vec!["Goal 2".to_string()], "Session 2".to_string());
+    let session2_id = session2.id.to_string();
+    model.sessions().add(session1);
+    model.sessions().add(session2);
+
+    start_session(&session1_id, "2025-05-01T12:00:00Z".to_string(), &mut model).unwrap();
+    pause_session(&session1_id, "2025-05-01T12:10:00Z".to_string(), &mut model).unwrap();
+    start_session(&session2_id, "2025-05-01T12:30:00Z".to_string(), &mut model).unwrap();
+
+    let session1 = model.sessions.iter().find(|s| s.id == session1_id).unwrap();
+    assert!(session1.is_ended());
+    assert_eq!(session1.duration_in_seconds(), Some(600));
+    assert!(model.sessions().is_session_active(&session2_id));
+}
+
+#[test]
+fn test_starting_new_session_tells_server_about_ended_one_first() {
+    let mut model = Model::default();
+    let session1 = PracticeSession::new(vec![], "Session 1".to_string());
+    let session1_id = session1.id.clone();
+    let session2 = PracticeSession::new(vec![], "Session 2".to_string());
+    let session2_id = session2.id.clone();
+    model.sessions = vec![session1, session2];
+
+    for session_id in [&session1_id, &session2_id] {
+        let _ = handle_event(
+            SessionEvent::StartSession(session_id.clone(), "2025-05-01T12:00:00Z".to_string()),
+            &mut model,
+        );
+    }
+
+    let paths: Vec<&str> = model
+        .outbox
+        .operations
+        .iter()
+        .map(|op| op.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            format!("/api/sessions/{session1_id}/start"),
+            format!("/api/sessions/{session1_id}/end"),
+            format!("/api/sessions/{session1_id}/complete"),
+            format!("/api/sessions/{session2_id}/start"),
+        ]
+    );
+}
+
+#[test]
+fn test_starting_first_session_for_goal_starts_goal() {
+    let mut model = Model::default();
+    let goal = crate::app::create_test_goal("Scales", None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+    let session1 = PracticeSession::new(vec![goal_id.clone()], "Session 1".to_string());
+    let session1_id = session1.id.clone();
+    let session2 = PracticeSession::new(vec![goal_id.clone()], "Session 2".to_string());
+    let session2_id = session2.id.clone();
+    model.sessions = vec![session1, session2];
+
+    let _ = handle_event(
+        SessionEvent::StartSession(session1_id, "2025-05-01T12:00:00Z".to_string()),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].status, crate::app::GoalStatus::InProgress);
+    assert_eq!(model.goals[0].start_date.as_deref(), Some("2025-05-01"));
+
+    // Later sessions leave the goal and its start date alone
+    let _ = handle_event(
+        SessionEvent::StartSession(session2_id, "2025-05-03T12:00:00Z".to_string()),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].start_date.as_deref(), Some("2025-05-01"));
+
+    let goal_updates = model
+        .outbox
+        .operations
+        .iter()
+        .filter(|op| op.path == format!("/api/goals/{goal_id}"))
+        .count();
+    assert_eq!(goal_updates, 1);
+}
+
 #[test]
 fn test_backward_compatibility() {
     let mut session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
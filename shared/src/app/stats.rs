@@ -0,0 +1,342 @@
+use crate::app::model::Model;
+use crate::app::PracticeSession;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// Days shown in the daily breakdown, ending today
+const DAILY_HISTORY_DAYS: i64 = 7;
+
+/// Practice minutes on one day
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct DailyMinutes {
+    pub date: String, // "2025-05-01"
+    pub minutes: u32,
+}
+
+/// Practice minutes spent on one goal or study
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct TimeSpent {
+    pub id: String,
+    pub name: String,
+    pub minutes: u32,
+}
+
+/// Totals computed from finished sessions, so every shell shows the same numbers.
+/// Days are the user's local days, so evening practice counts on the day it happened.
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct PracticeStats {
+    pub today_minutes: u32,
+    pub this_week_minutes: u32, // weeks start on Monday
+    pub this_month_minutes: u32,
+    pub total_minutes: u32,
+    // The last week, oldest day first
+    pub daily_minutes: Vec<DailyMinutes>,
+    pub current_streak_days: u32,
+    pub longest_streak_days: u32,
+    pub session_count: u32,
+    pub average_session_minutes: u32,
+    // Most practiced first
+    pub time_per_goal: Vec<TimeSpent>,
+    pub time_per_study: Vec<TimeSpent>,
+}
+
+// A finished session: the local day it started on and how long was practiced
+pub(crate) struct Practiced<'a> {
+    pub session: &'a PracticeSession,
+    pub day: NaiveDate,
+    pub seconds: u32,
+}
+
+pub(crate) fn practiced(session: &PracticeSession, zone: Tz) -> Option<Practiced<'_>> {
+    let seconds = session.duration_in_seconds()?;
+    let start_time = DateTime::parse_from_rfc3339(session.start_time()?).ok()?;
+    Some(Practiced {
+        session,
+        day: start_time.with_timezone(&zone).date_naive(),
+        seconds,
+    })
+}
+
+pub(crate) fn to_minutes(seconds: u64) -> u32 {
+    u32::try_from((seconds + 30) / 60).unwrap_or(u32::MAX)
+}
+
+// Consecutive days ending on the last day in `days` and the longest such run
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = match previous {
+            Some(previous) if previous + Duration::days(1) == day => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    // A streak isn't broken until a whole day goes by without practice
+    let current = match previous {
+        Some(last) if last == today || last + Duration::days(1) == today => run,
+        _ => 0,
+    };
+    (current, longest)
+}
+
+fn time_spent(
+    seconds_by_id: HashMap<&str, u64>,
+    name_of: impl Fn(&str) -> Option<String>,
+) -> Vec<TimeSpent> {
+    let mut spent: Vec<TimeSpent> = seconds_by_id
+        .into_iter()
+        .filter_map(|(id, seconds)| {
+            Some(TimeSpent {
+                id: id.to_string(),
+                name: name_of(id)?,
+                minutes: to_minutes(seconds),
+            })
+        })
+        .collect();
+    spent.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.name.cmp(&b.name)));
+    spent
+}
+
+impl PracticeStats {
+    pub fn new(model: &Model, now: DateTime<Utc>, zone: Tz) -> Self {
+        let today = now.with_timezone(&zone).date_naive();
+        let week_start = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        let month_start = today.with_day(1).unwrap_or(today);
+
+        let sessions: Vec<Practiced> = model
+            .sessions
+            .iter()
+            .filter_map(|session| practiced(session, zone))
+            .collect();
+
+        let mut seconds_by_day: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+        let mut seconds_by_goal: HashMap<&str, u64> = HashMap::new();
+        let mut seconds_by_study: HashMap<&str, u64> = HashMap::new();
+        for practiced in &sessions {
+            let seconds = u64::from(practiced.seconds);
+            *seconds_by_day.entry(practiced.day).or_default() += seconds;
+
+            // A session counts in full towards every goal it was for
+            for goal_id in &practiced.session.goal_ids {
+                *seconds_by_goal.entry(goal_id.as_str()).or_default() += seconds;
+            }
+            let end_time = practiced.session.end_time().unwrap_or_default();
+            for study_session in &practiced.session.study_sessions {
+                *seconds_by_study
+                    .entry(study_session.study_id.as_str())
+                    .or_default() += u64::from(study_session.elapsed_seconds_at(end_time));
+            }
+        }
+
+        let minutes_since = |start: NaiveDate| {
+            to_minutes(seconds_by_day.range(start..=today).map(|(_, s)| s).sum())
+        };
+        let total_seconds: u64 = seconds_by_day.values().sum();
+        let session_count = u32::try_from(sessions.len()).unwrap_or(u32::MAX);
+
+        let daily_minutes = (0..DAILY_HISTORY_DAYS)
+            .rev()
+            .map(|days_ago| {
+                let date = today - Duration::days(days_ago);
+                DailyMinutes {
+                    date: date.to_string(),
+                    minutes: to_minutes(seconds_by_day.get(&date).copied().unwrap_or(0)),
+                }
+            })
+            .collect();
+
+        let practice_days: BTreeSet<NaiveDate> = seconds_by_day
+            .keys()
+            .copied()
+            .filter(|day| *day <= today)
+            .collect();
+        let (current_streak_days, longest_streak_days) = streaks(&practice_days, today);
+
+        Self {
+            today_minutes: minutes_since(today),
+            this_week_minutes: minutes_since(week_start),
+            this_month_minutes: minutes_since(month_start),
+            total_minutes: to_minutes(total_seconds),
+            daily_minutes,
+            current_streak_days,
+            longest_streak_days,
+            session_count,
+            average_session_minutes: match session_count {
+                0 => 0,
+                count => to_minutes(total_seconds / u64::from(count)),
+            },
+            time_per_goal: time_spent(seconds_by_goal, |id| {
+                model
+                    .goals
+                    .iter()
+                    .find(|g| g.id == id)
+                    .map(|g| g.name.clone())
+            }),
+            time_per_study: time_spent(seconds_by_study, |id| {
+                model
+                    .studies
+                    .iter()
+                    .find(|s| s.id == id)
+                    .map(|s| s.name.clone())
+            }),
+        }
+    }
+}
+
+// *************
+// TESTS
+// *************
+
+#[test]
+fn test_minutes_per_period() {
+    let model = Model {
+        sessions: vec![
+            // Thursday 1 May 2025 is "today", the week started on Monday 28 April
+            crate::app::create_ended_test_session_at(&[], "2025-05-01T09:00:00Z", 30),
+            crate::app::create_ended_test_session_at(&[], "2025-05-01T18:00:00Z", 15),
+            crate::app::create_ended_test_session_at(&[], "2025-04-29T09:00:00Z", 20),
+            crate::app::create_ended_test_session_at(&[], "2025-04-27T09:00:00Z", 40),
+            // Still running, not counted yet
+            crate::app::create_test_session(&[], "Warm up"),
+        ],
+        ..Model::default()
+    };
+
+    let stats = PracticeStats::new(
+        &model,
+        crate::app::test_time("2025-05-01T20:00:00Z"),
+        Tz::UTC,
+    );
+
+    assert_eq!(stats.today_minutes, 45);
+    assert_eq!(stats.this_week_minutes, 65);
+    assert_eq!(stats.this_month_minutes, 45);
+    assert_eq!(stats.total_minutes, 105);
+    assert_eq!(stats.session_count, 4);
+    assert_eq!(stats.average_session_minutes, 26);
+    assert_eq!(stats.daily_minutes.len(), 7);
+    assert_eq!(
+        stats.daily_minutes.last(),
+        Some(&DailyMinutes {
+            date: "2025-05-01".to_string(),
+            minutes: 45
+        })
+    );
+    assert_eq!(stats.daily_minutes[0].date, "2025-04-25");
+}
+
+#[test]
+fn test_streaks() {
+    let model = Model {
+        sessions: vec![
+            crate::app::create_ended_test_session_at(&[], "2025-04-20T09:00:00Z", 10),
+            crate::app::create_ended_test_session_at(&[], "2025-04-21T09:00:00Z", 10),
+            crate::app::create_ended_test_session_at(&[], "2025-04-22T09:00:00Z", 10),
+            crate::app::create_ended_test_session_at(&[], "2025-04-29T09:00:00Z", 10),
+            crate::app::create_ended_test_session_at(&[], "2025-04-30T09:00:00Z", 10),
+        ],
+        ..Model::default()
+    };
+
+    // Nothing yet today, but practicing later still continues the streak
+    let stats = PracticeStats::new(
+        &model,
+        crate::app::test_time("2025-05-01T08:00:00Z"),
+        Tz::UTC,
+    );
+    assert_eq!(stats.current_streak_days, 2);
+    assert_eq!(stats.longest_streak_days, 3);
+
+    // A day without practice ends it
+    let stats = PracticeStats::new(
+        &model,
+        crate::app::test_time("2025-05-02T08:00:00Z"),
+        Tz::UTC,
+    );
+    assert_eq!(stats.current_streak_days, 0);
+    assert_eq!(stats.longest_streak_days, 3);
+}
+
+#[test]
+fn test_time_per_goal_and_study() {
+    let mut model = Model::default();
+    let scales = crate::app::create_test_goal("Scales", None);
+    let repertoire = crate::app::create_test_goal("Repertoire", None);
+    let hanon = crate::app::create_test_study("Hanon", None);
+
+    let mut session = crate::app::create_ended_test_session_at(
+        &[scales.id.as_str(), repertoire.id.as_str()],
+        "2025-05-01T09:00:00Z",
+        30,
+    );
+    let mut study_session = crate::app::create_test_study_session(&hanon.id, &session.id);
+    study_session.state = crate::app::StudySessionState::Completed {
+        start_time: "2025-05-01T09:00:00Z".to_string(),
+        end_time: "2025-05-01T09:12:00Z".to_string(),
+        elapsed_seconds: 12 * 60,
+    };
+    session.study_sessions.push(study_session);
+
+    model.sessions = vec![
+        session,
+        crate::app::create_ended_test_session_at(&[scales.id.as_str()], "2025-05-01T12:00:00Z", 10),
+    ];
+    model.goals = vec![scales.clone(), repertoire.clone()];
+    model.studies = vec![hanon.clone()];
+
+    let stats = PracticeStats::new(
+        &model,
+        crate::app::test_time("2025-05-01T20:00:00Z"),
+        Tz::UTC,
+    );
+
+    let goals: Vec<(&str, u32)> = stats
+        .time_per_goal
+        .iter()
+        .map(|t| (t.name.as_str(), t.minutes))
+        .collect();
+    assert_eq!(goals, vec![("Scales", 40), ("Repertoire", 30)]);
+    assert_eq!(
+        stats.time_per_study,
+        vec![TimeSpent {
+            id: hanon.id,
+            name: "Hanon".to_string(),
+            minutes: 12
+        }]
+    );
+}
+
+#[test]
+fn test_days_are_local_to_the_time_zone() {
+    // Late on 30 April in New York is already 1 May in UTC
+    let model = Model {
+        sessions: vec![
+            crate::app::create_ended_test_session_at(&[], "2025-05-01T03:30:00Z", 20),
+            crate::app::create_ended_test_session_at(&[], "2025-05-01T14:00:00Z", 10),
+        ],
+        ..Model::default()
+    };
+    let now = crate::app::test_time("2025-05-01T15:00:00Z");
+
+    let stats = PracticeStats::new(&model, now, "America/New_York".parse().unwrap());
+    assert_eq!(stats.today_minutes, 10);
+    assert_eq!(stats.current_streak_days, 2);
+    assert_eq!(
+        stats.daily_minutes[5],
+        DailyMinutes {
+            date: "2025-04-30".to_string(),
+            minutes: 20
+        }
+    );
+
+    let stats = PracticeStats::new(&model, now, Tz::UTC);
+    assert_eq!(stats.today_minutes, 30);
+    assert_eq!(stats.current_streak_days, 1);
+}
@@ -1,7 +1,10 @@
 use crate::app::model::Model;
-use crate::app::repository::Repository;
+use crate::app::outbox;
+use crate::app::repository::{DeleteMode, DetachedReferences, Repository};
 
 use crate::app::study_session::StudySession;
+use crate::app::trash;
+use crate::app::{history, Event};
 use crate::HttpResult;
 use crux_core::Command;
 use facet::Facet;
@@ -12,6 +15,16 @@ pub struct Study {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    // Hidden from lists but kept until restored
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    // In the trash, from where the server purges it after a retention period
+    #[serde(default)]
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -24,14 +37,20 @@ pub enum StudyEvent {
     #[serde(skip)]
     #[facet(skip)]
     StudiesSynced(HttpResult<crux_http::Response<Vec<Study>>, crux_http::HttpError>),
-    #[serde(skip)]
-    #[facet(skip)]
-    StudySynced(HttpResult<crux_http::Response<Study>, crux_http::HttpError>),
 
     // Optimistic user actions (all immediate, sync in background)
     CreateStudy(Study),
     UpdateStudy(Study),
-    RemoveStudy(String),
+    // Detaches the study from whatever lists it, or refuses while anything does
+    RemoveStudy(String, DeleteMode),
+    // Hides the study from lists without removing it
+    ArchiveStudy(String),
+    // Brings the study back from the archive or the trash
+    RestoreStudy(String),
+    // Undoes a Detach removal, putting the study back where it was listed (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    ReattachStudy(String, DetachedReferences),
 }
 
 impl Study {
@@ -40,6 +59,10 @@ impl Study {
             id: crate::app::generate_id(),
             name,
             description,
+            version: 1,
+            updated_at: None,
+            archived_at: None,
+            deleted_at: None,
         }
     }
 
@@ -59,7 +82,7 @@ pub fn handle_event(event: StudyEvent, model: &mut Model) -> Command<super::Effe
     match event {
         // Background sync events (internal only)
         StudyEvent::SyncStudies => {
-            return crate::app::api_get("/api/studies", |response| {
+            return crate::app::api_get(model, "/api/studies", |response| {
                 super::Event::Study(StudyEvent::StudiesSynced(response))
             });
         }
@@ -71,75 +94,96 @@ pub fn handle_event(event: StudyEvent, model: &mut Model) -> Command<super::Effe
         StudyEvent::StudiesSynced(HttpResult::Err(_e)) => {
             // Silently fail background sync - user doesn't need to know
         }
-        StudyEvent::StudySynced(HttpResult::Ok(_response)) => {
-            // Individual study synced successfully - nothing to do
-        }
-        StudyEvent::StudySynced(HttpResult::Err(_e)) => {
-            // Individual study sync failed - could retry or show status
-        }
 
         // Optimistic user actions (all immediate, sync in background)
         StudyEvent::CreateStudy(study) => {
             // Apply immediately to local model
             model.studies().add(study.clone());
+            history::record(
+                model,
+                "Add study",
+                Event::Study(StudyEvent::RemoveStudy(
+                    study.id.clone(),
+                    DeleteMode::Detach,
+                )),
+                Event::Study(StudyEvent::RestoreStudy(study.id.clone())),
+            );
 
-            // Trigger background sync
+            // Queue background sync
             let create_request = serde_json::json!({
+                "id": study.id,
                 "name": study.name,
                 "description": study.description
             });
-            return crate::app::api_post("/api/studies", &create_request, |response| {
-                super::Event::Study(StudyEvent::StudySynced(response))
-            });
+            outbox::enqueue_post(model, "/api/studies".to_string(), &create_request);
+            return outbox::sync(model);
         }
         StudyEvent::UpdateStudy(study) => {
             // Apply immediately to local model
+            if let Some(previous) = model.studies.iter().find(|s| s.id == study.id).cloned() {
+                history::record(
+                    model,
+                    "Edit study",
+                    Event::Study(StudyEvent::UpdateStudy(previous)),
+                    Event::Study(StudyEvent::UpdateStudy(study.clone())),
+                );
+            }
             model.studies().update(study.clone());
 
-            // Trigger background sync
+            // Queue background sync
             let update_request = serde_json::json!({
                 "name": study.name,
                 "description": study.description
             });
-            return crate::app::api_put(
-                &format!("/api/studies/{}", study.id),
-                &update_request,
-                |response| super::Event::Study(StudyEvent::StudySynced(response)),
-            );
+            outbox::enqueue_put(model, format!("/api/studies/{}", study.id), &update_request);
+            return outbox::sync(model);
         }
-        StudyEvent::RemoveStudy(study_id) => {
-            // Apply immediately to local model
-            model.studies.retain(|s| s.id != study_id);
+        StudyEvent::RemoveStudy(study_id, mode) => {
+            // Apply immediately to local model, along with the references to it
+            match model.studies().remove_with_references(&study_id, mode) {
+                Ok(Some((study, references))) => {
+                    trash::move_to_trash(study, model);
+                    history::record(
+                        model,
+                        "Remove study",
+                        Event::Study(StudyEvent::ReattachStudy(study_id.clone(), references)),
+                        Event::Study(StudyEvent::RemoveStudy(study_id.clone(), mode)),
+                    );
+                }
+                Ok(None) => {}
+                Err(impact) => {
+                    let summary = impact.summary.unwrap_or_default();
+                    model.last_error = Some(format!("Failed to remove study: {summary}"));
+                    return crux_core::render::render();
+                }
+            }
 
-            // Trigger background sync
-            return crate::app::api_delete(&format!("/api/studies/{study_id}"), |response| {
-                super::Event::Study(StudyEvent::StudySynced(response))
-            });
+            // Queue background sync, the server cleans up its own references
+            outbox::enqueue_delete(model, mode.delete_path(format!("/api/studies/{study_id}")));
+            return outbox::sync(model);
+        }
+        StudyEvent::ArchiveStudy(study_id) => {
+            return trash::archive::<Study>(&study_id, model);
+        }
+        StudyEvent::RestoreStudy(study_id) => {
+            return trash::restore::<Study>(&study_id, model);
+        }
+        StudyEvent::ReattachStudy(study_id, references) => {
+            // The server's restore brings back its own references
+            let command = trash::restore::<Study>(&study_id, model);
+            if model.studies.iter().any(|s| s.id == study_id) {
+                model.studies().reattach(&study_id, &references);
+            }
+            return command;
         }
     }
 
     crux_core::render::render()
 }
 
-// Helper function to merge server studies with local studies
+// Helper function to merge server studies with local ones, keeping unsynced local edits
 fn merge_studies_from_server(server_studies: Vec<Study>, model: &mut Model) {
-    // Simple merge strategy: server studies override local ones with same ID
-    let server_study_ids: std::collections::HashSet<String> =
-        server_studies.iter().map(|s| s.id.clone()).collect();
-
-    // Keep local studies that don't exist on server (likely new/pending sync)
-    model
-        .studies
-        .retain(|local_study| !server_study_ids.contains(&local_study.id));
-
-    // Add/update with server studies
-    for server_study in server_studies {
-        if let Some(existing_pos) = model.studies.iter().position(|s| s.id == server_study.id) {
-            model.studies[existing_pos] = server_study;
-        } else {
-            model.studies.push(server_study);
-        }
-    }
+    crate::app::conflict::merge_from_server(server_studies, model);
 }
 
 // *************
@@ -209,3 +253,22 @@ fn test_study_sessions() {
     let sessions = study.get_study_sessions(&model);
     assert_eq!(sessions.len(), 2);
 }
+
+#[test]
+fn test_remove_unused_study_asks_server_to_block() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let study = Study::new("Study 1".to_string(), None);
+    let study_id = study.id.clone();
+    model.studies.push(study);
+
+    let _ = handle_event(
+        StudyEvent::RemoveStudy(study_id.clone(), DeleteMode::BlockIfReferenced),
+        &mut model,
+    );
+    assert!(model.studies.is_empty());
+    // The server may know of references this device hasn't synced yet
+    assert_eq!(
+        model.outbox.operations[0].path,
+        format!("/api/studies/{study_id}?mode=block_if_referenced")
+    );
+}
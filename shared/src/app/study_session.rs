@@ -1,12 +1,14 @@
+use crate::app::error::StudySessionError;
+use crate::app::goal::complete_goals_at_tempo;
 use crate::app::model::Model;
+use crate::app::outbox;
+use crate::app::session::{calculate_duration_in_seconds, PracticeSession};
 use crux_core::Command;
 use facet::Facet;
 use serde::{Deserialize, Serialize};
 
 #[cfg(test)]
 use crate::app::repository::Repository;
-#[cfg(test)]
-use crate::app::session::PracticeSession;
 
 #[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
 pub struct StudySession {
@@ -14,13 +16,61 @@ pub struct StudySession {
     pub study_id: String,
     pub session_id: String,
     pub score: Option<u32>, // out of 10
+    #[serde(default)]
+    pub state: StudySessionState,
+    // Tempo reached, in BPM, and whether it was played cleanly at that tempo
+    #[serde(default)]
+    pub tempo: Option<u32>,
+    #[serde(default)]
+    pub clean: bool,
+    // Time set aside for this study by the template the session was made from
+    #[serde(default)]
+    pub planned_minutes: Option<u32>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[repr(C)]
+pub enum StudySessionState {
+    #[default]
+    NotStarted,
+    Active {
+        start_time: String,
+        // When the current stretch of work began
+        resumed_at: String,
+        // Seconds accumulated before `resumed_at`
+        elapsed_seconds: u32,
+    },
+    Paused {
+        start_time: String,
+        elapsed_seconds: u32,
+    },
+    Completed {
+        start_time: String,
+        end_time: String,
+        elapsed_seconds: u32,
+    },
 }
 
 #[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[repr(C)]
 pub enum StudySessionEvent {
+    // Optimistic user actions (all immediate, sync in background)
     AddStudySession(StudySession),
     UpdateStudySession(StudySession),
+
+    // Time tracking events (study_session_id, timestamp)
+    StartStudySession(String, String),
+    PauseStudySession(String, String),
+    ResumeStudySession(String, String),
+    CompleteStudySession(String, String),
+    // Tempo log (study_session_id, bpm, played cleanly)
+    RecordTempo(String, u32, bool),
+    // Convenience event - pauses the current study, starts (or creates) one for study_id
+    SwitchToStudy {
+        session_id: String,
+        study_id: String,
+        timestamp: String,
+    },
 }
 
 impl StudySession {
@@ -30,6 +80,117 @@ impl StudySession {
             study_id,
             session_id,
             score: None,
+            state: StudySessionState::NotStarted,
+            tempo: None,
+            clean: false,
+            planned_minutes: None,
+        }
+    }
+
+    pub fn record_tempo(&mut self, bpm: u32, clean: bool) {
+        self.tempo = Some(bpm);
+        self.clean = clean;
+    }
+
+    /// Whether this was played cleanly at or above `tempo_target`
+    pub fn is_clean_at(&self, tempo_target: u32) -> bool {
+        self.clean && self.tempo.is_some_and(|tempo| tempo >= tempo_target)
+    }
+
+    // Simple state transition methods with validation
+    pub fn start(&mut self, timestamp: String) -> Result<(), StudySessionError> {
+        match &self.state {
+            StudySessionState::NotStarted => {
+                self.state = StudySessionState::Active {
+                    start_time: timestamp.clone(),
+                    resumed_at: timestamp,
+                    elapsed_seconds: 0,
+                };
+                Ok(())
+            }
+            StudySessionState::Paused { .. } => self.resume(timestamp),
+            StudySessionState::Active { .. } => Err(StudySessionError::AlreadyActive),
+            StudySessionState::Completed { .. } => Err(StudySessionError::AlreadyCompleted),
+        }
+    }
+
+    pub fn pause(&mut self, timestamp: String) -> Result<(), StudySessionError> {
+        match &self.state {
+            StudySessionState::Active { start_time, .. } => {
+                self.state = StudySessionState::Paused {
+                    start_time: start_time.clone(),
+                    elapsed_seconds: self.elapsed_seconds_at(&timestamp),
+                };
+                Ok(())
+            }
+            StudySessionState::NotStarted | StudySessionState::Paused { .. } => {
+                Err(StudySessionError::NotActive)
+            }
+            StudySessionState::Completed { .. } => Err(StudySessionError::AlreadyCompleted),
+        }
+    }
+
+    pub fn resume(&mut self, timestamp: String) -> Result<(), StudySessionError> {
+        match &self.state {
+            StudySessionState::Paused {
+                start_time,
+                elapsed_seconds,
+            } => {
+                self.state = StudySessionState::Active {
+                    start_time: start_time.clone(),
+                    resumed_at: timestamp,
+                    elapsed_seconds: *elapsed_seconds,
+                };
+                Ok(())
+            }
+            StudySessionState::NotStarted => Err(StudySessionError::NotPaused),
+            StudySessionState::Active { .. } => Err(StudySessionError::AlreadyActive),
+            StudySessionState::Completed { .. } => Err(StudySessionError::AlreadyCompleted),
+        }
+    }
+
+    pub fn complete(&mut self, timestamp: String) -> Result<(), StudySessionError> {
+        match &self.state {
+            StudySessionState::Active { start_time, .. }
+            | StudySessionState::Paused { start_time, .. } => {
+                self.state = StudySessionState::Completed {
+                    start_time: start_time.clone(),
+                    elapsed_seconds: self.elapsed_seconds_at(&timestamp),
+                    end_time: timestamp,
+                };
+                Ok(())
+            }
+            StudySessionState::NotStarted => Err(StudySessionError::NotActive),
+            StudySessionState::Completed { .. } => Err(StudySessionError::AlreadyCompleted),
+        }
+    }
+
+    // Helper methods
+    pub fn is_active(&self) -> bool {
+        matches!(self.state, StudySessionState::Active { .. })
+    }
+
+    pub fn is_completed(&self) -> bool {
+        matches!(self.state, StudySessionState::Completed { .. })
+    }
+
+    /// Seconds worked on this study as of `timestamp`
+    pub fn elapsed_seconds_at(&self, timestamp: &str) -> u32 {
+        match &self.state {
+            StudySessionState::NotStarted => 0,
+            StudySessionState::Active {
+                resumed_at,
+                elapsed_seconds,
+                ..
+            } => {
+                elapsed_seconds + calculate_duration_in_seconds(resumed_at, timestamp).unwrap_or(0)
+            }
+            StudySessionState::Paused {
+                elapsed_seconds, ..
+            }
+            | StudySessionState::Completed {
+                elapsed_seconds, ..
+            } => *elapsed_seconds,
         }
     }
 }
@@ -57,28 +218,234 @@ pub fn get_study_sessions_for_session<'a>(
         .unwrap_or_else(Vec::new)
 }
 
+fn find_parent_session_id(model: &Model, study_session_id: &str) -> Option<String> {
+    model
+        .sessions
+        .iter()
+        .find(|session| {
+            session
+                .study_sessions
+                .iter()
+                .any(|study_session| study_session.id == study_session_id)
+        })
+        .map(|session| session.id.clone())
+}
+
+fn find_parent_session_mut<'a>(
+    model: &'a mut Model,
+    study_session_id: &str,
+) -> Option<&'a mut PracticeSession> {
+    model.sessions.iter_mut().find(|session| {
+        session
+            .study_sessions
+            .iter()
+            .any(|study_session| study_session.id == study_session_id)
+    })
+}
+
+pub fn start_study_session(
+    study_session_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), StudySessionError> {
+    let session =
+        find_parent_session_mut(model, study_session_id).ok_or(StudySessionError::NotFound)?;
+    session.start_study(study_session_id, timestamp)
+}
+
+pub fn pause_study_session(
+    study_session_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), StudySessionError> {
+    let session =
+        find_parent_session_mut(model, study_session_id).ok_or(StudySessionError::NotFound)?;
+    session.pause_study(study_session_id, timestamp)
+}
+
+pub fn complete_study_session(
+    study_session_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), StudySessionError> {
+    let session =
+        find_parent_session_mut(model, study_session_id).ok_or(StudySessionError::NotFound)?;
+    session.complete_study(study_session_id, timestamp)
+}
+
+/// Starts work on `study_id`, reusing its unfinished study session or creating a new one
+pub fn switch_to_study(
+    session_id: &str,
+    study_id: &str,
+    timestamp: String,
+    model: &mut Model,
+) -> Result<(), StudySessionError> {
+    let session = model
+        .sessions
+        .iter_mut()
+        .find(|s| s.id == session_id)
+        .ok_or(StudySessionError::NotFound)?;
+    if !session.is_active() {
+        return Err(StudySessionError::SessionNotActive);
+    }
+
+    let study_session_id = match session
+        .study_sessions
+        .iter()
+        .find(|s| s.study_id == study_id && !s.is_completed())
+    {
+        Some(existing) => existing.id.clone(),
+        None => {
+            let study_session = StudySession {
+                id: model.ids.next_id(),
+                ..StudySession::new(study_id.to_string(), session_id.to_string())
+            };
+            let id = study_session.id.clone();
+            session.push_study_session(study_session);
+            id
+        }
+    };
+
+    session.start_study(&study_session_id, timestamp)
+}
+
+fn enqueue_post_study_session(model: &mut Model, study_session: &StudySession) {
+    outbox::enqueue_post(
+        model,
+        format!("/api/sessions/{}/studies", study_session.session_id),
+        study_session,
+    );
+}
+
+fn enqueue_put_study_session(model: &mut Model, study_session: &StudySession) {
+    outbox::enqueue_put(
+        model,
+        format!(
+            "/api/sessions/{}/studies/{}",
+            study_session.session_id, study_session.id
+        ),
+        study_session,
+    );
+}
+
+// Applies a time tracking change and queues every study session it touched
+// (switching studies also pauses the previous one)
+fn apply_and_sync(
+    session_id: &str,
+    action: &str,
+    model: &mut Model,
+    apply: impl FnOnce(&mut Model) -> Result<(), StudySessionError>,
+) -> Command<super::Effect, super::Event> {
+    let study_sessions_of = |model: &Model| {
+        model
+            .sessions
+            .iter()
+            .find(|s| s.id == session_id)
+            .map(|s| s.study_sessions.clone())
+            .unwrap_or_default()
+    };
+
+    let before = study_sessions_of(model);
+    if let Err(e) = apply(model) {
+        model.last_error = Some(format!("Failed to {action}: {e:?}"));
+        return crux_core::render::render();
+    }
+
+    for study_session in study_sessions_of(model) {
+        match before.iter().find(|s| s.id == study_session.id) {
+            None => enqueue_post_study_session(model, &study_session),
+            Some(previous) if *previous != study_session => {
+                enqueue_put_study_session(model, &study_session);
+            }
+            Some(_) => {}
+        }
+    }
+    outbox::sync(model)
+}
+
 pub fn handle_event(
     event: StudySessionEvent,
     model: &mut Model,
 ) -> Command<super::Effect, super::Event> {
     match event {
+        // Optimistic user actions (all immediate, sync in background)
         StudySessionEvent::AddStudySession(session) => {
+            // Apply immediately to local model
             if let Some(practice_session) = model
                 .sessions
                 .iter_mut()
                 .find(|s| s.id == session.session_id)
             {
-                practice_session.push_study_session(session);
+                practice_session.push_study_session(session.clone());
+
+                // Queue background sync
+                enqueue_post_study_session(model, &session);
+                return outbox::sync(model);
             }
         }
         StudySessionEvent::UpdateStudySession(session) => {
+            // Apply immediately to local model
             if let Some(practice_session) = model
                 .sessions
                 .iter_mut()
                 .find(|s| s.id == session.session_id)
             {
-                practice_session.update_study_session(session);
+                practice_session.update_study_session(session.clone());
+
+                // Queue background sync
+                enqueue_put_study_session(model, &session);
+                return outbox::sync(model);
+            }
+        }
+        StudySessionEvent::StartStudySession(study_session_id, timestamp)
+        | StudySessionEvent::ResumeStudySession(study_session_id, timestamp) => {
+            let session_id = find_parent_session_id(model, &study_session_id).unwrap_or_default();
+            return apply_and_sync(&session_id, "start study", model, |model| {
+                start_study_session(&study_session_id, timestamp, model)
+            });
+        }
+        StudySessionEvent::PauseStudySession(study_session_id, timestamp) => {
+            let session_id = find_parent_session_id(model, &study_session_id).unwrap_or_default();
+            return apply_and_sync(&session_id, "pause study", model, |model| {
+                pause_study_session(&study_session_id, timestamp, model)
+            });
+        }
+        StudySessionEvent::CompleteStudySession(study_session_id, timestamp) => {
+            let session_id = find_parent_session_id(model, &study_session_id).unwrap_or_default();
+            return apply_and_sync(&session_id, "complete study", model, |model| {
+                complete_study_session(&study_session_id, timestamp, model)
+            });
+        }
+        StudySessionEvent::SwitchToStudy {
+            session_id,
+            study_id,
+            timestamp,
+        } => {
+            return apply_and_sync(&session_id, "switch study", model, |model| {
+                switch_to_study(&session_id, &study_id, timestamp, model)
+            });
+        }
+        StudySessionEvent::RecordTempo(study_session_id, bpm, clean) => {
+            let Some(study_session) = model
+                .sessions
+                .iter_mut()
+                .flat_map(|s| s.study_sessions.iter_mut())
+                .find(|s| s.id == study_session_id)
+            else {
+                model.last_error = Some(format!(
+                    "Failed to record tempo: {:?}",
+                    StudySessionError::NotFound
+                ));
+                return crux_core::render::render();
+            };
+            study_session.record_tempo(bpm, clean);
+            let study_session = study_session.clone();
+            enqueue_put_study_session(model, &study_session);
+
+            for goal in complete_goals_at_tempo(&study_session.study_id, model) {
+                outbox::enqueue_put(model, format!("/api/goals/{}", goal.id), &goal);
             }
+            return outbox::sync(model);
         }
     }
 
@@ -141,3 +508,256 @@ fn test_update_study_session() {
     let study_session = session.study_sessions.first().unwrap();
     assert_eq!(study_session.score, Some(8));
 }
+
+#[test]
+fn test_study_session_lifecycle() {
+    let mut study_session = StudySession::new("Study 1".to_string(), "Session 1".to_string());
+    assert_eq!(study_session.elapsed_seconds_at("2025-05-01T12:00:00Z"), 0);
+
+    study_session
+        .start("2025-05-01T12:00:00Z".to_string())
+        .unwrap();
+    assert!(study_session.is_active());
+    assert_eq!(
+        study_session.elapsed_seconds_at("2025-05-01T12:05:00Z"),
+        300
+    );
+
+    study_session
+        .pause("2025-05-01T12:10:00Z".to_string())
+        .unwrap();
+    assert_eq!(
+        study_session.elapsed_seconds_at("2025-05-01T12:30:00Z"),
+        600
+    );
+
+    study_session
+        .resume("2025-05-01T12:20:00Z".to_string())
+        .unwrap();
+    study_session
+        .complete("2025-05-01T12:25:00Z".to_string())
+        .unwrap();
+    assert_eq!(
+        study_session.state,
+        StudySessionState::Completed {
+            start_time: "2025-05-01T12:00:00Z".to_string(),
+            end_time: "2025-05-01T12:25:00Z".to_string(),
+            elapsed_seconds: 900,
+        }
+    );
+
+    // Completed study sessions can't be restarted
+    assert_eq!(
+        study_session.start("2025-05-01T12:30:00Z".to_string()),
+        Err(StudySessionError::AlreadyCompleted)
+    );
+}
+
+#[test]
+fn test_switching_studies_auto_pauses_previous() {
+    let mut model = Model::default();
+    let session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    let session_id = session.id.clone();
+    model.sessions().add(session);
+
+    // Studies can't be started before the session is running
+    assert_eq!(
+        switch_to_study(
+            &session_id,
+            "scales",
+            "2025-05-01T12:00:00Z".to_string(),
+            &mut model
+        ),
+        Err(StudySessionError::SessionNotActive)
+    );
+
+    crate::app::start_session(&session_id, "2025-05-01T12:00:00Z".to_string(), &mut model).unwrap();
+    switch_to_study(
+        &session_id,
+        "scales",
+        "2025-05-01T12:00:00Z".to_string(),
+        &mut model,
+    )
+    .unwrap();
+    switch_to_study(
+        &session_id,
+        "etudes",
+        "2025-05-01T12:15:00Z".to_string(),
+        &mut model,
+    )
+    .unwrap();
+
+    let session = &model.sessions[0];
+    assert_eq!(session.study_sessions.len(), 2);
+    assert!(matches!(
+        session.study_sessions[0].state,
+        StudySessionState::Paused {
+            elapsed_seconds: 900,
+            ..
+        }
+    ));
+    assert_eq!(
+        session.active_study_session().map(|s| s.study_id.as_str()),
+        Some("etudes")
+    );
+
+    // Switching back resumes the existing study session rather than creating one
+    switch_to_study(
+        &session_id,
+        "scales",
+        "2025-05-01T12:45:00Z".to_string(),
+        &mut model,
+    )
+    .unwrap();
+    crate::app::end_session(&session_id, "2025-05-01T13:00:00Z".to_string(), &mut model).unwrap();
+
+    let session = &model.sessions[0];
+    assert_eq!(session.study_sessions.len(), 2);
+    assert!(session.active_study_session_id.is_none());
+    assert!(session.study_sessions.iter().all(|s| !s.is_active()));
+    assert_eq!(
+        session.time_per_study("2025-05-01T13:00:00Z"),
+        vec![("scales".to_string(), 1800), ("etudes".to_string(), 1800)]
+    );
+}
+
+#[test]
+fn test_pausing_session_pauses_active_study() {
+    let mut model = Model::default();
+    let session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    let session_id = session.id.clone();
+    model.sessions().add(session);
+
+    crate::app::start_session(&session_id, "2025-05-01T12:00:00Z".to_string(), &mut model).unwrap();
+    switch_to_study(
+        &session_id,
+        "repertoire",
+        "2025-05-01T12:00:00Z".to_string(),
+        &mut model,
+    )
+    .unwrap();
+
+    crate::app::pause_session(&session_id, "2025-05-01T12:10:00Z".to_string(), &mut model).unwrap();
+    let study = model.sessions[0].active_study_session().unwrap();
+    assert!(matches!(study.state, StudySessionState::Paused { .. }));
+
+    crate::app::resume_session(&session_id, "2025-05-01T12:30:00Z".to_string(), &mut model)
+        .unwrap();
+    let study = model.sessions[0].active_study_session().unwrap();
+    assert!(study.is_active());
+    assert_eq!(study.elapsed_seconds_at("2025-05-01T12:40:00Z"), 1200);
+}
+
+#[test]
+fn test_switching_studies_syncs_changed_study_sessions() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let session = PracticeSession::new(vec!["Goal 1".to_string()], "Intention 1".to_string());
+    let session_id = session.id.clone();
+    model.sessions().add(session);
+    crate::app::start_session(&session_id, "2025-05-01T12:00:00Z".to_string(), &mut model).unwrap();
+    switch_to_study(
+        &session_id,
+        "scales",
+        "2025-05-01T12:00:00Z".to_string(),
+        &mut model,
+    )
+    .unwrap();
+    let scales_id = model.sessions[0].study_sessions[0].id.clone();
+
+    let mut command = handle_event(
+        StudySessionEvent::SwitchToStudy {
+            session_id: session_id.clone(),
+            study_id: "etudes".to_string(),
+            timestamp: "2025-05-01T12:20:00Z".to_string(),
+        },
+        &mut model,
+    );
+
+    // The paused study is updated and the new one is created, in that order
+    let queued: Vec<(crate::OperationMethod, String)> = model
+        .outbox
+        .operations
+        .iter()
+        .map(|op| (op.method, op.path.clone()))
+        .collect();
+    assert_eq!(
+        queued,
+        vec![
+            (
+                crate::OperationMethod::Put,
+                format!("/api/sessions/{session_id}/studies/{scales_id}")
+            ),
+            (
+                crate::OperationMethod::Post,
+                format!("/api/sessions/{session_id}/studies")
+            ),
+        ]
+    );
+
+    // Only the head of the queue is sent until the server acknowledges it
+    let requests: Vec<(String, String)> = command
+        .effects()
+        .filter_map(|effect| match effect {
+            crate::Effect::Http(request) => Some((request.operation.method, request.operation.url)),
+            crate::Effect::Render(_) | crate::Effect::Timer(_) => None,
+        })
+        .collect();
+    assert_eq!(
+        requests,
+        vec![(
+            "PUT".to_string(),
+            format!("https://intrada-server.fly.dev/api/sessions/{session_id}/studies/{scales_id}")
+        )]
+    );
+}
+
+#[test]
+fn test_recording_clean_tempo_completes_goal_at_target() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let mut goal = crate::app::create_custom_test_goal(
+        "Etude at 120",
+        None,
+        crate::app::GoalStatus::InProgress,
+        vec!["etude".to_string()],
+        Some(120),
+    );
+    goal.clean_reps_to_complete = Some(2);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+
+    let mut session = PracticeSession::new(vec![goal_id.clone()], "Tempo".to_string());
+    let session_id = session.id.clone();
+    let study_sessions: Vec<StudySession> = (0..3)
+        .map(|_| StudySession::new("etude".to_string(), session_id.clone()))
+        .collect();
+    for study_session in &study_sessions {
+        session.push_study_session(study_session.clone());
+    }
+    model.sessions().add(session);
+
+    // Fast but not clean, then clean once, neither completes the goal
+    let _ = handle_event(
+        StudySessionEvent::RecordTempo(study_sessions[0].id.clone(), 126, false),
+        &mut model,
+    );
+    let _ = handle_event(
+        StudySessionEvent::RecordTempo(study_sessions[1].id.clone(), 120, true),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].status, crate::app::GoalStatus::InProgress);
+    assert_eq!(model.sessions[0].study_sessions[0].tempo, Some(126));
+
+    let _ = handle_event(
+        StudySessionEvent::RecordTempo(study_sessions[2].id.clone(), 122, true),
+        &mut model,
+    );
+    assert_eq!(model.goals[0].status, crate::app::GoalStatus::Completed);
+    let paths: Vec<&str> = model
+        .outbox
+        .operations
+        .iter()
+        .map(|op| op.path.as_str())
+        .collect();
+    assert_eq!(paths.len(), 4);
+    assert_eq!(paths[3], format!("/api/goals/{goal_id}"));
+}
@@ -0,0 +1,192 @@
+use crate::app::conflict::{self, EntityKind};
+use crate::app::model::Model;
+use crate::app::{PracticeGoal, PracticePlan, PracticeSession, SessionTemplate, Study};
+use crate::HttpResult;
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// An entity deleted on the server
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Tombstone {
+    pub kind: EntityKind,
+    pub id: String,
+    pub deleted_at: String,
+}
+
+/// What changed on the server since a cursor, or everything when there was none
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct Changes {
+    pub goals: Vec<PracticeGoal>,
+    pub studies: Vec<Study>,
+    pub sessions: Vec<PracticeSession>,
+    #[serde(default)]
+    pub plans: Vec<PracticePlan>,
+    #[serde(default)]
+    pub templates: Vec<SessionTemplate>,
+    pub deleted: Vec<Tombstone>,
+    // Sent back as `since` to fetch the changes after this response
+    pub cursor: String,
+}
+
+/// Where the last pull from the server left off. Without one the next pull
+/// fetches everything.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct SyncCursor {
+    pub since: Option<String>,
+}
+
+impl SyncCursor {
+    pub fn path(&self) -> String {
+        match &self.since {
+            Some(since) => {
+                let since: String =
+                    url::form_urlencoded::byte_serialize(since.as_bytes()).collect();
+                format!("/api/changes?since={since}")
+            }
+            None => "/api/changes".to_string(),
+        }
+    }
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum SyncEvent {
+    // Background sync events (internal only)
+    #[serde(skip)]
+    #[facet(skip)]
+    // Boxed, as a full pull carries every kind of entity
+    ChangesFetched(HttpResult<crux_http::Response<Box<Changes>>, crux_http::HttpError>),
+
+    // Shell persistence
+    RestoreCursor(String),
+    // Start over with a full pull, e.g. after the local store was cleared
+    ResetCursor,
+}
+
+pub fn fetch_changes(model: &Model) -> Command<super::Effect, super::Event> {
+    if !model.auth.is_signed_in() {
+        return Command::done();
+    }
+    crate::app::api_get(model, &model.sync_cursor.path(), |response| {
+        super::Event::Sync(SyncEvent::ChangesFetched(response))
+    })
+}
+
+pub fn apply_changes(changes: Changes, model: &mut Model) {
+    conflict::merge_from_server(changes.goals, model);
+    conflict::merge_from_server(changes.studies, model);
+    conflict::merge_from_server(changes.sessions, model);
+    conflict::merge_from_server(changes.plans, model);
+    conflict::merge_from_server(changes.templates, model);
+
+    for tombstone in changes.deleted {
+        match tombstone.kind {
+            EntityKind::Goal => conflict::remove_deleted::<PracticeGoal>(&tombstone.id, model),
+            EntityKind::Study => conflict::remove_deleted::<Study>(&tombstone.id, model),
+            EntityKind::Session => {
+                conflict::remove_deleted::<PracticeSession>(&tombstone.id, model);
+            }
+            EntityKind::Plan => conflict::remove_deleted::<PracticePlan>(&tombstone.id, model),
+            EntityKind::Template => {
+                conflict::remove_deleted::<SessionTemplate>(&tombstone.id, model);
+            }
+        }
+    }
+
+    model.sync_cursor.since = Some(changes.cursor);
+}
+
+pub fn handle_event(event: SyncEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        SyncEvent::ChangesFetched(HttpResult::Ok(mut response)) => {
+            let changes = response.take_body().unwrap();
+            apply_changes(*changes, model);
+        }
+        SyncEvent::ChangesFetched(HttpResult::Err(e)) => {
+            // Silently fail background sync - the cursor is unchanged, so the
+            // next pull picks up the same changes
+            if crate::app::auth::is_unauthorized(&e) {
+                crate::app::auth::expire(model);
+            }
+        }
+        SyncEvent::RestoreCursor(cursor) => {
+            model.sync_cursor.since = Some(cursor);
+        }
+        SyncEvent::ResetCursor => {
+            model.sync_cursor = SyncCursor::default();
+        }
+    }
+
+    crux_core::render::render()
+}
+
+// *************
+// TESTS
+// *************
+
+#[test]
+fn test_cursor_path_encodes_timestamp() {
+    let mut cursor = SyncCursor::default();
+    assert_eq!(cursor.path(), "/api/changes");
+
+    cursor.since = Some("2025-01-01T10:00:00+00:00".to_string());
+    assert_eq!(
+        cursor.path(),
+        "/api/changes?since=2025-01-01T10%3A00%3A00%2B00%3A00"
+    );
+}
+
+#[test]
+fn test_apply_changes_upserts_in_place_and_advances_cursor() {
+    let mut model = Model::default();
+    let scales = PracticeGoal::new("Scales".to_string(), None, None, vec![], None);
+    let etudes = PracticeGoal::new("Etudes".to_string(), None, None, vec![], None);
+    model.goals = vec![scales.clone(), etudes.clone()];
+
+    let mut renamed = scales.clone();
+    renamed.name = "Scales in thirds".to_string();
+    renamed.version = 2;
+    let arpeggios = PracticeGoal::new("Arpeggios".to_string(), None, None, vec![], None);
+
+    apply_changes(
+        Changes {
+            goals: vec![renamed, arpeggios],
+            cursor: "2025-01-01T10:00:00+00:00".to_string(),
+            ..Changes::default()
+        },
+        &mut model,
+    );
+
+    let names: Vec<&str> = model.goals.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, vec!["Scales in thirds", "Etudes", "Arpeggios"]);
+    assert_eq!(
+        model.sync_cursor.since.as_deref(),
+        Some("2025-01-01T10:00:00+00:00")
+    );
+}
+
+#[test]
+fn test_apply_changes_removes_deleted_entities_and_their_queued_edits() {
+    let mut model = Model::default();
+    let study = Study::new("Hanon".to_string(), None);
+    let study_id = study.id.clone();
+    model.studies.push(study.clone());
+    crate::app::outbox::enqueue_put(&mut model, format!("/api/studies/{study_id}"), &study);
+
+    apply_changes(
+        Changes {
+            deleted: vec![Tombstone {
+                kind: EntityKind::Study,
+                id: study_id,
+                deleted_at: "2025-01-01T10:00:00+00:00".to_string(),
+            }],
+            cursor: "2025-01-01T10:00:00+00:00".to_string(),
+            ..Changes::default()
+        },
+        &mut model,
+    );
+
+    assert!(model.studies.is_empty());
+    assert!(model.outbox.operations.is_empty());
+}
@@ -0,0 +1,254 @@
+use crate::app::goal::PracticeGoal;
+use crate::app::plan::local_instant;
+use crate::app::stats::{practiced, to_minutes};
+use crate::app::PracticeSession;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// What a practice target counts
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub enum TargetMeasure {
+    #[default]
+    Minutes,
+    Sessions,
+}
+
+/// The period a practice target resets over. Total runs from the goal's
+/// start date to its target date.
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub enum TargetPeriod {
+    #[default]
+    Day,
+    Week, // weeks start on Monday
+    Total,
+}
+
+/// A structured practice target, e.g. 20 minutes a day, 3 sessions a week
+/// or 600 minutes in total
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct PracticeTarget {
+    pub amount: u32,
+    pub measure: TargetMeasure,
+    pub period: TargetPeriod,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[repr(C)]
+pub enum TargetStatus {
+    #[default]
+    OnTrack,
+    Behind,
+    Met,
+}
+
+/// How a goal is doing against its practice target in the current period
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct TargetProgress {
+    pub goal_id: String,
+    pub target: PracticeTarget,
+    // First day of the current period. None for a total without a start date.
+    pub period_start: Option<String>,
+    // Minutes or sessions, as the target counts
+    pub done: u32,
+    pub remaining: u32,
+    pub status: TargetStatus,
+}
+
+// The current period as [start, end) in local days, the days stats are counted in
+fn period_bounds(
+    goal: &PracticeGoal,
+    period: TargetPeriod,
+    today: NaiveDate,
+) -> (Option<NaiveDate>, Option<NaiveDate>) {
+    let date = |d: &Option<String>| {
+        d.as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d.get(..10)?, "%Y-%m-%d").ok())
+    };
+    match period {
+        TargetPeriod::Day => (Some(today), Some(today + Duration::days(1))),
+        TargetPeriod::Week => {
+            let start = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+            (Some(start), Some(start + Duration::days(7)))
+        }
+        TargetPeriod::Total => (
+            date(&goal.start_date),
+            date(&goal.target_date).map(|d| d + Duration::days(1)),
+        ),
+    }
+}
+
+fn midnight(day: NaiveDate, zone: Tz) -> DateTime<Utc> {
+    local_instant(zone, day.and_hms_opt(0, 0, 0).unwrap_or_default()).unwrap_or_default()
+}
+
+/// Progress against the practice target of every goal that has one, counting
+/// ended sessions for the goal in the current period. A target is behind when
+/// less has been done than an even pace through the period would have by `now`.
+/// Periods start at midnight in `zone`, the user's time zone.
+pub fn target_progress(
+    goals: &[PracticeGoal],
+    sessions: &[PracticeSession],
+    now: DateTime<Utc>,
+    zone: Tz,
+) -> Vec<TargetProgress> {
+    let today = now.with_timezone(&zone).date_naive();
+
+    goals
+        .iter()
+        .filter_map(|goal| {
+            let target = goal.practice_target.clone()?;
+            let (start, end) = period_bounds(goal, target.period, today);
+
+            let in_period: Vec<u32> = sessions
+                .iter()
+                .filter(|s| s.goal_ids.contains(&goal.id))
+                .filter_map(|session| practiced(session, zone))
+                .filter(|p| start.map_or(true, |start| p.day >= start) && p.day <= today)
+                .map(|p| p.seconds)
+                .collect();
+            let done = match target.measure {
+                TargetMeasure::Minutes => to_minutes(in_period.iter().map(|s| u64::from(*s)).sum()),
+                TargetMeasure::Sessions => u32::try_from(in_period.len()).unwrap_or(u32::MAX),
+            };
+
+            let expected = match (start, end) {
+                (Some(start), Some(end)) if end > start => {
+                    let length = (midnight(end, zone) - midnight(start, zone)).num_seconds();
+                    let elapsed = (now - midnight(start, zone)).num_seconds().clamp(0, length);
+                    u64::from(target.amount) * elapsed.unsigned_abs() / length.unsigned_abs()
+                }
+                _ => 0,
+            };
+            let status = if done >= target.amount {
+                TargetStatus::Met
+            } else if u64::from(done) < expected {
+                TargetStatus::Behind
+            } else {
+                TargetStatus::OnTrack
+            };
+
+            Some(TargetProgress {
+                goal_id: goal.id.clone(),
+                period_start: start.map(|d| d.to_string()),
+                done,
+                remaining: target.amount.saturating_sub(done),
+                status,
+                target,
+            })
+        })
+        .collect()
+}
+
+// *************
+// TESTS
+// *************
+
+#[test]
+fn test_daily_minutes_target() {
+    let mut goal = crate::app::create_test_goal("Scales", None);
+    goal.practice_target = Some(PracticeTarget {
+        amount: 20,
+        measure: TargetMeasure::Minutes,
+        period: TargetPeriod::Day,
+    });
+    let sessions = vec![
+        crate::app::create_ended_test_session_at(&[&goal.id], "2025-05-01T09:00:00Z", 5),
+        // Yesterday doesn't count towards today
+        crate::app::create_ended_test_session_at(&[&goal.id], "2025-04-30T09:00:00Z", 30),
+        crate::app::create_ended_test_session_at(&["other"], "2025-05-01T10:00:00Z", 30),
+    ];
+    let goals = [goal];
+
+    // A quarter through the day, 5 of 20 minutes is on pace
+    let progress = target_progress(
+        &goals,
+        &sessions,
+        crate::app::test_time("2025-05-01T06:00:00Z"),
+        Tz::UTC,
+    );
+    assert_eq!(progress[0].done, 5);
+    assert_eq!(progress[0].remaining, 15);
+    assert_eq!(progress[0].period_start.as_deref(), Some("2025-05-01"));
+    assert_eq!(progress[0].status, TargetStatus::OnTrack);
+
+    let progress = target_progress(
+        &goals,
+        &sessions,
+        crate::app::test_time("2025-05-01T18:00:00Z"),
+        Tz::UTC,
+    );
+    assert_eq!(progress[0].status, TargetStatus::Behind);
+}
+
+#[test]
+fn test_weekly_sessions_and_total_targets() {
+    let mut weekly = crate::app::create_test_goal("Etudes", None);
+    weekly.practice_target = Some(PracticeTarget {
+        amount: 3,
+        measure: TargetMeasure::Sessions,
+        period: TargetPeriod::Week,
+    });
+    let mut total = crate::app::create_test_goal("Repertoire", None);
+    total.practice_target = Some(PracticeTarget {
+        amount: 60,
+        measure: TargetMeasure::Minutes,
+        period: TargetPeriod::Total,
+    });
+    let untargeted = crate::app::create_test_goal("Sight reading", None);
+
+    // Thursday 1 May 2025, the week started on Monday 28 April
+    let sessions = vec![
+        crate::app::create_ended_test_session_at(&[&weekly.id], "2025-04-28T09:00:00Z", 10),
+        crate::app::create_ended_test_session_at(&[&weekly.id], "2025-04-29T09:00:00Z", 10),
+        crate::app::create_ended_test_session_at(&[&weekly.id], "2025-05-01T09:00:00Z", 10),
+        crate::app::create_ended_test_session_at(&[&weekly.id], "2025-04-27T09:00:00Z", 10),
+        crate::app::create_ended_test_session_at(&[&total.id], "2025-04-01T09:00:00Z", 45),
+        crate::app::create_ended_test_session_at(&[&total.id], "2025-04-20T09:00:00Z", 15),
+    ];
+
+    let progress = target_progress(
+        &[weekly, total, untargeted],
+        &sessions,
+        crate::app::test_time("2025-05-01T20:00:00Z"),
+        Tz::UTC,
+    );
+    assert_eq!(progress.len(), 2);
+    assert_eq!(progress[0].period_start.as_deref(), Some("2025-04-28"));
+    assert_eq!(progress[0].done, 3);
+    assert_eq!(progress[0].status, TargetStatus::Met);
+    assert_eq!(progress[1].period_start, None);
+    assert_eq!(progress[1].done, 60);
+    assert_eq!(progress[1].remaining, 0);
+    assert_eq!(progress[1].status, TargetStatus::Met);
+}
+
+#[test]
+fn test_daily_target_starts_at_local_midnight() {
+    let mut goal = crate::app::create_test_goal("Scales", None);
+    goal.practice_target = Some(PracticeTarget {
+        amount: 20,
+        measure: TargetMeasure::Minutes,
+        period: TargetPeriod::Day,
+    });
+    // 00:30 on 2 May in Berlin, still 1 May in UTC
+    let sessions = vec![crate::app::create_ended_test_session_at(
+        &[&goal.id],
+        "2025-05-01T22:30:00Z",
+        20,
+    )];
+    let zone: Tz = "Europe/Berlin".parse().unwrap();
+
+    let progress = target_progress(
+        &[goal],
+        &sessions,
+        crate::app::test_time("2025-05-01T23:00:00Z"),
+        zone,
+    );
+    assert_eq!(progress[0].period_start.as_deref(), Some("2025-05-02"));
+    assert_eq!(progress[0].done, 20);
+    assert_eq!(progress[0].status, TargetStatus::Met);
+}
@@ -0,0 +1,259 @@
+use crate::app::model::{format_duration_hms, Model};
+use crate::app::outbox;
+use crate::app::session::{PracticeSession, SessionState};
+use crate::app::study_session::StudySession;
+use crate::app::utils::IdGenerator;
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// One study of a routine and how long to spend on it
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct TemplateBlock {
+    pub study_id: String,
+    pub planned_minutes: u32,
+}
+
+/// A routine: the intention, goals and ordered study blocks a session starts with
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct SessionTemplate {
+    pub id: String,
+    pub name: String,
+    pub intention: String,
+    pub goal_ids: Vec<String>,
+    // Worked through in order; a study may appear more than once
+    pub blocks: Vec<TemplateBlock>,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+#[allow(clippy::enum_variant_names)]
+pub enum TemplateEvent {
+    // Optimistic user actions (all immediate, sync in background)
+    CreateTemplate(SessionTemplate),
+    UpdateTemplate(SessionTemplate),
+    RemoveTemplate(String),
+}
+
+/// The block being worked on in a started session made from a template
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct CurrentBlock {
+    pub study_session_id: String,
+    pub study_id: String,
+    pub position: u32, // 1 based
+    pub block_count: u32,
+    pub planned_minutes: u32,
+    pub time_remaining: String, // e.g. "00:04:12", zero once overrun
+    pub is_overrun: bool,
+}
+
+impl SessionTemplate {
+    pub fn new(name: String, intention: String) -> Self {
+        Self {
+            id: crate::app::generate_id(),
+            name,
+            intention,
+            goal_ids: Vec::new(),
+            blocks: Vec::new(),
+            version: 1,
+            updated_at: None,
+        }
+    }
+
+    /// A NotStarted session with a study session for every block
+    pub fn session(&self, ids: &mut IdGenerator) -> PracticeSession {
+        let mut session = PracticeSession {
+            id: ids.next_id(),
+            ..PracticeSession::new(self.goal_ids.clone(), self.intention.clone())
+        };
+        for block in &self.blocks {
+            let mut study_session = StudySession {
+                id: ids.next_id(),
+                ..StudySession::new(block.study_id.clone(), session.id.clone())
+            };
+            study_session.planned_minutes = Some(block.planned_minutes);
+            session.push_study_session(study_session);
+        }
+        session
+    }
+}
+
+/// The active block of a started session, or the first one not yet completed.
+/// None for sessions that aren't running or have no planned blocks.
+pub fn current_block(session: &PracticeSession, now: &str) -> Option<CurrentBlock> {
+    if !matches!(session.state, SessionState::Started { .. }) {
+        return None;
+    }
+
+    let blocks: Vec<&StudySession> = session
+        .study_sessions
+        .iter()
+        .filter(|s| s.planned_minutes.is_some())
+        .collect();
+    let index = blocks
+        .iter()
+        .position(|s| session.active_study_session_id.as_deref() == Some(s.id.as_str()))
+        .or_else(|| blocks.iter().position(|s| !s.is_completed()))?;
+    let block = blocks[index];
+
+    let planned_minutes = block.planned_minutes.unwrap_or_default();
+    let remaining = i64::from(planned_minutes) * 60 - i64::from(block.elapsed_seconds_at(now));
+    Some(CurrentBlock {
+        study_session_id: block.id.clone(),
+        study_id: block.study_id.clone(),
+        position: index as u32 + 1,
+        block_count: blocks.len() as u32,
+        planned_minutes,
+        time_remaining: format_duration_hms(remaining.max(0)),
+        is_overrun: remaining < 0,
+    })
+}
+
+fn template_request(template: &SessionTemplate) -> serde_json::Value {
+    serde_json::json!({
+        "id": template.id,
+        "name": template.name,
+        "intention": template.intention,
+        "goal_ids": template.goal_ids,
+        "blocks": template.blocks,
+        "version": template.version
+    })
+}
+
+pub fn handle_event(
+    event: TemplateEvent,
+    model: &mut Model,
+) -> Command<super::Effect, super::Event> {
+    match event {
+        // Optimistic user actions (all immediate, sync in background)
+        TemplateEvent::CreateTemplate(template) => {
+            // Apply immediately to local model
+            model.templates.push(template.clone());
+
+            // Queue background sync
+            outbox::enqueue_post(
+                model,
+                "/api/templates".to_string(),
+                &template_request(&template),
+            );
+        }
+        TemplateEvent::UpdateTemplate(template) => {
+            // Apply immediately to local model
+            if let Some(existing) = model.templates.iter_mut().find(|t| t.id == template.id) {
+                *existing = template.clone();
+            }
+
+            // Queue background sync
+            outbox::enqueue_put(
+                model,
+                format!("/api/templates/{}", template.id),
+                &template_request(&template),
+            );
+        }
+        TemplateEvent::RemoveTemplate(template_id) => {
+            // Apply immediately to local model, sessions made from it are kept
+            model.templates.retain(|t| t.id != template_id);
+
+            // Queue background sync
+            outbox::enqueue_delete(model, format!("/api/templates/{template_id}"));
+        }
+    }
+
+    outbox::sync(model)
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn warm_up_template() -> SessionTemplate {
+    let mut template = SessionTemplate::new("Warm up".to_string(), "Loosen up".to_string());
+    template.blocks = vec![
+        TemplateBlock {
+            study_id: "scales".to_string(),
+            planned_minutes: 5,
+        },
+        TemplateBlock {
+            study_id: "arpeggios".to_string(),
+            planned_minutes: 10,
+        },
+    ];
+    template
+}
+
+#[test]
+fn test_template_session_has_a_study_session_per_block() {
+    let template = warm_up_template();
+
+    let session = template.session(&mut IdGenerator::seeded());
+    assert_eq!(session.intention, "Loosen up");
+    assert_eq!(session.state, SessionState::NotStarted);
+    let blocks: Vec<(&str, Option<u32>)> = session
+        .study_sessions
+        .iter()
+        .map(|s| (s.study_id.as_str(), s.planned_minutes))
+        .collect();
+    assert_eq!(blocks, vec![("scales", Some(5)), ("arpeggios", Some(10))]);
+    assert!(session
+        .study_sessions
+        .iter()
+        .all(|s| s.session_id == session.id));
+}
+
+#[test]
+fn test_current_block_follows_the_active_study() {
+    let mut session = warm_up_template().session(&mut IdGenerator::seeded());
+    assert_eq!(current_block(&session, "2025-05-01T18:00:00Z"), None);
+
+    session.start("2025-05-01T18:00:00Z".to_string()).unwrap();
+    let first = current_block(&session, "2025-05-01T18:00:00Z").unwrap();
+    assert_eq!((first.position, first.block_count), (1, 2));
+    assert_eq!(first.time_remaining, "00:05:00");
+
+    let scales_id = session.study_sessions[0].id.clone();
+    session
+        .start_study(&scales_id, "2025-05-01T18:00:00Z".to_string())
+        .unwrap();
+    let overrun = current_block(&session, "2025-05-01T18:06:00Z").unwrap();
+    assert_eq!(overrun.study_id, "scales");
+    assert_eq!(overrun.time_remaining, "00:00:00");
+    assert!(overrun.is_overrun);
+
+    session
+        .complete_study(&scales_id, "2025-05-01T18:06:00Z".to_string())
+        .unwrap();
+    let second = current_block(&session, "2025-05-01T18:07:00Z").unwrap();
+    assert_eq!(second.study_id, "arpeggios");
+    assert_eq!(second.position, 2);
+    assert_eq!(second.time_remaining, "00:10:00");
+    assert!(!second.is_overrun);
+}
+
+#[test]
+fn test_create_from_template_adds_and_sends_session() {
+    let mut model = crate::app::create_signed_in_test_model();
+    model.sessions.clear();
+    let template = warm_up_template();
+    model.templates.push(template.clone());
+
+    let _ = crate::app::session::handle_event(
+        crate::app::SessionEvent::CreateFromTemplate(template.id),
+        &mut model,
+    );
+    assert_eq!(model.sessions.len(), 1);
+    assert_eq!(model.sessions[0].study_sessions.len(), 2);
+    // The session and each of its blocks are sent
+    assert_eq!(model.outbox.operations.len(), 3);
+
+    let _ = crate::app::session::handle_event(
+        crate::app::SessionEvent::CreateFromTemplate("missing".to_string()),
+        &mut model,
+    );
+    assert_eq!(model.sessions.len(), 1);
+    assert!(model.last_error.is_some());
+}
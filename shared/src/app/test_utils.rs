@@ -1,6 +1,6 @@
 use crate::app::{
     goal::PracticeGoal, model::Model, session::PracticeSession, study::Study,
-    study_session::StudySession, GoalStatus,
+    study_session::StudySession, Clock, GoalStatus, IdGenerator, SessionState,
 };
 
 /// Test utilities for creating common test objects and scenarios
@@ -9,6 +9,23 @@ pub fn create_test_model() -> Model {
     Model::default()
 }
 
+/// Creates a test model signed in to the server, so changes are sent
+pub fn create_signed_in_test_model() -> Model {
+    let mut model = Model::default();
+    model.auth.token = Some("test-token".to_string());
+    model
+}
+
+/// Creates a signed in test model whose clock only moves on ticks and whose
+/// ids count up from 1, so views are the same on every run
+pub fn create_deterministic_test_model(now: &str) -> Model {
+    Model {
+        clock: Clock::at(test_time(now)),
+        ids: IdGenerator::seeded(),
+        ..create_signed_in_test_model()
+    }
+}
+
 /// Creates a test model with pre-populated data
 pub fn create_populated_test_model() -> Model {
     let mut model = Model::default();
@@ -104,6 +121,29 @@ pub fn create_ended_test_session(goal_ids: &[&str], intention: &str) -> Practice
     session
 }
 
+/// Creates a test session for some goals that ended `minutes` after `start_time`
+pub fn create_ended_test_session_at(
+    goal_ids: &[&str],
+    start_time: &str,
+    minutes: u32,
+) -> PracticeSession {
+    let mut session = create_test_session(goal_ids, "Practice");
+    let end_time = test_time(start_time) + chrono::Duration::minutes(i64::from(minutes));
+    session.state = SessionState::Ended {
+        start_time: start_time.to_string(),
+        end_time: end_time.to_rfc3339(),
+        duration_in_seconds: minutes * 60,
+    };
+    session
+}
+
+/// Parses an RFC 3339 test time
+pub fn test_time(timestamp: &str) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .expect("test time is RFC 3339")
+        .with_timezone(&chrono::Utc)
+}
+
 /// Helper to add a session to a model and return the session ID
 pub fn add_session_to_model(model: &mut Model, session: PracticeSession) -> String {
     let session_id = session.id.clone();
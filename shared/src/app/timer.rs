@@ -0,0 +1,197 @@
+use crate::app::model::Model;
+use crate::app::session::SessionState;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use crux_core::{capability::Operation, Command};
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+// How often a running session's elapsed time is re-rendered
+const TICK_MILLIS: u32 = 1000;
+
+/// Asks the shell to resolve the request with the current time once
+/// `millis` have passed
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TimerRequest {
+    pub id: u32,
+    pub millis: u32,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TimerResponse {
+    pub id: u32,
+    pub now: String, // RFC 3339
+}
+
+impl Operation for TimerRequest {
+    type Output = TimerResponse;
+}
+
+/// The time updates and the view are computed for. The core never reads the
+/// system clock; only the shell moves this forward, with `Event::Tick` on
+/// launch and `TimerResponse`s to the ticks the core asks for while a session
+/// is running.
+#[derive(Default)]
+pub struct Clock {
+    pub now: DateTime<Utc>,
+    // The user's time zone, which days are counted in
+    pub zone: Tz,
+    // The timer request waiting on the shell, so only one is ever in flight
+    pending: Option<u32>,
+    next_id: u32,
+}
+
+impl Clock {
+    /// A clock stopped at `now` until the shell moves it
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            ..Self::default()
+        }
+    }
+
+    /// Moves the clock to `timestamp`, an RFC 3339 time
+    pub fn set(&mut self, timestamp: &str) -> Result<(), chrono::ParseError> {
+        self.now = DateTime::parse_from_rfc3339(timestamp)?.with_timezone(&Utc);
+        Ok(())
+    }
+
+    /// Counts days in `name`, an IANA time zone such as "Europe/London"
+    pub fn set_zone(&mut self, name: &str) -> Result<(), chrono_tz::ParseError> {
+        self.zone = name.parse()?;
+        Ok(())
+    }
+
+    /// Forgets the timer request `id` once the shell has resolved it
+    pub fn fired(&mut self, id: u32) {
+        if self.pending == Some(id) {
+            self.pending = None;
+        }
+    }
+}
+
+/// Requests the next tick while a session is running, unless one is already
+/// on its way
+pub fn keep_ticking(model: &mut Model) -> Command<super::Effect, super::Event> {
+    let is_running = model
+        .sessions
+        .iter()
+        .any(|s| matches!(s.state, SessionState::Started { .. }));
+    if !is_running || model.clock.pending.is_some() {
+        return Command::done();
+    }
+
+    let id = model.clock.next_id;
+    model.clock.next_id = id.wrapping_add(1);
+    model.clock.pending = Some(id);
+
+    Command::request_from_shell(TimerRequest {
+        id,
+        millis: TICK_MILLIS,
+    })
+    .then_send(|response| super::Event::TimerFired(response.id, response.now))
+}
+
+// *************
+// TESTS
+// *************
+
+#[cfg(test)]
+fn timer_requests(command: &mut Command<super::Effect, super::Event>) -> Vec<TimerRequest> {
+    command
+        .effects()
+        .filter_map(|effect| match effect {
+            crate::Effect::Timer(request) => Some(request.operation),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_ticks_are_requested_one_at_a_time_while_a_session_runs() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let mut session = crate::app::create_test_session(&[], "Scales");
+    assert!(timer_requests(&mut keep_ticking(&mut model)).is_empty());
+
+    session.start("2025-05-01T18:00:00Z".to_string()).unwrap();
+    model.sessions.push(session);
+    let requests = timer_requests(&mut keep_ticking(&mut model));
+    assert_eq!(
+        requests,
+        vec![TimerRequest {
+            id: 0,
+            millis: 1000
+        }]
+    );
+    assert!(timer_requests(&mut keep_ticking(&mut model)).is_empty());
+
+    // A tick from the shell itself leaves the pending request alone
+    model.clock.fired(7);
+    assert!(timer_requests(&mut keep_ticking(&mut model)).is_empty());
+    model.clock.fired(0);
+    let requests = timer_requests(&mut keep_ticking(&mut model));
+    assert_eq!(
+        requests,
+        vec![TimerRequest {
+            id: 1,
+            millis: 1000
+        }]
+    );
+}
+
+#[test]
+fn test_view_elapsed_time_follows_ticks() {
+    use crux_core::App as _;
+
+    let app = crate::Chopin;
+    let mut model = crate::app::create_signed_in_test_model();
+    let mut session = crate::app::create_test_session(&[], "Scales");
+    session.start("2025-05-01T18:00:00Z".to_string()).unwrap();
+    model.sessions.push(session);
+
+    let _ = app.update(
+        crate::Event::Tick("2025-05-01T18:01:05Z".to_string()),
+        &mut model,
+        &(),
+    );
+    assert_eq!(
+        app.view(&model).current_session_elapsed_time.as_deref(),
+        Some("00:01:05")
+    );
+
+    let mut command = app.update(
+        crate::Event::TimerFired(0, "2025-05-01T18:01:06Z".to_string()),
+        &mut model,
+        &(),
+    );
+    assert_eq!(
+        app.view(&model).current_session_elapsed_time.as_deref(),
+        Some("00:01:06")
+    );
+    // The session still runs, so the next tick is asked for
+    assert_eq!(timer_requests(&mut command).len(), 1);
+}
+
+#[test]
+fn test_only_the_shell_moves_the_clock() {
+    let app = crate::Chopin;
+    let mut model = crate::app::create_deterministic_test_model("2025-05-01T18:00:00Z");
+    let mut update = |event| {
+        let _ = crux_core::App::update(&app, event, &mut model, &());
+        model.clock.now
+    };
+
+    let started = crate::app::test_time("2025-05-01T18:00:00Z");
+    assert_eq!(update(super::Event::ClearError), started);
+    assert_eq!(
+        update(super::Event::Tick("2025-05-01T18:00:05Z".to_string())),
+        crate::app::test_time("2025-05-01T18:00:05Z")
+    );
+    assert_eq!(
+        update(super::Event::TimerFired(
+            0,
+            "2025-05-01T18:00:06Z".to_string()
+        )),
+        crate::app::test_time("2025-05-01T18:00:06Z")
+    );
+}
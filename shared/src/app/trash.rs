@@ -0,0 +1,271 @@
+use crate::app::conflict::Versioned;
+use crate::app::history;
+use crate::app::model::Model;
+use crate::app::outbox;
+use crate::app::{
+    DeleteMode, Event, GoalEvent, PracticeGoal, PracticeSession, SessionEvent, Study, StudyEvent,
+};
+use crate::HttpResult;
+use crux_core::Command;
+use facet::Facet;
+use serde::{Deserialize, Serialize};
+
+/// Deleted goals, studies and sessions, kept by the server until they are
+/// purged after its retention period
+#[derive(Facet, Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+pub struct Trash {
+    pub goals: Vec<PracticeGoal>,
+    pub studies: Vec<Study>,
+    pub sessions: Vec<PracticeSession>,
+}
+
+#[derive(Facet, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub enum TrashEvent {
+    FetchTrash,
+    #[serde(skip)]
+    #[facet(skip)]
+    TrashFetched(HttpResult<crux_http::Response<Trash>, crux_http::HttpError>),
+}
+
+/// An entity that can be archived, hidden from lists but kept, or deleted
+/// into the trash; either can be restored
+pub trait Archivable: Versioned {
+    // Where the server keeps them, e.g. "/api/goals"
+    const PATH: &'static str;
+    // For history labels, e.g. "Archive goal"
+    const NAME: &'static str;
+
+    fn archived_at_mut(&mut self) -> &mut Option<String>;
+    fn deleted_at_mut(&mut self) -> &mut Option<String>;
+    fn trashed(trash: &mut Trash) -> &mut Vec<Self>;
+
+    // The events undo and redo replay
+    fn remove_event(id: String) -> Event;
+    fn archive_event(id: String) -> Event;
+    fn restore_event(id: String) -> Event;
+}
+
+impl Archivable for PracticeGoal {
+    const PATH: &'static str = "/api/goals";
+    const NAME: &'static str = "goal";
+
+    fn archived_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.archived_at
+    }
+    fn deleted_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.deleted_at
+    }
+    fn trashed(trash: &mut Trash) -> &mut Vec<Self> {
+        &mut trash.goals
+    }
+
+    fn remove_event(id: String) -> Event {
+        Event::Goal(GoalEvent::RemoveGoal(id, DeleteMode::Detach))
+    }
+    fn archive_event(id: String) -> Event {
+        Event::Goal(GoalEvent::ArchiveGoal(id))
+    }
+    fn restore_event(id: String) -> Event {
+        Event::Goal(GoalEvent::RestoreGoal(id))
+    }
+}
+
+impl Archivable for Study {
+    const PATH: &'static str = "/api/studies";
+    const NAME: &'static str = "study";
+
+    fn archived_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.archived_at
+    }
+    fn deleted_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.deleted_at
+    }
+    fn trashed(trash: &mut Trash) -> &mut Vec<Self> {
+        &mut trash.studies
+    }
+
+    fn remove_event(id: String) -> Event {
+        Event::Study(StudyEvent::RemoveStudy(id, DeleteMode::Detach))
+    }
+    fn archive_event(id: String) -> Event {
+        Event::Study(StudyEvent::ArchiveStudy(id))
+    }
+    fn restore_event(id: String) -> Event {
+        Event::Study(StudyEvent::RestoreStudy(id))
+    }
+}
+
+impl Archivable for PracticeSession {
+    const PATH: &'static str = "/api/sessions";
+    const NAME: &'static str = "session";
+
+    fn archived_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.archived_at
+    }
+    fn deleted_at_mut(&mut self) -> &mut Option<String> {
+        &mut self.deleted_at
+    }
+    fn trashed(trash: &mut Trash) -> &mut Vec<Self> {
+        &mut trash.sessions
+    }
+
+    fn remove_event(id: String) -> Event {
+        Event::Session(SessionEvent::RemoveSession(id))
+    }
+    fn archive_event(id: String) -> Event {
+        Event::Session(SessionEvent::ArchiveSession(id))
+    }
+    fn restore_event(id: String) -> Event {
+        Event::Session(SessionEvent::RestoreSession(id))
+    }
+}
+
+/// Hides an entity from the lists until it is restored
+pub fn archive<T: Archivable>(id: &str, model: &mut Model) -> Command<super::Effect, super::Event> {
+    let now = model.clock.now.to_rfc3339();
+    let Some(entity) = T::collection(model).iter_mut().find(|e| e.id() == id) else {
+        model.last_error = Some(format!("Failed to archive: {id} not found"));
+        return crux_core::render::render();
+    };
+    *entity.archived_at_mut() = Some(now);
+    history::record(
+        model,
+        &format!("Archive {}", T::NAME),
+        T::restore_event(id.to_string()),
+        T::archive_event(id.to_string()),
+    );
+
+    outbox::enqueue_post(
+        model,
+        format!("{}/{id}/archive", T::PATH),
+        &serde_json::json!({}),
+    );
+    outbox::sync(model)
+}
+
+/// Keeps a removed entity in the local trash, so it can be restored before
+/// the server's trash has been fetched
+pub fn move_to_trash<T: Archivable>(mut entity: T, model: &mut Model) {
+    *entity.deleted_at_mut() = Some(model.clock.now.to_rfc3339());
+    T::trashed(&mut model.trash).push(entity);
+}
+
+/// Brings an entity back from the archive or the trash
+pub fn restore<T: Archivable>(id: &str, model: &mut Model) -> Command<super::Effect, super::Event> {
+    // Undone by putting it back where it came from
+    let trashed = T::trashed(&mut model.trash);
+    let undo = if let Some(position) = trashed.iter().position(|e| e.id() == id) {
+        let mut entity = trashed.remove(position);
+        *entity.deleted_at_mut() = None;
+        *entity.archived_at_mut() = None;
+        T::collection(model).push(entity);
+        Some(T::remove_event(id.to_string()))
+    } else if let Some(entity) = T::collection(model).iter_mut().find(|e| e.id() == id) {
+        entity
+            .archived_at_mut()
+            .take()
+            .map(|_| T::archive_event(id.to_string()))
+    } else {
+        model.last_error = Some(format!("Failed to restore: {id} not found"));
+        return crux_core::render::render();
+    };
+    if let Some(undo) = undo {
+        history::record(
+            model,
+            &format!("Restore {}", T::NAME),
+            undo,
+            T::restore_event(id.to_string()),
+        );
+    }
+
+    outbox::enqueue_post(
+        model,
+        format!("{}/{id}/restore", T::PATH),
+        &serde_json::json!({}),
+    );
+    outbox::sync(model)
+}
+
+pub fn handle_event(event: TrashEvent, model: &mut Model) -> Command<super::Effect, super::Event> {
+    match event {
+        TrashEvent::FetchTrash => {
+            return crate::app::api_get(model, "/api/trash", |response| {
+                super::Event::Trash(TrashEvent::TrashFetched(response))
+            });
+        }
+        TrashEvent::TrashFetched(HttpResult::Ok(mut response)) => {
+            model.trash = response.take_body().unwrap_or_default();
+        }
+        TrashEvent::TrashFetched(HttpResult::Err(e)) => {
+            model.last_error = Some(format!("Failed to fetch trash: {e:?}"));
+        }
+    }
+
+    crux_core::render::render()
+}
+
+// *************
+// TESTS
+// *************
+
+#[test]
+fn test_removed_goal_can_be_restored_from_the_trash() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let goal = crate::app::create_test_goal("Scales", None);
+    let goal_id = goal.id.clone();
+    model.goals.push(goal);
+
+    let _ = crate::app::goal::handle_event(
+        crate::app::GoalEvent::RemoveGoal(goal_id.clone(), crate::app::DeleteMode::Detach),
+        &mut model,
+    );
+    assert!(model.goals.is_empty());
+    assert_eq!(model.trash.goals.len(), 1);
+    assert!(model.trash.goals[0].deleted_at.is_some());
+
+    let _ = crate::app::goal::handle_event(
+        crate::app::GoalEvent::RestoreGoal(goal_id.clone()),
+        &mut model,
+    );
+    assert!(model.trash.goals.is_empty());
+    assert_eq!(model.goals[0].deleted_at, None);
+    assert_eq!(
+        model.outbox.operations[1].path,
+        format!("/api/goals/{goal_id}/restore")
+    );
+}
+
+#[test]
+fn test_archived_sessions_are_listed_apart() {
+    let mut model = crate::app::create_signed_in_test_model();
+    let session = crate::app::create_ended_test_session(&[], "Scales");
+    let session_id = session.id.clone();
+    model.sessions.push(session);
+
+    let _ = crate::app::session::handle_event(
+        crate::app::SessionEvent::ArchiveSession(session_id.clone()),
+        &mut model,
+    );
+    let archived_at = Some(model.clock.now.to_rfc3339());
+    assert_eq!(model.sessions[0].archived_at, archived_at);
+    let view = crate::app::ViewModel::new(&model);
+    assert!(view.sessions.is_empty());
+    assert_eq!(view.archived_sessions[0].id, session_id);
+    // Archived practice still counts
+    assert_eq!(view.stats.session_count, 1);
+
+    let _ = crate::app::session::handle_event(
+        crate::app::SessionEvent::RestoreSession(session_id),
+        &mut model,
+    );
+    let view = crate::app::ViewModel::new(&model);
+    assert_eq!(view.sessions.len(), 1);
+    assert!(view.archived_sessions.is_empty());
+
+    let _ = crate::app::session::handle_event(
+        crate::app::SessionEvent::RestoreSession("missing".to_string()),
+        &mut model,
+    );
+    assert!(model.last_error.is_some());
+}
@@ -1,4 +1,4 @@
-use crate::app::{Effect, Event};
+use crate::app::{ApiErrorBody, AppError, Effect, Event};
 use crux_core::Command;
 /// Utility functions for common operations across the application
 use crux_http::HttpError;
@@ -9,6 +9,38 @@ pub fn generate_id() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// Generates the same ID from the same key on every device, for records that
+/// more than one device may create on its own
+pub fn derived_id(key: &str) -> String {
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes()).to_string()
+}
+
+/// Where the core gets ids for what it creates itself, such as queued
+/// operations and sessions made from templates. Seeded generators count up from 1, so
+/// tests and snapshots see the same ids on every run.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum IdGenerator {
+    #[default]
+    Random,
+    Seeded(u128),
+}
+
+impl IdGenerator {
+    pub fn seeded() -> Self {
+        IdGenerator::Seeded(0)
+    }
+
+    pub fn next_id(&mut self) -> String {
+        match self {
+            IdGenerator::Random => generate_id(),
+            IdGenerator::Seeded(last) => {
+                *last += 1;
+                Uuid::from_u128(*last).to_string()
+            }
+        }
+    }
+}
+
 /// Validates that a string is a valid UUID
 pub fn is_valid_id(id: &str) -> bool {
     Uuid::parse_str(id).is_ok()
@@ -23,38 +55,50 @@ pub fn short_id(id: &str) -> String {
     }
 }
 
-/// Centralized HTTP error handling - now dispatches error events
-pub fn handle_http_error(error: HttpError, operation: &str) -> Command<Effect, Event> {
-    let user_friendly_message = match &error {
-        HttpError::Io(_) => {
-            format!("Network error during {operation}. Please check your connection.")
-        }
-        HttpError::Http {
-            code,
-            message: _,
-            body,
-        } => {
+/// Turns a failed request into a typed error, using the server's error body
+/// when it sent one
+pub fn app_error_from_http(error: &HttpError, operation: &str) -> AppError {
+    match error {
+        HttpError::Io(_) | HttpError::Timeout => AppError::Network(format!(
+            "Network error during {operation}. Please check your connection."
+        )),
+        HttpError::Http { code, body, .. } => {
+            let api_error = body
+                .as_deref()
+                .and_then(|b| serde_json::from_slice::<ApiErrorBody>(b).ok());
+            if let Some(api_error) = api_error {
+                return api_error.into();
+            }
+
             let status_code: u16 = (*code).into();
-            if status_code == 404 {
-                format!("Resource not found during {operation}")
-            } else if status_code >= 500 {
-                format!("Server error during {operation}. Please try again later.")
-            } else if status_code >= 400 {
-                let body_text = body
-                    .as_ref()
-                    .and_then(|b| String::from_utf8(b.clone()).ok())
-                    .unwrap_or_else(|| "Invalid request".to_string());
-                format!("Client error during {operation}: {body_text}")
-            } else {
-                format!("HTTP error during {operation}: {code}")
+            match status_code {
+                401 => AppError::Unauthorized(format!("Not signed in during {operation}")),
+                404 => AppError::NotFound(format!("Resource not found during {operation}")),
+                409 => AppError::VersionConflict(format!("Conflicting change during {operation}")),
+                500.. => AppError::Server(format!(
+                    "Server error during {operation}. Please try again later."
+                )),
+                400.. => {
+                    let body_text = body
+                        .as_ref()
+                        .and_then(|b| String::from_utf8(b.clone()).ok())
+                        .unwrap_or_else(|| "Invalid request".to_string());
+                    AppError::Http(format!("Client error during {operation}: {body_text}"))
+                }
+                _ => AppError::Http(format!("HTTP error during {operation}: {code}")),
             }
         }
-        HttpError::Json(_) => format!("Data format error during {operation}"),
-        _ => format!("Unknown error during {operation}"),
-    };
+        HttpError::Json(_) => {
+            AppError::Serialization(format!("Data format error during {operation}"))
+        }
+        _ => AppError::Unknown(format!("Unknown error during {operation}")),
+    }
+}
 
+/// Centralized HTTP error handling - now dispatches error events
+pub fn handle_http_error(error: HttpError, operation: &str) -> Command<Effect, Event> {
     log::error!("HTTP {operation} failed: {error:?}");
-    Command::event(Event::Error(user_friendly_message))
+    Command::event(Event::Failed(app_error_from_http(&error, operation)))
 }
 
 /// Centralized operation result handling
@@ -102,6 +146,17 @@ mod tests {
         assert_eq!(id2.len(), 36);
     }
 
+    #[test]
+    fn test_seeded_ids_repeat_across_runs() {
+        let mut ids = IdGenerator::seeded();
+        let first = ids.next_id();
+        assert_eq!(first, "00000000-0000-0000-0000-000000000001");
+        assert!(is_valid_id(&first));
+        assert_ne!(ids.next_id(), first);
+
+        assert_eq!(IdGenerator::seeded().next_id(), first);
+    }
+
     #[test]
     fn test_is_valid_id() {
         let valid_id = generate_id();
@@ -123,4 +178,59 @@ mod tests {
         let empty = "";
         assert_eq!(short_id(empty), "");
     }
+
+    fn http_error(status: u16, body: Option<&str>) -> HttpError {
+        HttpError::Http {
+            code: status.try_into().unwrap(),
+            message: String::new(),
+            body: body.map(|b| b.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_app_error_from_server_error_body() {
+        let error = http_error(
+            422,
+            Some(
+                r#"{"code":"validation_failed","message":"Invalid sign up",
+                    "details":[{"field":"email","message":"A valid email address is required"}]}"#,
+            ),
+        );
+
+        match app_error_from_http(&error, "sign up") {
+            AppError::Validation(validation) => {
+                assert_eq!(validation.message, "Invalid sign up");
+                assert_eq!(validation.fields.len(), 1);
+                assert_eq!(validation.fields[0].field, "email");
+            }
+            other => panic!("Expected a validation error, got {other:?}"),
+        }
+
+        let error = http_error(
+            404,
+            Some(r#"{"code":"not_found","message":"Goal not found"}"#),
+        );
+        assert_eq!(
+            app_error_from_http(&error, "fetch goal"),
+            AppError::NotFound("Goal not found".to_string())
+        );
+    }
+
+    #[test]
+    fn test_app_error_from_unknown_code_or_missing_body() {
+        let error = http_error(418, Some(r#"{"code":"teapot","message":"I'm a teapot"}"#));
+        assert_eq!(
+            app_error_from_http(&error, "brew"),
+            AppError::Http("I'm a teapot".to_string())
+        );
+
+        assert!(matches!(
+            app_error_from_http(&http_error(503, None), "sync"),
+            AppError::Server(_)
+        ));
+        assert!(matches!(
+            app_error_from_http(&HttpError::Timeout, "sync"),
+            AppError::Network(_)
+        ));
+    }
 }
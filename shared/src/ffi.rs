@@ -11,12 +11,13 @@ pub mod uniffi_ffi {
     };
     use crux_http::protocol::HttpRequest;
 
-    use crate::Chopin;
+    use crate::{Chopin, TimerRequest};
 
     #[effect]
     pub enum Effect {
         Render(RenderOperation),
         Http(HttpRequest),
+        Timer(TimerRequest),
     }
 
     impl From<crate::app::Effect> for Effect {
@@ -24,6 +25,7 @@ pub mod uniffi_ffi {
             match effect {
                 crate::Effect::Render(request) => Effect::Render(request),
                 crate::Effect::Http(request) => Effect::Http(request),
+                crate::Effect::Timer(request) => Effect::Timer(request),
             }
         }
     }
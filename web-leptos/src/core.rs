@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
+use gloo_timers::future::TimeoutFuture;
 use leptos::prelude::{Update as _, WriteSignal};
-use shared::{Chopin, Effect, Event, ViewModel};
+use leptos::task::spawn_local;
+use shared::{Chopin, Effect, Event, TimerResponse, ViewModel};
 
 pub type Core = Arc<shared::Core<Chopin>>;
 
@@ -19,6 +21,26 @@ pub fn process_effect(core: &Core, effect: Effect, render: WriteSignal<ViewModel
         Effect::Http(_) => {
             // Do nothing
         }
+        Effect::Timer(mut request) => {
+            // Fires once the time has passed, carrying out whatever the core
+            // asks for next, e.g. another tick
+            let core = core.clone();
+            spawn_local(async move {
+                TimeoutFuture::new(request.operation.millis).await;
+                let response = TimerResponse {
+                    id: request.operation.id,
+                    now: chrono::Utc::now().to_rfc3339(),
+                };
+                match core.resolve(&mut request, response) {
+                    Ok(effects) => {
+                        for effect in effects {
+                            process_effect(&core, effect, render);
+                        }
+                    }
+                    Err(e) => log::error!("Failed to resolve timer: {e}"),
+                }
+            });
+        }
     }
 }
 
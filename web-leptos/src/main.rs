@@ -25,6 +25,18 @@ pub fn App() -> impl IntoView {
     provide_meta_context();
 
     let global_state = GlobalState::default();
+    // The core never reads the system clock, so it starts from the shell's
+    global_state
+        .core
+        .process_event(Event::Tick(chrono::Utc::now().to_rfc3339()));
+    // Stats count days in the browser's time zone
+    let options = js_sys::Intl::DateTimeFormat::default().resolved_options();
+    if let Some(time_zone) = js_sys::Reflect::get(&options, &"timeZone".into())
+        .ok()
+        .and_then(|zone| zone.as_string())
+    {
+        global_state.core.process_event(Event::TimeZone(time_zone));
+    }
     global_state.core.process_event(Event::FetchAll);
     provide_context(Store::new(global_state));
 